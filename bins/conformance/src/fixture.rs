@@ -0,0 +1,304 @@
+//! State-test fixtures (ethereum/tests `GeneralStateTests` and EEST `state_test` JSON) and running one case.
+//!
+//! A case is one `post.<fork>[i]` entry: the transaction built from `indexes` into the `data`/`gasLimit`/`value`
+//! lists, executed against `pre` under that fork. It passes when every account of the expected post `state`
+//! has the expected code and non-zero storage. Balances and nonces are not compared: the VM executes calls but
+//! doesn't charge fees or bump the sender's nonce. Entries without a post `state` (older fixtures that only carry
+//! a state root) and entries expecting an invalid-transaction exception are skipped.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    panic::{self, AssertUnwindSafe},
+};
+
+use alloy::primitives::{Address, Bytes, U256};
+use evm_core::prelude::*;
+use serde::Deserialize;
+
+/// Intrinsic gas of a transaction, charged before execution starts.
+const TX_BASE_GAS: u64 = 21_000;
+const TX_CREATE_GAS: u64 = 32_000;
+const TX_DATA_ZERO_GAS: u64 = 4;
+const TX_DATA_NON_ZERO_GAS: u64 = 16;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateTest {
+    pub env: Env,
+    pub pre: BTreeMap<Address, FixtureAccount>,
+    pub transaction: FixtureTransaction,
+    pub post: BTreeMap<String, Vec<PostEntry>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Env {
+    #[serde(default)]
+    pub current_coinbase: Address,
+    #[serde(default)]
+    pub current_number: U256,
+    #[serde(default)]
+    pub current_timestamp: U256,
+    #[serde(default)]
+    pub current_gas_limit: U256,
+    #[serde(default)]
+    pub current_base_fee: U256,
+    #[serde(default)]
+    pub current_difficulty: U256,
+    #[serde(default)]
+    pub current_random: Option<U256>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FixtureAccount {
+    #[serde(default)]
+    pub balance: U256,
+    #[serde(default)]
+    pub nonce: U256,
+    #[serde(default)]
+    pub code: Bytes,
+    #[serde(default)]
+    pub storage: BTreeMap<U256, U256>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FixtureTransaction {
+    pub data: Vec<Bytes>,
+    pub gas_limit: Vec<U256>,
+    pub value: Vec<U256>,
+    /// Empty for contract creation.
+    #[serde(default)]
+    pub to: String,
+    /// EEST fixtures name the sender; older fixtures only carry `secretKey`, which this runner can't recover.
+    pub sender: Option<Address>,
+    #[serde(default)]
+    pub nonce: U256,
+    /// Legacy transactions only; EIP-1559 fixtures carry `maxFeePerGas` instead and run with a zero price.
+    #[serde(default)]
+    pub gas_price: U256,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostEntry {
+    pub indexes: Indexes,
+    pub state: Option<BTreeMap<Address, FixtureAccount>>,
+    pub expect_exception: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Indexes {
+    pub data: usize,
+    pub gas: usize,
+    pub value: usize,
+}
+
+/// How one case ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaseResult {
+    Passed,
+    /// First mismatching account or slot.
+    Failed(String),
+    /// The interpreter panicked.
+    Panicked(String),
+    Skipped(&'static str),
+}
+
+impl StateTest {
+    /// Run `post[fork][index]` under `spec`.
+    pub fn run_case(&self, spec: SpecId, entry: &PostEntry) -> CaseResult {
+        if entry.expect_exception.is_some() {
+            return CaseResult::Skipped("expects an invalid transaction");
+        }
+        let Some(expected) = &entry.state else {
+            return CaseResult::Skipped("no post state");
+        };
+        let Some(sender) = self.transaction.sender else {
+            return CaseResult::Skipped("no sender");
+        };
+        let tx = &self.transaction;
+        let (Some(data), Some(gas_limit), Some(value)) = (
+            tx.data.get(entry.indexes.data),
+            tx.gas_limit.get(entry.indexes.gas),
+            tx.value.get(entry.indexes.value),
+        ) else {
+            return CaseResult::Failed("indexes out of range".to_string());
+        };
+        let to = if tx.to.is_empty() {
+            Address::ZERO
+        } else {
+            match tx.to.parse() {
+                Ok(to) => to,
+                Err(_) => return CaseResult::Failed(format!("bad `to`: {}", tx.to)),
+            }
+        };
+
+        let transaction = Transaction {
+            from: sender,
+            to,
+            value: *value,
+            nonce: tx.nonce,
+            data: data.clone(),
+            gas_limit: gas_limit.saturating_sub(U256::from(intrinsic_gas(data, to))),
+            gas_price: tx.gas_price,
+        };
+        let block_env = BlockEnv {
+            number: self.env.current_number,
+            timestamp: self.env.current_timestamp,
+            coinbase: self.env.current_coinbase,
+            difficulty: self
+                .env
+                .current_random
+                .unwrap_or(self.env.current_difficulty),
+            gas_limit: self.env.current_gas_limit,
+            base_fee: self.env.current_base_fee,
+            ..Default::default()
+        };
+        let mut evm = Evm::new(
+            block_env,
+            transaction,
+            Memory::new(),
+            Stack::default(),
+            pre_state(&self.pre),
+        )
+        .with_spec(spec);
+
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| evm.transact_commit())) {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            return CaseResult::Panicked(message);
+        }
+
+        match compare(&evm.storage, expected) {
+            Some(mismatch) => CaseResult::Failed(mismatch),
+            None => CaseResult::Passed,
+        }
+    }
+}
+
+fn intrinsic_gas(data: &Bytes, to: Address) -> u64 {
+    let data_gas: u64 = data
+        .iter()
+        .map(|byte| {
+            if *byte == 0 {
+                TX_DATA_ZERO_GAS
+            } else {
+                TX_DATA_NON_ZERO_GAS
+            }
+        })
+        .sum();
+    let create_gas = if to == Address::ZERO {
+        TX_CREATE_GAS
+    } else {
+        0
+    };
+    TX_BASE_GAS + create_gas + data_gas
+}
+
+fn pre_state(pre: &BTreeMap<Address, FixtureAccount>) -> EvmStorage {
+    let mut storage = EvmStorage::default();
+    for (address, account) in pre {
+        storage.set_code(*address, account.code.to_vec());
+        let entry = storage.data.entry(*address).or_default();
+        entry.info.balance = account.balance;
+        entry.info.nonce = account.nonce;
+        entry.storage = account
+            .storage
+            .iter()
+            .map(|(key, value)| (*key, *value))
+            .collect();
+    }
+    storage.clear_touched();
+    storage
+}
+
+/// The first difference between `state` and the expected post accounts, if any.
+fn compare(state: &EvmStorage, expected: &BTreeMap<Address, FixtureAccount>) -> Option<String> {
+    for (address, account) in expected {
+        if state.code(*address).as_slice() != account.code.as_ref() {
+            return Some(format!("{address}: code differs"));
+        }
+        let actual: HashMap<U256, U256> = state
+            .data
+            .get(address)
+            .map(|evm_account| {
+                evm_account
+                    .storage
+                    .iter()
+                    .filter(|(_, value)| !value.is_zero())
+                    .map(|(key, value)| (*key, *value))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let wanted: HashMap<U256, U256> = account
+            .storage
+            .iter()
+            .filter(|(_, value)| !value.is_zero())
+            .map(|(key, value)| (*key, *value))
+            .collect();
+        if actual != wanted {
+            let slot = wanted
+                .keys()
+                .chain(actual.keys())
+                .find(|key| wanted.get(key) != actual.get(key))
+                .expect("maps differ");
+            return Some(format!(
+                "{address}: slot {slot:#x} is {:#x}, expected {:#x}",
+                actual.get(slot).copied().unwrap_or_default(),
+                wanted.get(slot).copied().unwrap_or_default()
+            ));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `SSTORE(0, CALLVALUE)` on the callee; expects slot 0 = 1.
+    const FIXTURE: &str = r#"{
+        "env": {"currentCoinbase": "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba", "currentNumber": "0x01"},
+        "pre": {
+            "0x1000000000000000000000000000000000000000": {"balance": "0x00", "nonce": "0x01", "code": "0x345f55", "storage": {}},
+            "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b": {"balance": "0x3635c9adc5dea00000", "nonce": "0x00", "code": "0x", "storage": {}}
+        },
+        "transaction": {
+            "data": ["0x"], "gasLimit": ["0x0186a0"], "value": ["0x01", "0x00"],
+            "to": "0x1000000000000000000000000000000000000000",
+            "sender": "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b", "nonce": "0x00"
+        },
+        "post": {
+            "Cancun": [
+                {"indexes": {"data": 0, "gas": 0, "value": 0},
+                 "state": {"0x1000000000000000000000000000000000000000": {"code": "0x345f55", "storage": {"0x00": "0x01"}}}},
+                {"indexes": {"data": 0, "gas": 0, "value": 1},
+                 "state": {"0x1000000000000000000000000000000000000000": {"code": "0x345f55", "storage": {"0x00": "0x01"}}}},
+                {"indexes": {"data": 0, "gas": 0, "value": 0}, "hash": "0x00"}
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn runs_cases_and_compares_post_storage() {
+        let test: StateTest = serde_json::from_str(FIXTURE).unwrap();
+        let entries = &test.post["Cancun"];
+
+        assert_eq!(
+            test.run_case(SpecId::Cancun, &entries[0]),
+            CaseResult::Passed
+        );
+        assert!(matches!(
+            test.run_case(SpecId::Cancun, &entries[1]),
+            CaseResult::Failed(message) if message.contains("slot 0x0 is 0x0, expected 0x1")
+        ));
+        assert_eq!(
+            test.run_case(SpecId::Cancun, &entries[2]),
+            CaseResult::Skipped("no post state")
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! Runs a directory of state-test fixtures per fork and writes a conformance matrix.
+//!
+//! Usage: `cargo run -p conformance --release -- <fixtures-dir> [--forks cancun,shanghai] [--html] [--out FILE]
+//! [--list-failures]`
+//!
+//! Every `*.json` file under the directory (recursively) is read as ethereum/tests `GeneralStateTests` or EEST
+//! `state_test` fixtures; see `fixture` for what a passing case means. The report, markdown by default, has:
+//! - per fork: cases passed, failed, panicked and skipped, and the pass rate of the cases that ran;
+//! - per fork: opcode coverage, i.e. how many of the opcodes active under the fork have a handler;
+//! - the opcodes still missing under the newest fork in the run.
+//!
+//! `--list-failures` also prints every failing or panicking case with its first mismatch to stderr.
+//!
+//! EEST fixtures are generated by the Python execution-specs, so running them is a cross-check against that
+//! reference implementation. `cargo test -p conformance -- --ignored` does so for the directory named by
+//! `EXECUTION_SPEC_FIXTURES` and fails on any panic.
+//!
+//! State tests carry no gas figures, so gas accuracy is visible only indirectly: a case whose outcome depends on
+//! running out of gas fails if the VM's costs differ.
+
+mod fixture;
+mod report;
+
+use std::{
+    collections::BTreeMap,
+    fs, panic,
+    path::{Path, PathBuf},
+    process,
+};
+
+use evm_core::spec::SpecId;
+
+use crate::{
+    fixture::{CaseResult, StateTest},
+    report::{ForkStats, Report},
+};
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: conformance <fixtures-dir> [--forks <fork>,...] [--html] [--out FILE] [--list-failures]"
+    );
+    process::exit(2);
+}
+
+/// Every `*.json` file under `dir`, sorted.
+fn fixture_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        if path.is_dir() {
+            fixture_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+}
+
+/// Run every case under `files` for the forks in `forks` (all known forks when `None`), tallying outcomes per
+/// fork. Each failing or panicking case is passed to `on_failure` as `(fork, test name, case index, message)`.
+/// Returns the stats and the number of files that couldn't be read.
+fn run_fixtures(
+    files: &[PathBuf],
+    forks: Option<&[SpecId]>,
+    mut on_failure: impl FnMut(SpecId, &str, usize, &str),
+) -> (BTreeMap<SpecId, ForkStats>, usize) {
+    let mut stats: BTreeMap<SpecId, ForkStats> = BTreeMap::new();
+    let mut unreadable = 0;
+    for path in files {
+        let tests: BTreeMap<String, StateTest> = match fs::read(path)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|err| err.to_string()))
+        {
+            Ok(tests) => tests,
+            Err(err) => {
+                eprintln!("skipping {}: {err}", path.display());
+                unreadable += 1;
+                continue;
+            }
+        };
+        for (name, test) in &tests {
+            for (fork, entries) in &test.post {
+                let Ok(spec) = fork.parse::<SpecId>() else {
+                    continue;
+                };
+                if forks.is_some_and(|forks| !forks.contains(&spec)) {
+                    continue;
+                }
+                let fork_stats = stats.entry(spec).or_default();
+                for (index, entry) in entries.iter().enumerate() {
+                    match test.run_case(spec, entry) {
+                        CaseResult::Passed => fork_stats.passed += 1,
+                        CaseResult::Failed(message) => {
+                            fork_stats.failed += 1;
+                            on_failure(spec, name, index, &message);
+                        }
+                        CaseResult::Panicked(message) => {
+                            fork_stats.panicked += 1;
+                            on_failure(spec, name, index, &format!("panicked: {message}"));
+                        }
+                        CaseResult::Skipped(_) => fork_stats.skipped += 1,
+                    }
+                }
+            }
+        }
+    }
+    (stats, unreadable)
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut dir = None;
+    let mut forks: Option<Vec<SpecId>> = None;
+    let mut html = false;
+    let mut out = None;
+    let mut list_failures = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--forks" => {
+                let list = args.next().unwrap_or_else(|| usage());
+                let parsed: Result<Vec<SpecId>, String> =
+                    list.split(',').map(|name| name.trim().parse()).collect();
+                forks = Some(parsed.unwrap_or_else(|err| {
+                    eprintln!("error: {err}");
+                    process::exit(2);
+                }));
+            }
+            "--html" => html = true,
+            "--list-failures" => list_failures = true,
+            "--out" => out = Some(args.next().unwrap_or_else(|| usage())),
+            _ if dir.is_none() => dir = Some(PathBuf::from(arg)),
+            _ => usage(),
+        }
+    }
+    let dir = dir.unwrap_or_else(|| usage());
+
+    let mut files = Vec::new();
+    fixture_files(&dir, &mut files);
+    files.sort();
+
+    // Panics are counted in the matrix instead of printed.
+    panic::set_hook(Box::new(|_| {}));
+
+    let (mut stats, unreadable) =
+        run_fixtures(&files, forks.as_deref(), |spec, name, index, message| {
+            if list_failures {
+                eprintln!("{spec:?} {name}[{index}]: {message}");
+            }
+        });
+    for spec in forks.unwrap_or_default() {
+        stats.entry(spec).or_default();
+    }
+
+    let report = Report::new(files.len(), unreadable, stats);
+    let rendered = if html {
+        report.to_html()
+    } else {
+        report.to_markdown()
+    };
+    match out {
+        Some(out) => {
+            if let Err(err) = fs::write(&out, rendered) {
+                eprintln!("error: cannot write {out}: {err}");
+                process::exit(2);
+            }
+        }
+        None => print!("{rendered}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cross-check against the execution-specs: point `EXECUTION_SPEC_FIXTURES` at a directory of EEST
+    /// `state_test` fixtures (e.g. an unpacked `fixtures/state_tests` release).
+    #[test]
+    #[ignore = "needs EXECUTION_SPEC_FIXTURES"]
+    fn execution_spec_fixtures_never_panic() {
+        let dir =
+            std::env::var("EXECUTION_SPEC_FIXTURES").expect("EXECUTION_SPEC_FIXTURES is not set");
+        let mut files = Vec::new();
+        fixture_files(Path::new(&dir), &mut files);
+        assert!(!files.is_empty(), "no fixtures under {dir}");
+
+        let mut panics = Vec::new();
+        let (stats, _) = run_fixtures(&files, None, |spec, name, index, message| {
+            eprintln!("{spec:?} {name}[{index}]: {message}");
+            if message.starts_with("panicked") {
+                panics.push(format!("{spec:?} {name}[{index}]"));
+            }
+        });
+
+        println!("{}", Report::new(files.len(), 0, stats).to_markdown());
+        assert!(panics.is_empty(), "interpreter panicked on: {panics:?}");
+    }
+}
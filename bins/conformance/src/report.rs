@@ -0,0 +1,180 @@
+//! The conformance matrix, rendered as markdown or a standalone HTML page.
+
+use std::collections::BTreeMap;
+
+use evm_core::{jump_tables::implemented_opcodes, opcodes::Opcode, spec::SpecId};
+
+/// Case outcomes under one fork.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ForkStats {
+    pub passed: usize,
+    pub failed: usize,
+    pub panicked: usize,
+    pub skipped: usize,
+}
+
+impl ForkStats {
+    /// Share of the cases that ran (not skipped) which passed, in percent.
+    pub fn pass_rate(&self) -> f64 {
+        let ran = self.passed + self.failed + self.panicked;
+        if ran == 0 {
+            0.0
+        } else {
+            100.0 * self.passed as f64 / ran as f64
+        }
+    }
+}
+
+/// Opcodes active under `spec`, and which of those have a handler.
+fn coverage(spec: SpecId) -> (Vec<Opcode>, Vec<Opcode>) {
+    let implemented = implemented_opcodes();
+    let active: Vec<Opcode> = (0..=255u8)
+        .filter_map(Opcode::from_u8)
+        .filter(|opcode| {
+            opcode
+                .info()
+                .introduced
+                .is_some_and(|introduced| introduced <= spec)
+        })
+        .collect();
+    let missing = active
+        .iter()
+        .copied()
+        .filter(|opcode| !implemented.contains(opcode))
+        .collect();
+    (active, missing)
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    files: usize,
+    unreadable: usize,
+    forks: BTreeMap<SpecId, ForkStats>,
+}
+
+impl Report {
+    pub fn new(files: usize, unreadable: usize, forks: BTreeMap<SpecId, ForkStats>) -> Self {
+        Report {
+            files,
+            unreadable,
+            forks,
+        }
+    }
+
+    /// Header and one row per fork, as plain cells.
+    fn rows(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        let header = vec![
+            "fork",
+            "passed",
+            "failed",
+            "panicked",
+            "skipped",
+            "pass rate",
+            "opcodes",
+        ];
+        let rows = self
+            .forks
+            .iter()
+            .map(|(spec, stats)| {
+                let (active, missing) = coverage(*spec);
+                vec![
+                    format!("{spec:?}"),
+                    stats.passed.to_string(),
+                    stats.failed.to_string(),
+                    stats.panicked.to_string(),
+                    stats.skipped.to_string(),
+                    format!("{:.1}%", stats.pass_rate()),
+                    format!("{}/{}", active.len() - missing.len(), active.len()),
+                ]
+            })
+            .collect();
+        (header, rows)
+    }
+
+    /// Opcodes without a handler under the newest fork in the report.
+    fn missing(&self) -> Vec<&'static str> {
+        self.forks
+            .keys()
+            .next_back()
+            .map(|spec| {
+                coverage(*spec)
+                    .1
+                    .iter()
+                    .map(|opcode| opcode.info().name)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "{} fixture files ({} unreadable).",
+            self.files, self.unreadable
+        )
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let (header, rows) = self.rows();
+        let mut out = format!("# Conformance\n\n{}\n\n", self.summary());
+        out += &format!("| {} |\n", header.join(" | "));
+        out += &format!("|{}\n", "---|".repeat(header.len()));
+        for row in rows {
+            out += &format!("| {} |\n", row.join(" | "));
+        }
+        let missing = self.missing();
+        if !missing.is_empty() {
+            out += &format!("\nMissing opcodes: {}\n", missing.join(", "));
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let (header, rows) = self.rows();
+        let mut out = String::from(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Conformance</title></head><body>\n",
+        );
+        out += &format!(
+            "<h1>Conformance</h1>\n<p>{}</p>\n<table>\n<tr>",
+            self.summary()
+        );
+        for cell in header {
+            out += &format!("<th>{cell}</th>");
+        }
+        out += "</tr>\n";
+        for row in rows {
+            out += "<tr>";
+            for cell in row {
+                out += &format!("<td>{cell}</td>");
+            }
+            out += "</tr>\n";
+        }
+        out += "</table>\n";
+        let missing = self.missing();
+        if !missing.is_empty() {
+            out += &format!("<p>Missing opcodes: {}</p>\n", missing.join(", "));
+        }
+        out += "</body></html>\n";
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_has_a_row_per_fork() {
+        let stats = ForkStats {
+            passed: 3,
+            failed: 1,
+            panicked: 0,
+            skipped: 2,
+        };
+        let report = Report::new(1, 0, BTreeMap::from([(SpecId::Cancun, stats)]));
+        let markdown = report.to_markdown();
+
+        assert!(markdown.contains("| Cancun | 3 | 1 | 0 | 2 | 75.0% |"));
+        assert!(markdown.contains("Missing opcodes:"));
+        assert!(report.to_html().contains("<td>Cancun</td>"));
+    }
+}
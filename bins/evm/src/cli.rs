@@ -0,0 +1,211 @@
+use alloy::primitives::Address;
+use evm_core::{limits::Profile, spec::SpecId};
+
+/// Transaction fields and run modes that can be set from the command line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliArgs {
+    pub sender: Address,
+    pub receiver: Address,
+    /// `--gas-diff <base>,<target>`: run the program under both forks and print a gas comparison.
+    pub gas_diff: Option<(SpecId, SpecId)>,
+    /// `--op-stats <top>`: profile the program and print its `top` most frequent opcode bigrams and trigrams.
+    pub op_stats: Option<usize>,
+    /// `--profile <name>`: the sandbox limits to run under (`strict`, `mainnet` or `permissive`).
+    pub profile: Profile,
+    /// `--json`: print machine-readable JSON instead of text (schema in the README).
+    pub json: bool,
+}
+
+impl Default for CliArgs {
+    fn default() -> Self {
+        CliArgs {
+            sender: Address::with_last_byte(1),
+            receiver: Address::ZERO,
+            gas_diff: None,
+            op_stats: None,
+            profile: Profile::Mainnet,
+            json: false,
+        }
+    }
+}
+
+impl CliArgs {
+    /// Parse `--sender <address>`, `--receiver <address>`, `--gas-diff <fork>,<fork>`, `--op-stats <top>`,
+    /// `--profile <name>` (also `--flag=<value>`) and the `--json` switch.
+    /// Unset flags keep their defaults; unknown flags are an error.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut parsed = CliArgs::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "--json" {
+                parsed.json = true;
+                continue;
+            }
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            if !matches!(
+                flag.as_str(),
+                "--sender" | "--receiver" | "--gas-diff" | "--op-stats" | "--profile"
+            ) {
+                return Err(format!("unknown argument `{flag}`"));
+            }
+            let value = inline
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{flag}: missing value"))?;
+            match flag.as_str() {
+                "--sender" => parsed.sender = parse_address(&flag, &value)?,
+                "--receiver" => parsed.receiver = parse_address(&flag, &value)?,
+                "--op-stats" => {
+                    parsed.op_stats = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("{flag}: `{value}` is not a count"))?,
+                    )
+                }
+                "--profile" => {
+                    parsed.profile = value.parse().map_err(|err| format!("{flag}: {err}"))?
+                }
+                _ => parsed.gas_diff = Some(parse_spec_pair(&flag, &value)?),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// Parse a 20-byte hex address for `flag`.
+/// - Accepts all-lowercase or all-uppercase hex with or without `0x`.
+/// - Mixed-case input is treated as EIP-55 checksummed and rejected if the checksum does not match.
+/// - ENS names are reported as unsupported: there is no resolver to look them up.
+pub fn parse_address(flag: &str, value: &str) -> Result<Address, String> {
+    let value = value.trim();
+    if value.contains('.') {
+        return Err(format!(
+            "{flag}: `{value}` looks like an ENS name; ENS is not resolved, pass a hex address"
+        ));
+    }
+
+    let hex = value.strip_prefix("0x").unwrap_or(value);
+    if hex.len() != 40 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!(
+            "{flag}: `{value}` is not a 20-byte hex address (expected 40 hex digits)"
+        ));
+    }
+
+    let mixed_case =
+        hex.bytes().any(|b| b.is_ascii_lowercase()) && hex.bytes().any(|b| b.is_ascii_uppercase());
+    if mixed_case {
+        return Address::parse_checksummed(format!("0x{hex}"), None)
+            .map_err(|_| format!("{flag}: `{value}` has an invalid EIP-55 checksum"));
+    }
+
+    hex.parse()
+        .map_err(|err| format!("{flag}: `{value}`: {err}"))
+}
+
+/// Parse `<base>,<target>` fork names for `flag`, e.g. `shanghai,cancun`.
+pub fn parse_spec_pair(flag: &str, value: &str) -> Result<(SpecId, SpecId), String> {
+    let (base, target) = value
+        .split_once(',')
+        .ok_or_else(|| format!("{flag}: expected `<base>,<target>`, got `{value}`"))?;
+    let parse = |name: &str| {
+        name.trim()
+            .parse::<SpecId>()
+            .map_err(|err| format!("{flag}: {err}"))
+    };
+    Ok((parse(base)?, parse(target)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECKSUMMED: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn accepts_prefixed_unprefixed_and_checksummed() {
+        let lower = CHECKSUMMED.to_lowercase();
+        let expected: Address = lower.parse().unwrap();
+
+        assert_eq!(parse_address("--sender", &lower), Ok(expected));
+        assert_eq!(parse_address("--sender", &lower[2..]), Ok(expected));
+        assert_eq!(parse_address("--sender", CHECKSUMMED), Ok(expected));
+    }
+
+    #[test]
+    fn rejects_bad_checksum_length_and_ens() {
+        let bad_checksum = CHECKSUMMED.replace("aAe", "AAe");
+        assert!(
+            parse_address("--sender", &bad_checksum)
+                .unwrap_err()
+                .contains("checksum")
+        );
+        assert!(
+            parse_address("--sender", "0x1234")
+                .unwrap_err()
+                .contains("40 hex digits")
+        );
+        assert!(
+            parse_address("--receiver", "vitalik.eth")
+                .unwrap_err()
+                .contains("ENS")
+        );
+    }
+
+    #[test]
+    fn parses_flags() {
+        let parsed = CliArgs::parse(args(&[
+            "--sender",
+            CHECKSUMMED,
+            &format!("--receiver={CHECKSUMMED}"),
+        ]))
+        .unwrap();
+        assert_eq!(parsed.sender, parsed.receiver);
+
+        assert_eq!(CliArgs::parse(args(&[])), Ok(CliArgs::default()));
+        assert!(
+            CliArgs::parse(args(&["--sender"]))
+                .unwrap_err()
+                .contains("missing")
+        );
+        assert_eq!(
+            CliArgs::parse(args(&["--gas-diff", "shanghai,Cancun"]))
+                .unwrap()
+                .gas_diff,
+            Some((SpecId::Shanghai, SpecId::Cancun))
+        );
+        assert!(CliArgs::parse(args(&["--json"])).unwrap().json);
+        assert_eq!(
+            CliArgs::parse(args(&["--op-stats=5"])).unwrap().op_stats,
+            Some(5)
+        );
+        assert_eq!(
+            CliArgs::parse(args(&["--profile", "strict"]))
+                .unwrap()
+                .profile,
+            Profile::Strict
+        );
+        assert!(
+            CliArgs::parse(args(&["--profile=lax"]))
+                .unwrap_err()
+                .contains("unknown profile")
+        );
+        assert!(
+            CliArgs::parse(args(&["--gas-diff=cancun"]))
+                .unwrap_err()
+                .contains("<base>,<target>")
+        );
+        assert!(
+            CliArgs::parse(args(&["--to", CHECKSUMMED]))
+                .unwrap_err()
+                .contains("unknown")
+        );
+    }
+}
@@ -1,7 +1,10 @@
-use std::vec;
+mod cli;
 
-use alloy::primitives::{Address, U256};
-use evm_core::Evm;
+use std::{process, vec};
+
+use alloy::primitives::U256;
+use cli::CliArgs;
+use evm_core::{Evm, gas_diff::gas_diff, op_stats::OpStats};
 use primitives::{
     evm_types::{BlockEnv, EvmStorage, Transaction},
     memory::Memory,
@@ -9,6 +12,14 @@ use primitives::{
 };
 
 fn main() {
+    let args = CliArgs::parse(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("error: {err}");
+        eprintln!(
+            "usage: evm [--sender <address>] [--receiver <address>] [--gas-diff <fork>,<fork>] [--op-stats <top>] [--profile <name>] [--json]"
+        );
+        process::exit(2);
+    });
+
     // for contract deployment
     //
     // This example constructs a tiny EVM bytecode sequence (raw bytes) in `call_data`
@@ -57,38 +68,55 @@ fn main() {
 
     // Block environment and memory initialization
     let block_env = BlockEnv::default();
-    // initialize memory with 1 KiB (1024 bytes) so it has a default size before growth
-    let memory: Memory = Memory::new_with_data(vec![0u8; 1024]);
+    // memory starts empty and grows (32-byte aligned) as MSTORE/MLOAD touch it
+    let memory: Memory = Memory::new();
     let stack = Stack::default();
     let storage = EvmStorage::default();
 
     // Transaction: put our bytecode into `data` so the EVM can load/process it.
     // If you want this to behave like contract creation code, you can set `tx.to` to Address::ZERO
-    // (already the case here) and the VM code that seeds memory from `tx.data` will place these
-    // bytes into memory for execution.
+    // (already the case here) and the VM will load these bytes as the code it executes.
     let tx: Transaction = Transaction {
-        from: Address::from_slice(&[1]),
-        to: Address::ZERO,
+        from: args.sender,
+        to: args.receiver,
         value: U256::ZERO,
         nonce: U256::ZERO,
-        data: call_data.clone(), // transaction payload contains our raw opcodes
+        data: call_data.clone().into(), // transaction payload contains our raw opcodes
         gas_limit: U256::from(100000),
+        gas_price: U256::ZERO,
     };
 
-    // Create the EVM instance with the prepared environment and transaction.
-    let mut evm = Evm::new(block_env, tx, memory, stack, storage);
+    // Create the EVM instance with the prepared environment and transaction, under the `--profile` limits.
+    let mut evm = Evm::new(block_env, tx, memory, stack, storage).with_profile(args.profile);
 
-    // NOTE: this example only constructs the EVM state and populates tx.data.
-    // To actually execute the bytecode you need to call `evm.execute()` or `evm.run()`
-    // depending on how you want to drive execution. Execution will depend on how the
-    // VM is implemented to load the tx.data into memory and interpret it.
-    //
-    // Example: if the VM expects contract code in memory when tx.to == Address::ZERO,
-    // calling `evm.execute()` (or `evm.run()`) should iterate through the opcodes and
-    // perform the pushes, arithmetic, MSTORE/MLOAD, and STOP as described above.
-    //
-    // You can print the transaction data for verification:
-    // println!(\"tx.data = {:x?}\", evm.tx.data);
+    // `--gas-diff base,target`: run the program under both forks and compare gas per opcode.
+    if let Some((base, target)) = args.gas_diff {
+        evm.execute();
+        let report = gas_diff(&evm, base, target);
+        if args.json {
+            println!(
+                "{}",
+                serde_json::to_string(&report).expect("report serializes")
+            );
+        } else {
+            println!("{report}");
+        }
+        return;
+    }
 
-    // println!(\"Hello, world!\");
+    // `--op-stats top`: profile the program's most frequent opcode sequences.
+    if let Some(top) = args.op_stats {
+        evm.execute();
+        let mut stats = OpStats::default();
+        stats.profile(&evm);
+        let report = stats.report(top);
+        if args.json {
+            println!(
+                "{}",
+                serde_json::to_string(&report).expect("report serializes")
+            );
+        } else {
+            print!("{report}");
+        }
+    }
 }
@@ -0,0 +1,238 @@
+//! Renders the gas schedule and opcode availability the interpreter applies under each fork.
+//!
+//! Usage: `cargo run -p gas_schedule -- [--forks cancun,prague] [--csv] [--out FILE]`
+//!
+//! Everything comes from the in-code tables, so the output is what a configured `SpecId` actually produces:
+//! - opcodes: for every defined opcode, whether it has a handler and its static gas under each fork, or `-`
+//!   where the fork hasn't activated it (it traps as invalid there);
+//! - fork-dependent dynamic costs: the EXP byte price, and the cost of a warm SSTORE for the cases net metering
+//!   tells apart, with the clearing refund;
+//! - dynamic costs: the fork-independent per-word, per-byte and access prices handlers charge on top.
+//!
+//! Markdown by default; `--csv` writes the same tables as CSV, separated by a blank line.
+
+use std::{fs, process};
+
+use alloy::primitives::U256;
+use evm_core::{
+    access::{COLD_ACCOUNT_ACCESS_GAS, COLD_SLOAD_GAS, WARM_STORAGE_READ_GAS},
+    gas::{
+        CALL_STIPEND, CALL_VALUE_GAS, CODE_DEPOSIT_GAS, COPY_WORD_GAS, INITCODE_WORD_GAS,
+        KECCAK256_WORD_GAS, LOG_DATA_GAS, LOG_TOPIC_GAS, NEW_ACCOUNT_GAS, exp_byte_gas, memory_gas,
+        sstore_clears_refund, sstore_cost, static_gas,
+    },
+    jump_tables::implemented_opcodes,
+    opcodes::Opcode,
+    spec::SpecId,
+};
+
+fn usage() -> ! {
+    eprintln!("usage: gas_schedule [--forks <fork>,...] [--csv] [--out FILE]");
+    process::exit(2);
+}
+
+/// A titled table of plain cells.
+struct Table {
+    title: &'static str,
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn to_markdown(&self) -> String {
+        let mut out = format!("## {}\n\n", self.title);
+        out += &format!("| {} |\n", self.header.join(" | "));
+        out += &format!("|{}\n", "---|".repeat(self.header.len()));
+        for row in &self.rows {
+            out += &format!("| {} |\n", row.join(" | "));
+        }
+        out
+    }
+
+    fn to_csv(&self) -> String {
+        let mut out = format!("{}\n", self.header.join(","));
+        for row in &self.rows {
+            out += &format!("{}\n", row.join(","));
+        }
+        out
+    }
+}
+
+/// `first` followed by one column per fork.
+fn fork_header(first: &[&str], forks: &[SpecId]) -> Vec<String> {
+    first
+        .iter()
+        .map(|cell| cell.to_string())
+        .chain(forks.iter().map(|spec| format!("{spec:?}")))
+        .collect()
+}
+
+fn opcode_table(forks: &[SpecId]) -> Table {
+    let implemented = implemented_opcodes();
+    let rows = (0..=255u8)
+        .filter_map(Opcode::from_u8)
+        .map(|opcode| {
+            let info = opcode.info();
+            let mut row = vec![
+                format!("0x{:02x}", opcode as u8),
+                info.name.to_string(),
+                if implemented.contains(&opcode) {
+                    "yes"
+                } else {
+                    "no"
+                }
+                .to_string(),
+            ];
+            row.extend(forks.iter().map(|spec| match info.introduced {
+                Some(introduced) if introduced <= *spec => static_gas(opcode, *spec).to_string(),
+                _ => "-".to_string(),
+            }));
+            row
+        })
+        .collect();
+    Table {
+        title: "Opcodes (static gas)",
+        header: fork_header(&["byte", "opcode", "handler"], forks),
+        rows,
+    }
+}
+
+/// `name` followed by `cost` under each fork.
+fn fork_row(name: &str, forks: &[SpecId], cost: impl Fn(SpecId) -> String) -> Vec<String> {
+    let mut row = vec![name.to_string()];
+    row.extend(forks.iter().map(|spec| cost(*spec)));
+    row
+}
+
+fn fork_costs_table(forks: &[SpecId]) -> Table {
+    let (zero, one, two) = (U256::ZERO, U256::ONE, U256::from(2));
+    // (case, original, present, new)
+    let cases = [
+        ("SSTORE set (0 -> 1)", zero, zero, one),
+        ("SSTORE reset (1 -> 2)", one, one, two),
+        ("SSTORE clear (1 -> 0)", one, one, zero),
+        ("SSTORE no-op (1 -> 1)", one, one, one),
+        ("SSTORE dirty (1, now 2 -> 0)", one, two, zero),
+    ];
+    let mut rows = vec![fork_row("EXP exponent byte", forks, |spec| {
+        exp_byte_gas(spec).to_string()
+    })];
+    rows.extend(cases.iter().map(|(case, original, present, new)| {
+        fork_row(case, forks, |spec| {
+            sstore_cost(spec, *original, *present, *new).to_string()
+        })
+    }));
+    rows.push(fork_row("SSTORE clear refund", forks, |spec| {
+        sstore_clears_refund(spec).to_string()
+    }));
+    Table {
+        title: "Fork-dependent dynamic costs (SSTORE on a warm slot)",
+        header: fork_header(&["cost"], forks),
+        rows,
+    }
+}
+
+fn dynamic_table() -> Table {
+    let costs = [
+        ("memory word (plus words^2 / 512)", memory_gas(1)),
+        ("copied word", COPY_WORD_GAS),
+        ("KECCAK256 word", KECCAK256_WORD_GAS),
+        ("LOG topic", LOG_TOPIC_GAS),
+        ("LOG data byte", LOG_DATA_GAS),
+        ("CALL value transfer", CALL_VALUE_GAS),
+        ("CALL to new account", NEW_ACCOUNT_GAS),
+        ("CALL stipend", CALL_STIPEND),
+        ("code deposit byte", CODE_DEPOSIT_GAS),
+        ("init code word", INITCODE_WORD_GAS),
+        ("cold account access", COLD_ACCOUNT_ACCESS_GAS),
+        ("cold SLOAD", COLD_SLOAD_GAS),
+        ("warm storage read", WARM_STORAGE_READ_GAS),
+    ];
+    Table {
+        title: "Dynamic costs",
+        header: vec!["cost".to_string(), "gas".to_string()],
+        rows: costs
+            .iter()
+            .map(|(name, gas)| vec![name.to_string(), gas.to_string()])
+            .collect(),
+    }
+}
+
+fn tables(forks: &[SpecId]) -> [Table; 3] {
+    [
+        opcode_table(forks),
+        fork_costs_table(forks),
+        dynamic_table(),
+    ]
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut forks = SpecId::ALL.to_vec();
+    let mut csv = false;
+    let mut out = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--forks" => {
+                let list = args.next().unwrap_or_else(|| usage());
+                let parsed: Result<Vec<SpecId>, String> =
+                    list.split(',').map(|name| name.trim().parse()).collect();
+                forks = parsed.unwrap_or_else(|err| {
+                    eprintln!("error: {err}");
+                    process::exit(2);
+                });
+            }
+            "--csv" => csv = true,
+            "--out" => out = Some(args.next().unwrap_or_else(|| usage())),
+            _ => usage(),
+        }
+    }
+
+    let tables = tables(&forks);
+    let rendered = if csv {
+        tables
+            .iter()
+            .map(Table::to_csv)
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        let body: Vec<String> = tables.iter().map(Table::to_markdown).collect();
+        format!("# Gas schedule\n\n{}", body.join("\n"))
+    };
+    match out {
+        Some(out) => {
+            if let Err(err) = fs::write(&out, rendered) {
+                eprintln!("error: cannot write {out}: {err}");
+                process::exit(2);
+            }
+        }
+        None => print!("{rendered}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_follow_the_forks() {
+        let [opcodes, costs, _] = tables(&[SpecId::Istanbul, SpecId::Shanghai]);
+
+        assert_eq!(
+            opcodes.header,
+            ["byte", "opcode", "handler", "Istanbul", "Shanghai"]
+        );
+        let push0 = opcodes.rows.iter().find(|row| row[1] == "PUSH0").unwrap();
+        assert_eq!(push0[3..], ["-", "2"]);
+        assert!(opcodes.to_csv().contains("0x01,ADD,yes,3,3\n"));
+        assert!(opcodes.to_csv().contains("0x54,SLOAD,yes,800,100\n"));
+
+        let refund = costs.rows.last().unwrap();
+        assert_eq!(refund[1..], ["15000", "4800"]);
+        assert!(
+            costs
+                .to_markdown()
+                .contains("| SSTORE set (0 -> 1) | 20000 | 20000 |")
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! Replays a directory of recorded inputs against the interpreter in parallel and prints a pass/fail matrix.
+//!
+//! Usage: `cargo run -p replay --release -- <dir> [--threads N] [--budget N] [--snapshot FILE] [--json]`
+//!
+//! Each file is one input: bytecode either as hex text (optional `0x`, whitespace ignored) or raw bytes.
+//! The code runs as creation code with unlimited gas under an instruction budget. An input fails if the
+//! interpreter panics; traps, out-of-gas and budget exhaustion are normal outcomes and are reported as such.
+//!
+//! `--snapshot FILE` seeds the state every input runs against from recorded RPC responses
+//! (`EvmStorage::import_rpc_snapshot`), so inputs that read chain state replay offline.
+//!
+//! `--json` prints one JSON document (`JsonReport`, schema in the README) instead of the text matrix.
+
+use std::{
+    fs, panic,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use alloy::primitives::hex;
+use evm_core::prelude::*;
+use serde::Serialize;
+
+const DEFAULT_BUDGET: u64 = 1_000_000;
+
+struct Outcome {
+    path: PathBuf,
+    result: Result<ProgramExitStatus, String>,
+    elapsed: Duration,
+}
+
+/// `--json` output: every input in file-name order, then the totals.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonReport {
+    inputs: Vec<JsonOutcome>,
+    passed: usize,
+    failed: usize,
+    threads: usize,
+    elapsed_micros: u128,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonOutcome {
+    file: String,
+    passed: bool,
+    /// Exit status (`Debug` form) for passing inputs, the panic message for failing ones.
+    detail: String,
+    elapsed_micros: u128,
+}
+
+impl Outcome {
+    fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn detail(&self) -> String {
+        match &self.result {
+            Ok(status) => format!("{status:?}"),
+            Err(message) => message.clone(),
+        }
+    }
+}
+
+/// Decode an input file: hex text if it parses as such, raw bytes otherwise.
+fn decode(bytes: Vec<u8>) -> Vec<u8> {
+    let Ok(text) = std::str::from_utf8(&bytes) else {
+        return bytes;
+    };
+    let compact: String = text.split_whitespace().collect();
+    hex::decode(compact.strip_prefix("0x").unwrap_or(&compact)).unwrap_or(bytes)
+}
+
+fn replay(path: &Path, budget: u64, state: &EvmStorage) -> Outcome {
+    let start = Instant::now();
+    let result = fs::read(path)
+        .map_err(|err| format!("read error: {err}"))
+        .and_then(|bytes| {
+            let code = decode(bytes);
+            let memory = Memory::new();
+            let tx = Transaction {
+                data: code.into(),
+                ..Default::default()
+            };
+            let mut evm = Evm::new(
+                BlockEnv::default(),
+                tx,
+                memory,
+                Stack::default(),
+                state.clone(),
+            )
+            .with_gas_limit(None)
+            .with_instruction_budget(budget);
+
+            evm.execute();
+            evm.run_catching();
+            match evm.status {
+                ProgramExitStatus::InternalError(message) => Err(format!("panic: {message}")),
+                status => Ok(status),
+            }
+        });
+
+    Outcome {
+        path: path.to_path_buf(),
+        result,
+        elapsed: start.elapsed(),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("usage: replay <dir> [--threads N] [--budget N] [--snapshot FILE] [--json]");
+    process::exit(2);
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut dir = None;
+    let mut threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut budget = DEFAULT_BUDGET;
+    let mut json = false;
+    let mut snapshot = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threads" => {
+                threads = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or_else(|| usage())
+            }
+            "--budget" => {
+                budget = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or_else(|| usage())
+            }
+            "--snapshot" => snapshot = Some(args.next().unwrap_or_else(|| usage())),
+            "--json" => json = true,
+            _ if dir.is_none() => dir = Some(PathBuf::from(arg)),
+            _ => usage(),
+        }
+    }
+    let dir = dir.unwrap_or_else(|| usage());
+
+    let mut state = EvmStorage::default();
+    if let Some(snapshot) = snapshot {
+        match state.import_rpc_snapshot(&snapshot) {
+            Ok(summary) => eprintln!(
+                "seeded {} accounts ({} code, {} slots) from {snapshot}",
+                summary.accounts, summary.codes, summary.slots
+            ),
+            Err(err) => {
+                eprintln!("error: cannot import {snapshot}: {err}");
+                process::exit(2);
+            }
+        }
+    }
+
+    let mut inputs: Vec<PathBuf> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .collect(),
+        Err(err) => {
+            eprintln!("error: cannot read {}: {err}", dir.display());
+            process::exit(2);
+        }
+    };
+    inputs.sort();
+
+    // Silence the default hook; panics are reported in the matrix instead.
+    panic::set_hook(Box::new(|_| {}));
+
+    let started = Instant::now();
+    let next = AtomicUsize::new(0);
+    let mut outcomes: Vec<Outcome> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.max(1))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    while let Some(path) = inputs.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        done.push(replay(path, budget, &state));
+                    }
+                    done
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("replay worker panicked"))
+            .collect()
+    });
+    outcomes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let failed = outcomes.iter().filter(|o| o.result.is_err()).count();
+    if json {
+        let report = JsonReport {
+            inputs: outcomes
+                .iter()
+                .map(|outcome| JsonOutcome {
+                    file: outcome.file_name(),
+                    passed: outcome.result.is_ok(),
+                    detail: outcome.detail(),
+                    elapsed_micros: outcome.elapsed.as_micros(),
+                })
+                .collect(),
+            passed: outcomes.len() - failed,
+            failed,
+            threads,
+            elapsed_micros: started.elapsed().as_micros(),
+        };
+        println!(
+            "{}",
+            serde_json::to_string(&report).expect("report serializes")
+        );
+    } else {
+        for outcome in &outcomes {
+            let mark = if outcome.result.is_ok() {
+                "pass"
+            } else {
+                "FAIL"
+            };
+            println!(
+                "{mark}  {:>10.2?}  {:<40} {}",
+                outcome.elapsed,
+                outcome.file_name(),
+                outcome.detail()
+            );
+        }
+        println!(
+            "{} inputs, {} passed, {failed} failed in {:.2?} on {threads} threads",
+            outcomes.len(),
+            outcomes.len() - failed,
+            started.elapsed()
+        );
+    }
+
+    if failed > 0 {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_hex_text_and_falls_back_to_raw() {
+        assert_eq!(
+            decode(b"0x6001\n6002 01".to_vec()),
+            vec![0x60, 0x01, 0x60, 0x02, 0x01]
+        );
+        assert_eq!(decode(vec![0x60, 0xff]), vec![0x60, 0xff]);
+    }
+}
@@ -0,0 +1,39 @@
+//! Plugs an extra handler into the jump table for a byte the interpreter leaves undefined.
+//! `Evm::step` always dispatches through `build_jump_table()`, so this example runs its own
+//! dispatch loop over a patched copy of the table.
+//!
+//! Run with `cargo run -p evm_core --example custom_opcode`.
+
+use alloy::primitives::U256;
+use evm_core::{jump_tables::build_jump_table, prelude::*};
+
+/// Custom `0x0C`: pop `a`, push `a * a`.
+const SQUARE: u8 = 0x0C;
+
+fn square(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let a = evm.stack.try_pop()?;
+    evm.stack.push(a.wrapping_mul(a))
+}
+
+fn main() {
+    let mut jump_table = build_jump_table();
+    jump_table[SQUARE as usize] = square;
+
+    // PUSH1 12; SQUARE; STOP
+    let mut evm = Evm {
+        code: Bytecode::new(vec![0x60, 0x0c, SQUARE, 0x00]),
+        ..Default::default()
+    };
+
+    loop {
+        let byte = evm.code.as_slice()[evm.pc];
+        if byte == 0x00 {
+            break;
+        }
+        evm.pc += 1;
+        jump_table[byte as usize](&mut evm).expect("handler failed");
+    }
+
+    assert_eq!(evm.stack.as_slice(), &[U256::from(144)]);
+    println!("12 squared = {}", evm.stack.as_slice()[0]);
+}
@@ -0,0 +1,70 @@
+//! Deploys a contract with a creation transaction, then calls it at the address the creation derived
+//! from the sender's nonce.
+//!
+//! Run with `cargo run -p evm_core --example deploy_and_call`.
+
+use alloy::primitives::{Address, U256};
+use evm_core::Evm;
+use primitives::{
+    evm_types::{BlockEnv, EvmStorage, Transaction},
+    memory::Memory,
+    stack::Stack,
+};
+
+/// Runtime code: `PUSH1 6; PUSH1 7; ADD; PUSH1 0; MSTORE; PUSH1 32; PUSH1 0; RETURN`, returning 13 as a word.
+const RUNTIME: [u8; 13] = [
+    0x60, 0x06, 0x60, 0x07, 0x01, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xF3,
+];
+
+/// Init code that copies `RUNTIME` (appended right after it) into memory and returns it to be deployed:
+/// `PUSH1 13; PUSH1 12; PUSH1 0; CODECOPY; PUSH1 13; PUSH1 0; RETURN`.
+const INIT: [u8; 12] = [
+    0x60, 0x0d, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, 0x0d, 0x60, 0x00, 0xF3,
+];
+
+fn evm(tx: Transaction, storage: EvmStorage) -> Evm {
+    Evm::new(
+        BlockEnv::default(),
+        tx,
+        Memory::new(),
+        Stack::default(),
+        storage,
+    )
+}
+
+fn main() {
+    let sender = Address::with_last_byte(0x01);
+
+    // Creation: `tx.to == Address::ZERO`, so `tx.data` runs as init code and what it returns becomes the code
+    // of `sender.create(nonce)`.
+    let deploy = Transaction {
+        from: sender,
+        data: [INIT.as_slice(), RUNTIME.as_slice()].concat().into(),
+        gas_limit: U256::from(100_000),
+        ..Default::default()
+    };
+    let mut creation = evm(deploy, EvmStorage::default());
+    let result = creation.transact_commit();
+    let contract = sender.create(0);
+    println!(
+        "creation: {:?}, deployed {} bytes at {contract}",
+        result.status,
+        creation.storage.code(contract).len()
+    );
+
+    // Call: the deployed code is loaded from `tx.to` in the state the creation left behind.
+    let call = Transaction {
+        from: sender,
+        to: contract,
+        nonce: U256::ONE,
+        gas_limit: U256::from(100_000),
+        ..Default::default()
+    };
+    let mut call = evm(call, creation.storage.clone().into_inner());
+    let result = call.transact();
+    println!(
+        "call:     {:?}, returned {}",
+        result.status,
+        U256::from_be_slice(call.status.output())
+    );
+}
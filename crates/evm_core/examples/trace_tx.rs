@@ -0,0 +1,33 @@
+//! Prints an instruction-level trace with `Evm::run_traced`: once through a callback as a table,
+//! then as JSON lines written to stdout.
+//!
+//! Run with `cargo run -p evm_core --example trace_tx`.
+
+use std::io;
+
+use evm_core::prelude::*;
+
+fn program() -> Evm {
+    // PUSH1 6; PUSH1 7; ADD; PUSH1 0x40; MSTORE; STOP
+    let code = vec![0x60, 0x06, 0x60, 0x07, 0x01, 0x60, 0x40, 0x52, 0x00];
+    Evm {
+        code: Bytecode::new(code),
+        memory: Memory::new_with_data(vec![0u8; 128]),
+        ..Default::default()
+    }
+}
+
+fn main() -> io::Result<()> {
+    let mut evm = program();
+    let mut table = Tracer::new(TraceSink::Callback(Box::new(|step: &TraceStep| {
+        println!(
+            "{:>4}  {:<8} cost={:<3} stack={:?}",
+            step.pc, step.op_name, step.gas_cost, step.stack
+        );
+    })));
+    evm.run_traced(&mut table)?;
+    println!("exit: {:?}, gas used: {}\n", evm.status, evm.gas.spent());
+
+    let mut json = Tracer::new(TraceSink::Writer(Box::new(io::stdout())));
+    program().run_traced(&mut json)
+}
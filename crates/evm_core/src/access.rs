@@ -0,0 +1,141 @@
+//! Warm/cold access tracking (EIP-2929, since Berlin).
+//!
+//! The first touch of an account or a storage slot in a transaction is "cold" and costs extra; later touches are
+//! "warm". `Evm::accessed` holds what has been touched. `execute()` starts it with the transaction's sender and
+//! target, the precompiles and (since Shanghai, EIP-3651) the coinbase. A callee that fails or reverts forgets
+//! what it warmed, like its state changes (see `call_frame`).
+//!
+//! The static base the jump table charges for SLOAD, BALANCE and the CALL family is the warm price; handlers
+//! charge the cold surcharge on top.
+
+use std::collections::{HashMap, HashSet};
+
+use alloy::primitives::{Address, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::{Evm, precompiles::active_precompiles, spec::SpecId};
+
+/// First access to a storage slot in a transaction.
+pub const COLD_SLOAD_GAS: u64 = 2_100;
+
+/// First access to an account in a transaction.
+pub const COLD_ACCOUNT_ACCESS_GAS: u64 = 2_600;
+
+/// Any later access to a slot or account.
+pub const WARM_STORAGE_READ_GAS: u64 = 100;
+
+/// Accounts and storage slots already touched in the current transaction.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccessSet {
+    addresses: HashSet<Address>,
+    slots: HashMap<Address, HashSet<U256>>,
+}
+
+impl AccessSet {
+    /// Mark `address` warm. Returns `true` if it was cold.
+    pub fn warm_address(&mut self, address: Address) -> bool {
+        self.addresses.insert(address)
+    }
+
+    /// Mark slot `key` of `address` warm. Returns `true` if it was cold.
+    pub fn warm_slot(&mut self, address: Address, key: U256) -> bool {
+        self.slots.entry(address).or_default().insert(key)
+    }
+
+    pub fn is_warm_address(&self, address: Address) -> bool {
+        self.addresses.contains(&address)
+    }
+
+    pub fn is_warm_slot(&self, address: Address, key: U256) -> bool {
+        self.slots
+            .get(&address)
+            .is_some_and(|slots| slots.contains(&key))
+    }
+}
+
+impl Evm {
+    /// Start a transaction's access set: everything the transaction touches by definition is warm.
+    pub(crate) fn reset_accessed(&mut self) {
+        self.accessed = AccessSet::default();
+        self.accessed.warm_address(self.tx.from);
+        self.accessed.warm_address(self.tx.to);
+        if self.spec >= SpecId::Shanghai {
+            self.accessed.warm_address(self.block_env.coinbase);
+        }
+        for precompile in active_precompiles(self.spec) {
+            self.accessed.warm_address(precompile);
+        }
+    }
+
+    /// Mark `address` warm and return the surcharge over the warm price the opcode's base covers:
+    /// `COLD_ACCOUNT_ACCESS_GAS - WARM_STORAGE_READ_GAS` if it was cold, else 0. Always 0 before Berlin.
+    pub(crate) fn account_access_surcharge(&mut self, address: Address) -> u64 {
+        if self.spec >= SpecId::Berlin && self.accessed.warm_address(address) {
+            COLD_ACCOUNT_ACCESS_GAS - WARM_STORAGE_READ_GAS
+        } else {
+            0
+        }
+    }
+
+    /// Mark slot `key` of the executing contract warm. Returns `true` if it was cold; always `false` before Berlin.
+    pub(crate) fn slot_was_cold(&mut self, key: U256) -> bool {
+        self.spec >= SpecId::Berlin && self.accessed.warm_slot(self.context.address, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use primitives::bytecode::Bytecode;
+
+    use super::*;
+
+    #[test]
+    fn second_sload_of_a_slot_is_warm() {
+        // PUSH1 1; SLOAD; PUSH1 1; SLOAD; STOP
+        let mut evm = Evm {
+            code: Bytecode::new(vec![0x60, 0x01, 0x54, 0x60, 0x01, 0x54, 0x00]),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(100_000));
+        evm.reset_accessed();
+        evm.run();
+
+        assert_eq!(
+            evm.gas.spent(),
+            3 + COLD_SLOAD_GAS + 3 + WARM_STORAGE_READ_GAS
+        );
+        assert!(evm.accessed.is_warm_slot(Address::ZERO, U256::ONE));
+
+        let mut legacy = Evm {
+            code: Bytecode::new(vec![0x60, 0x01, 0x54, 0x00]),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(100_000))
+        .with_spec(SpecId::Istanbul);
+        legacy.run();
+        // No warm/cold split before Berlin: Istanbul's flat SLOAD price (EIP-1884).
+        assert_eq!(legacy.gas.spent(), 3 + 800);
+    }
+
+    #[test]
+    fn transaction_parties_and_precompiles_start_warm() {
+        let mut evm = Evm::default();
+        evm.tx.from = Address::repeat_byte(1);
+        evm.tx.to = Address::repeat_byte(2);
+        evm.block_env.coinbase = Address::repeat_byte(3);
+        evm.execute();
+
+        for address in [
+            Address::repeat_byte(1),
+            Address::repeat_byte(2),
+            Address::repeat_byte(3),
+            Address::left_padding_from(&[0x0a]),
+        ] {
+            assert_eq!(evm.account_access_surcharge(address), 0);
+        }
+        assert_eq!(
+            evm.account_access_surcharge(Address::repeat_byte(4)),
+            COLD_ACCOUNT_ACCESS_GAS - WARM_STORAGE_READ_GAS
+        );
+    }
+}
@@ -0,0 +1,1019 @@
+//! Nested executions for the CALL and CREATE families.
+//!
+//! The `Evm` fields `context`, `code`, `memory`, `stack`, `pc`, `gas` and `return_data` always describe the
+//! executing frame. A call parks the caller's copies in a `CallFrame` on `Evm::call_stack` and starts the callee
+//! with fresh ones. When the callee halts, `step()` pops the frame, restores the caller and reports the outcome:
+//! a success flag on the caller's stack, the output in `return_data` and in the caller's return buffer, and the
+//! callee's unused gas handed back.
+//!
+//! State changes made by a callee that fails or reverts are undone from a copy of `storage` taken when it started.
+//!
+//! CREATE and CREATE2 run their init code as such a callee. When it succeeds, its output becomes the new
+//! account's code and the caller gets the new address instead of a success flag.
+
+use std::mem;
+
+use alloy::primitives::{Address, Bytes, KECCAK256_EMPTY, U256};
+use primitives::{
+    bytecode::Bytecode, errors::EvmErrors, journal::JournalCheckpoint, memory::Memory,
+    stack::Stack, utils::address_to_word,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Evm, ProgramExitStatus,
+    access::AccessSet,
+    call_inputs::{CallInputs, CallScheme, CreateInputs, CreateScheme},
+    gas::{
+        CALL_STIPEND, CALL_VALUE_GAS, CODE_DEPOSIT_GAS, Gas, INITCODE_WORD_GAS, KECCAK256_WORD_GAS,
+        NEW_ACCOUNT_GAS, all_but_one_64th,
+    },
+    operations::ariths::memory_access,
+    precompiles::precompile,
+    spec::SpecId,
+};
+
+/// Most frames that can be suspended below the executing one: calls at depth 1024 fail.
+pub const CALL_DEPTH_LIMIT: usize = 1024;
+
+/// Largest runtime code a CREATE may deploy (EIP-170, since Spurious Dragon).
+pub const MAX_CODE_SIZE: usize = 0x6000;
+
+/// Largest init code CREATE / CREATE2 accept (EIP-3860, since Shanghai).
+pub const MAX_INITCODE_SIZE: usize = 2 * MAX_CODE_SIZE;
+
+/// Who is executing, on whose behalf, and with what input.
+/// - `caller`: `CALLER`, the account that made this call.
+/// - `address`: `ADDRESS`, the account whose storage and balance the code acts on.
+/// - `code_address`: the account the code was loaded from; differs from `address` under DELEGATECALL/CALLCODE.
+/// - `value`: `CALLVALUE`.
+/// - `calldata`: the call's input.
+/// - `is_static`: inside a STATICCALL; state changes fail.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CallContext {
+    pub caller: Address,
+    pub address: Address,
+    pub code_address: Address,
+    pub value: U256,
+    pub calldata: Bytes,
+    pub is_static: bool,
+}
+
+/// A caller suspended while its callee runs.
+/// - `ret_offset` / `ret_size`: where the callee's output is copied in the caller's memory.
+/// - `storage_checkpoint` / `access_checkpoint`: state journal position and warm set when the callee started,
+///   restored if it fails or reverts.
+/// - `log_checkpoint`: length of `Evm::logs` when the callee started; its logs past that are dropped if it fails
+///   or reverts.
+/// - `created`: for CREATE / CREATE2, the address the init code is deploying to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallFrame {
+    pub context: CallContext,
+    pub code: Bytecode,
+    pub memory: Memory,
+    pub stack: Stack,
+    pub pc: usize,
+    pub gas: Gas,
+    pub return_data: Vec<u8>,
+    pub ret_offset: usize,
+    pub ret_size: usize,
+    pub storage_checkpoint: JournalCheckpoint,
+    pub access_checkpoint: AccessSet,
+    pub log_checkpoint: usize,
+    pub created: Option<Address>,
+}
+
+/// Suspended callers, outermost first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CallStack {
+    frames: Vec<CallFrame>,
+}
+
+impl CallStack {
+    /// Number of suspended frames (0 while the top-level frame runs).
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Suspend `frame`. Returns `Err(EvmErrors::CallTooDeep)` once `CALL_DEPTH_LIMIT` frames are suspended.
+    pub fn push(&mut self, frame: CallFrame) -> Result<(), EvmErrors> {
+        if self.frames.len() >= CALL_DEPTH_LIMIT {
+            return Err(EvmErrors::CallTooDeep);
+        }
+        self.frames.push(frame);
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<CallFrame> {
+        self.frames.pop()
+    }
+
+    pub fn frames(&self) -> &[CallFrame] {
+        &self.frames
+    }
+}
+
+impl ProgramExitStatus {
+    /// Whether this status ends the executing frame (as opposed to still running, or stopping the whole
+    /// interpreter, like an exhausted instruction budget).
+    pub(crate) fn ends_frame(&self) -> bool {
+        matches!(
+            self,
+            ProgramExitStatus::Success { .. }
+                | ProgramExitStatus::Revert { .. }
+                | ProgramExitStatus::Halt(_)
+        )
+    }
+}
+
+impl Evm {
+    /// Depth of the executing frame: 1 for the top-level frame.
+    pub fn depth(&self) -> usize {
+        self.call_stack.len() + 1
+    }
+
+    /// Start the call described by `inputs` (already popped from the stack).
+    ///
+    /// - Charges memory expansion for the argument and return ranges, the cold-account surcharge for a target not
+    ///   yet accessed, `CALL_VALUE_GAS` (and `NEW_ACCOUNT_GAS` for a CALL creating its target) when value moves,
+    ///   then forwards at most all but one 64th of what is left.
+    /// - A call that can't start (depth limit, value over the balance) pushes 0 and gives the forwarded gas back.
+    /// - Otherwise the caller is suspended and the callee runs from `pc` 0 on the next `step()`, or, for a
+    ///   precompile's address, runs the precompile and ends right away (see `precompiles`).
+    pub(crate) fn begin_call(&mut self, inputs: CallInputs) -> Result<(), EvmErrors> {
+        let transfers_value = inputs.scheme.has_value() && !inputs.value.is_zero();
+        if self.context.is_static && inputs.scheme == CallScheme::Call && transfers_value {
+            return Err(EvmErrors::StateChangeDuringStaticCall);
+        }
+
+        let args_len: usize = inputs.args_size.saturating_to();
+        let ret_size: usize = inputs.ret_size.saturating_to();
+        let args_offset = memory_access(self, inputs.args_offset, args_len)?;
+        let ret_offset = memory_access(self, inputs.ret_offset, ret_size)?;
+
+        let mut cost = self.account_access_surcharge(inputs.target);
+        if transfers_value {
+            cost += CALL_VALUE_GAS;
+            if inputs.scheme == CallScheme::Call && !self.storage.data.contains_key(&inputs.target)
+            {
+                cost += NEW_ACCOUNT_GAS;
+            }
+        }
+        if !self.gas.record_cost(cost) {
+            return Err(EvmErrors::OutOfGas);
+        }
+        let forwarded = all_but_one_64th(self.gas.remaining()).min(inputs.gas.saturating_to());
+        let _ = self.gas.record_cost(forwarded);
+
+        let balance = self
+            .storage
+            .data
+            .get(&self.context.address)
+            .map(|account| account.info.balance)
+            .unwrap_or_default();
+        if self.call_stack.len() >= self.limits.call_depth_limit()
+            || (transfers_value && balance < inputs.value)
+        {
+            self.gas.erase_cost(forwarded);
+            self.return_data.clear();
+            return self.stack.push(U256::ZERO);
+        }
+
+        let calldata = if args_len == 0 {
+            Bytes::new()
+        } else {
+            Bytes::copy_from_slice(&self.memory.data[args_offset..args_offset + args_len])
+        };
+        let context = match inputs.scheme {
+            CallScheme::Call | CallScheme::StaticCall => CallContext {
+                caller: self.context.address,
+                address: inputs.target,
+                code_address: inputs.target,
+                value: inputs.value,
+                calldata,
+                is_static: self.context.is_static || inputs.scheme == CallScheme::StaticCall,
+            },
+            CallScheme::CallCode => CallContext {
+                caller: self.context.address,
+                address: self.context.address,
+                code_address: inputs.target,
+                value: inputs.value,
+                calldata,
+                is_static: self.context.is_static,
+            },
+            CallScheme::DelegateCall => CallContext {
+                caller: self.context.caller,
+                address: self.context.address,
+                code_address: inputs.target,
+                value: self.context.value,
+                calldata,
+                is_static: self.context.is_static,
+            },
+        };
+
+        self.storage.touch(inputs.target);
+        let code = self.storage.code(inputs.target).clone();
+        let stipend = if transfers_value { CALL_STIPEND } else { 0 };
+        self.enter_frame(
+            context,
+            code,
+            forwarded + stipend,
+            ret_offset,
+            ret_size,
+            None,
+        )?;
+
+        if transfers_value && inputs.scheme == CallScheme::Call {
+            let (from, to) = (self.context.caller, self.context.address);
+            self.storage.transfer(from, to, inputs.value);
+        }
+        if let Some(precompile) = precompile(self.context.code_address, self.spec) {
+            self.run_precompile(precompile);
+        }
+
+        Ok(())
+    }
+
+    /// Start the creation described by `inputs` (already popped from the stack).
+    ///
+    /// - Charges memory expansion for the init code, `INITCODE_WORD_GAS` per word since Shanghai and, for
+    ///   CREATE2, `KECCAK256_WORD_GAS` per word for hashing it; then forwards all but one 64th of what is left.
+    /// - The new address is `keccak256(rlp([sender, nonce]))[12..]` for CREATE and
+    ///   `keccak256(0xff ++ sender ++ salt ++ keccak256(init_code))[12..]` for CREATE2 (EIP-1014).
+    /// - A creation that can't start (depth limit, value over the balance) pushes 0 and gives the gas back;
+    ///   one whose address already has code or a nonce pushes 0 and keeps it, but still bumps the sender's nonce.
+    /// - Otherwise the init code runs as a callee from `pc` 0 on the next `step()`; see `end_call`.
+    pub(crate) fn begin_create(&mut self, inputs: CreateInputs) -> Result<(), EvmErrors> {
+        if self.context.is_static {
+            return Err(EvmErrors::StateChangeDuringStaticCall);
+        }
+
+        let len: usize = inputs.size.saturating_to();
+        if self.spec >= SpecId::Shanghai && len > MAX_INITCODE_SIZE {
+            return Err(EvmErrors::InitcodeTooLarge {
+                size: len,
+                limit: MAX_INITCODE_SIZE,
+            });
+        }
+        let words = (len as u64).div_ceil(32);
+        let mut cost = 0;
+        if self.spec >= SpecId::Shanghai {
+            cost += INITCODE_WORD_GAS * words;
+        }
+        if matches!(inputs.scheme, CreateScheme::Create2 { .. }) {
+            cost += KECCAK256_WORD_GAS * words;
+        }
+        if !self.gas.record_cost(cost) {
+            return Err(EvmErrors::OutOfGas);
+        }
+        let offset = memory_access(self, inputs.offset, len)?;
+        let init_code = if len == 0 {
+            Vec::new()
+        } else {
+            self.memory.data[offset..offset + len].to_vec()
+        };
+        let forwarded = all_but_one_64th(self.gas.remaining());
+        let _ = self.gas.record_cost(forwarded);
+
+        let sender = self.context.address;
+        let info = self
+            .storage
+            .data
+            .get(&sender)
+            .map(|account| account.info.clone())
+            .unwrap_or_default();
+        let nonce: u64 = info.nonce.saturating_to();
+        if self.call_stack.len() >= self.limits.call_depth_limit()
+            || info.balance < inputs.value
+            || nonce == u64::MAX
+        {
+            self.gas.erase_cost(forwarded);
+            self.return_data.clear();
+            return self.stack.push(U256::ZERO);
+        }
+
+        let created = match inputs.scheme {
+            CreateScheme::Create => sender.create(nonce),
+            CreateScheme::Create2 { salt } => {
+                sender.create2_from_code(salt.to_be_bytes::<32>(), &init_code)
+            }
+        };
+        self.storage.bump_nonce(sender);
+
+        let collides = self.storage.data.get(&created).is_some_and(|account| {
+            !account.info.nonce.is_zero() || account.info.code_hash != KECCAK256_EMPTY
+        });
+        if collides {
+            self.return_data.clear();
+            return self.stack.push(U256::ZERO);
+        }
+
+        self.storage.touch(created);
+        self.accessed.warm_address(created);
+        let context = CallContext {
+            caller: sender,
+            address: created,
+            code_address: created,
+            value: inputs.value,
+            calldata: Bytes::new(),
+            is_static: false,
+        };
+        self.enter_frame(
+            context,
+            Bytecode::new(init_code),
+            forwarded,
+            0,
+            0,
+            Some(created),
+        )?;
+
+        if self.spec >= SpecId::SpuriousDragon {
+            self.storage.set_nonce(created, U256::ONE);
+        }
+        if !inputs.value.is_zero() {
+            self.storage.transfer(sender, created, inputs.value);
+        }
+
+        Ok(())
+    }
+
+    /// Set up the top-level frame of a creation transaction deploying to `created`: the init code from `tx.data`
+    /// runs as the new account, created with nonce 1 since Spurious Dragon. An address that already has code or
+    /// a nonce halts with `CreateCollision` before anything runs.
+    pub(crate) fn begin_create_transaction(&mut self, created: Address) {
+        self.code = Bytecode::new(self.tx.data.to_vec());
+        let collides = self.storage.data.get(&created).is_some_and(|account| {
+            !account.info.nonce.is_zero() || account.info.code_hash != KECCAK256_EMPTY
+        });
+        if collides {
+            self.exceptional_halt(EvmErrors::CreateCollision { address: created });
+            return;
+        }
+
+        self.storage.touch(created);
+        self.accessed.warm_address(created);
+        if self.spec >= SpecId::SpuriousDragon {
+            self.storage.set_nonce(created, U256::ONE);
+        }
+        self.created = Some(created);
+    }
+
+    /// Suspend the executing frame and make the callee described by `context` and `code` the executing one,
+    /// with `gas` to spend. The storage checkpoint is taken here, before any value moves.
+    fn enter_frame(
+        &mut self,
+        context: CallContext,
+        code: Bytecode,
+        gas: u64,
+        ret_offset: usize,
+        ret_size: usize,
+        created: Option<Address>,
+    ) -> Result<(), EvmErrors> {
+        let frame = CallFrame {
+            context: mem::replace(&mut self.context, context),
+            code: mem::replace(&mut self.code, code),
+            memory: mem::take(&mut self.memory),
+            stack: mem::take(&mut self.stack),
+            pc: mem::replace(&mut self.pc, 0),
+            gas: mem::replace(&mut self.gas, Gas::new(gas)),
+            return_data: mem::take(&mut self.return_data),
+            ret_offset,
+            ret_size,
+            storage_checkpoint: self.storage.checkpoint(),
+            access_checkpoint: self.accessed.clone(),
+            log_checkpoint: self.logs.len(),
+            created,
+        };
+        self.call_stack.push(frame)
+    }
+
+    /// Finish the executing callee (its `status` ends the frame) and resume the caller.
+    ///
+    /// - Success (`STOP`/`RETURN`): state kept, unused gas and refunds go back to the caller, 1 is pushed.
+    /// - `REVERT`: state and logs undone, unused gas goes back, 0 is pushed.
+    /// - Exceptional halt (`Halt`, including out of gas): state and logs undone, the callee's gas is gone, 0 is pushed.
+    ///
+    /// Output of `RETURN`/`REVERT` becomes the caller's `return_data`, and as much of it as fits is copied into
+    /// the return range the caller gave.
+    ///
+    /// A successful creation frame instead stores its output as the new account's code (see `deploy`), pushes
+    /// the new address and leaves `return_data` empty. Output that can't be deployed fails the creation like an
+    /// exceptional halt.
+    pub(crate) fn end_call(&mut self) {
+        let Some(frame) = self.call_stack.pop() else {
+            return;
+        };
+        let status = mem::take(&mut self.status);
+
+        // Success and Revert hand the callee's unused gas back; exceptional halts (failure, out of gas) burn it.
+        let (mut success, mut gas_back, output) = match status {
+            ProgramExitStatus::Success { output, .. } => (true, true, output),
+            ProgramExitStatus::Revert { output } => (false, true, output),
+            _ => (false, false, Bytes::new()),
+        };
+        if let Some(created) = frame.created
+            && success
+            && self.deploy(created, &output).is_err()
+        {
+            (success, gas_back) = (false, false);
+        }
+        let callee_gas = self.gas;
+
+        self.context = frame.context;
+        self.code = frame.code;
+        self.memory = frame.memory;
+        self.stack = frame.stack;
+        self.pc = frame.pc;
+        self.gas = frame.gas;
+
+        if !success {
+            self.storage.revert_to(frame.storage_checkpoint);
+            self.accessed = frame.access_checkpoint;
+            self.logs.truncate(frame.log_checkpoint);
+        }
+        if gas_back {
+            self.gas.erase_cost(callee_gas.remaining());
+        }
+        if success {
+            self.gas.record_refund(callee_gas.refunded());
+        }
+
+        // A zero-length return range is never bounds-checked (see `memory_access`), so its offset may lie past
+        // the end of memory.
+        let copied = output.len().min(frame.ret_size);
+        if copied > 0 {
+            self.memory.data[frame.ret_offset..frame.ret_offset + copied]
+                .copy_from_slice(&output[..copied]);
+        }
+        self.return_data = match frame.created {
+            Some(_) if success => Vec::new(),
+            _ => output.to_vec(),
+        };
+
+        let result = match frame.created {
+            Some(created) if success => address_to_word(created),
+            _ => U256::from(success),
+        };
+        if let Err(error) = self.stack.push(result) {
+            self.status = ProgramExitStatus::Halt(error);
+        }
+    }
+
+    /// Store `output` as the code of `created`, charging the executing frame `CODE_DEPOSIT_GAS` per byte.
+    /// Fails, storing nothing, for output over `MAX_CODE_SIZE` (since Spurious Dragon, or over
+    /// `Limits::max_code_size` when that is set), starting with `0xEF` (EIP-3541, since London) or that the
+    /// frame's gas can't pay for.
+    fn deploy(&mut self, created: Address, output: &[u8]) -> Result<(), EvmErrors> {
+        let limit = match self.limits.max_code_size {
+            Some(limit) => Some(limit),
+            None if self.spec >= SpecId::SpuriousDragon => Some(MAX_CODE_SIZE),
+            None => None,
+        };
+        if let Some(limit) = limit
+            && output.len() > limit
+        {
+            return Err(EvmErrors::CodeTooLarge {
+                size: output.len(),
+                limit,
+            });
+        }
+        if self.spec >= SpecId::London && output.first() == Some(&0xEF) {
+            return Err(EvmErrors::InvalidCodePrefix);
+        }
+        if !self.gas.record_cost(CODE_DEPOSIT_GAS * output.len() as u64) {
+            return Err(EvmErrors::OutOfGas);
+        }
+        self.storage.set_code(created, output.to_vec());
+        Ok(())
+    }
+
+    /// Finish the init code of a creation transaction (see `Evm::execute`) once it ends the top-level frame.
+    ///
+    /// - `STOP`: the new account keeps empty code.
+    /// - `RETURN`: the output is deployed as in `end_call`, and stays the run's output. Output that can't be
+    ///   deployed halts the run instead, consuming the gas left.
+    /// - `REVERT` or an exceptional halt: nothing is deployed; `transact_commit` rolls the account back.
+    pub(crate) fn end_create_transaction(&mut self, created: Address) {
+        let ProgramExitStatus::Success { output, .. } = &self.status else {
+            return;
+        };
+        let output = output.clone();
+        match self.deploy(created, &output) {
+            Ok(()) => {
+                self.status = ProgramExitStatus::Success {
+                    output,
+                    gas_used: self.gas.spent(),
+                }
+            }
+            Err(error) => self.exceptional_halt(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::B256;
+    use primitives::evm_types::{BlockEnv, EvmStorage, Transaction};
+
+    use super::*;
+    use crate::{TransactResult, expect_stack, expect_storage};
+
+    const CALLEE: u8 = 0xc0;
+
+    /// Top-level code: `<opcode>(gas 0xffff, CALLEE, [value 0,] args 0..0, ret 0..32)`, then `MLOAD 0`.
+    fn caller_code(opcode: u8) -> Vec<u8> {
+        let mut code = vec![0x60, 0x20, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00];
+        if opcode == 0xF1 {
+            code.extend([0x60, 0x00]);
+        }
+        code.extend([
+            0x60, CALLEE, 0x61, 0xff, 0xff, opcode, 0x60, 0x00, 0x51, 0x00,
+        ]);
+        code
+    }
+
+    fn run(opcode: u8, callee: Vec<u8>) -> Evm {
+        let mut evm = Evm {
+            code: Bytecode::new(caller_code(opcode)),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(1_000_000));
+        evm.storage
+            .set_code(Address::left_padding_from(&[CALLEE]), callee);
+        evm.run();
+        evm
+    }
+
+    /// `SSTORE(1, 7)`, then `<end>(0, 32)` with 0x2a stored at memory 0.
+    fn callee_code(end: u8) -> Vec<u8> {
+        vec![
+            0x60, 0x07, 0x60, 0x01, 0x55, 0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, end,
+        ]
+    }
+
+    #[test]
+    fn call_returns_output_and_keeps_state() {
+        let evm = run(0xF1, callee_code(0xF3));
+        let callee = Address::left_padding_from(&[CALLEE]);
+
+        assert!(evm.status.is_success());
+        expect_stack!(evm, [1, 0x2a]);
+        expect_storage!(evm, callee, 1 => 7);
+        assert_eq!(evm.return_data.len(), 32);
+        assert!(evm.call_stack.is_empty());
+    }
+
+    #[test]
+    fn reverted_call_rolls_back_state_and_refunds_gas() {
+        let reverted = run(0xF1, callee_code(0xFD));
+        let callee = Address::left_padding_from(&[CALLEE]);
+
+        expect_stack!(reverted, [0, 0x2a]);
+        expect_storage!(reverted, callee, 1 => 0);
+        // The caller warmed the callee's account; the slot the callee warmed is cold again.
+        assert!(reverted.accessed.is_warm_address(callee));
+        assert!(!reverted.accessed.is_warm_slot(callee, U256::ONE));
+
+        // A failing callee (invalid opcode) keeps all 0xffff gas it was given; the reverted one only what it used.
+        let failed = run(0xF1, vec![0x0C]);
+        expect_stack!(failed, [0, 0]);
+        assert!(reverted.gas.spent() < 0xffff);
+        assert!(failed.gas.spent() > 0xffff);
+    }
+
+    #[test]
+    fn reverted_callee_drops_its_logs() {
+        // LOG1(0, 0, topic 9) before the usual callee body.
+        let with_log = |end| [vec![0x60, 0x09, 0x5F, 0x5F, 0xA1], callee_code(end)].concat();
+        let callee = Address::left_padding_from(&[CALLEE]);
+
+        let returned = run(0xF1, with_log(0xF3));
+        assert_eq!(returned.logs.len(), 1);
+        assert_eq!(returned.logs[0].address, callee);
+        assert_eq!(returned.logs[0].topics(), &[B256::from(U256::from(9))]);
+
+        let reverted = run(0xF1, with_log(0xFD));
+        assert!(reverted.logs.is_empty());
+
+        // Logging is a state change as far as STATICCALL is concerned.
+        let static_call = run(0xFA, with_log(0xF3));
+        expect_stack!(static_call, [0, 0]);
+        assert!(static_call.logs.is_empty());
+    }
+
+    #[test]
+    fn transient_writes_revert_with_the_callee() {
+        // DELEGATECALL(gas 0xffff, CALLEE, 0, 0, 0, 0); TLOAD(1); STOP
+        let caller = vec![
+            0x5F, 0x5F, 0x5F, 0x5F, 0x60, CALLEE, 0x61, 0xff, 0xff, 0xF4, 0x60, 0x01, 0x5C, 0x00,
+        ];
+        // TSTORE(1, 7); <end>(0, 0)
+        let run_with = |end| {
+            let mut evm = Evm {
+                code: Bytecode::new(caller.clone()),
+                ..Default::default()
+            }
+            .with_gas_limit(Some(1_000_000));
+            evm.storage.set_code(
+                Address::left_padding_from(&[CALLEE]),
+                vec![0x60, 0x07, 0x60, 0x01, 0x5D, 0x5F, 0x5F, end],
+            );
+            evm.run();
+            evm
+        };
+
+        expect_stack!(run_with(0xF3), [1, 7]);
+        expect_stack!(run_with(0xFD), [0, 0]);
+    }
+
+    #[test]
+    fn stipend_alone_cannot_pay_for_sstore() {
+        // CALL(gas <gas>, CALLEE, value 1, 0, 0, 0, 0); STOP, under Istanbul.
+        let run = |gas: u8| {
+            let mut evm = Evm {
+                code: Bytecode::new(vec![
+                    0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x01, 0x60, CALLEE, 0x60,
+                    gas, 0xF1, 0x00,
+                ]),
+                ..Default::default()
+            }
+            .with_gas_limit(Some(100_000))
+            .with_spec(SpecId::Istanbul);
+            evm.storage.set_balance(evm.context.address, U256::ONE);
+            // SSTORE(0, 0): a no-op store, 800 gas under Istanbul.
+            evm.storage.set_code(
+                Address::left_padding_from(&[CALLEE]),
+                vec![0x60, 0x00, 0x60, 0x00, 0x55, 0x00],
+            );
+            evm.run();
+            evm
+        };
+        let callee = Address::left_padding_from(&[CALLEE]);
+
+        // The callee gets the 2300 stipend plus 6, which its two pushes use up: at the SSTORE exactly the
+        // stipend is left, so the sentry fails the store even though 800 gas would cover it. The value
+        // transfer is undone with it.
+        let at_stipend = run(6);
+        assert!(at_stipend.status.is_success());
+        expect_stack!(at_stipend, [0]);
+        assert_eq!(at_stipend.storage.data[&callee].info.balance, U256::ZERO);
+
+        // One gas over the stipend passes the sentry.
+        let above_stipend = run(7);
+        expect_stack!(above_stipend, [1]);
+        assert_eq!(above_stipend.storage.data[&callee].info.balance, U256::ONE);
+    }
+
+    #[test]
+    fn return_data_opcodes_read_the_last_callee_output() {
+        // caller_code, then RETURNDATASIZE; RETURNDATACOPY(dest 32, offset 31, length 1); MLOAD 32 (shifted);
+        // then RETURNDATACOPY(0, 0, 33), which reads past the 32-byte output.
+        let mut code = caller_code(0xF1);
+        code.pop();
+        code.extend([
+            0x3D, 0x60, 0x01, 0x60, 0x1f, 0x60, 0x20, 0x3E, 0x60, 0x20, 0x51, 0x60, 0x21, 0x5F,
+            0x5F, 0x3E, 0x00,
+        ]);
+        let mut evm = Evm {
+            code: Bytecode::new(code),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(1_000_000));
+        evm.storage
+            .set_code(Address::left_padding_from(&[CALLEE]), callee_code(0xF3));
+        evm.run();
+
+        expect_stack!(evm, [1, 0x2a, 32, U256::from(0x2a) << 248]);
+        assert!(matches!(evm.status, ProgramExitStatus::Halt(_)));
+    }
+
+    #[test]
+    fn staticcall_forbids_state_changes() {
+        let evm = run(0xFA, callee_code(0xF3));
+        let callee = Address::left_padding_from(&[CALLEE]);
+
+        expect_stack!(evm, [0, 0]);
+        expect_storage!(evm, callee, 1 => 0);
+    }
+
+    #[test]
+    fn delegatecall_runs_in_caller_context() {
+        let evm = run(0xF4, callee_code(0xF3));
+
+        expect_stack!(evm, [1, 0x2a]);
+        // The callee's code wrote to the caller's (the top-level frame's) storage.
+        expect_storage!(evm, Address::ZERO, 1 => 7);
+    }
+
+    #[test]
+    fn empty_return_range_past_memory_copies_nothing() {
+        // CALL(gas 0xffff, CALLEE, 0, args 0..0, ret 0xffff..+0); STOP -- CALLEE has no code.
+        let mut evm = Evm {
+            code: Bytecode::new(vec![
+                0x60, 0x00, 0x61, 0xff, 0xff, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, CALLEE,
+                0x61, 0xff, 0xff, 0xF1, 0x00,
+            ]),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(1_000_000));
+        evm.run();
+        assert!(evm.status.is_success());
+        expect_stack!(evm, [1]);
+        assert_eq!(evm.memory.msize(), 0);
+
+        // STATICCALL(gas 0xffff, identity, args 0..1, ret 0xffff..+0); RETURNDATASIZE; STOP -- output dropped.
+        let mut evm = Evm {
+            code: Bytecode::new(vec![
+                0x60, 0x00, 0x61, 0xff, 0xff, 0x60, 0x01, 0x60, 0x00, 0x60, 0x04, 0x61, 0xff, 0xff,
+                0xFA, 0x3D, 0x00,
+            ]),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(1_000_000));
+        evm.run();
+        assert!(evm.status.is_success());
+        expect_stack!(evm, [1, 1]);
+        assert_eq!(evm.memory.msize(), 32);
+    }
+
+    #[test]
+    fn each_call_type_sees_its_own_context() {
+        let (sender, a, c) = (
+            Address::with_last_byte(0x01),
+            Address::with_last_byte(0xa0),
+            Address::with_last_byte(0xc0),
+        );
+        // Returns ADDRESS, CALLER, CALLVALUE and SLOAD(0) as four words.
+        let report = vec![
+            0x30, 0x60, 0x00, 0x52, 0x33, 0x60, 0x20, 0x52, 0x34, 0x60, 0x40, 0x52, 0x60, 0x00,
+            0x54, 0x60, 0x60, 0x52, 0x60, 0x80, 0x60, 0x00, 0xF3,
+        ];
+        let (callee, caller, origin) = (
+            address_to_word(c),
+            address_to_word(a),
+            address_to_word(sender),
+        );
+        // (opcode, ADDRESS, CALLER, CALLVALUE, slot 0 of the account SLOAD reads)
+        let matrix = [
+            (0xF1, callee, caller, 3, 0xcc),
+            (0xF2, caller, caller, 3, 0xaa),
+            (0xF4, caller, origin, 5, 0xaa),
+            (0xFA, callee, caller, 0, 0xcc),
+        ];
+        for (opcode, address, msg_sender, value, slot) in matrix {
+            // <opcode>(gas 0xffff, c, [value 3,] args 0..0, ret 0..0x80)
+            let mut code = vec![0x60, 0x80, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00];
+            if matches!(opcode, 0xF1 | 0xF2) {
+                code.extend([0x60, 0x03]);
+            }
+            code.extend([0x60, 0xc0, 0x61, 0xff, 0xff, opcode, 0x00]);
+            let mut evm = Evm::default().with_gas_limit(Some(1_000_000));
+            evm.tx.from = sender;
+            evm.tx.to = a;
+            evm.tx.value = U256::from(5);
+            evm.storage.set_code(a, code);
+            evm.storage.set_code(c, report.clone());
+            evm.storage.set_balance(a, U256::from(100));
+            evm.storage.s_store(a, U256::ZERO, U256::from(0xaa));
+            evm.storage.s_store(c, U256::ZERO, U256::from(0xcc));
+            evm.transact_commit();
+
+            expect_stack!(evm, [1]);
+            let seen: Vec<U256> = (0..4).map(|n| evm.memory.load_word(n * 32)).collect();
+            assert_eq!(
+                seen,
+                [address, msg_sender, U256::from(value), U256::from(slot)],
+                "opcode {opcode:#x}"
+            );
+        }
+    }
+
+    #[test]
+    fn origin_stays_the_sender_while_caller_tracks_each_frame() {
+        let (sender, a, b, c) = (
+            Address::with_last_byte(0x01),
+            Address::with_last_byte(0xa0),
+            Address::with_last_byte(0xb0),
+            Address::with_last_byte(0xc0),
+        );
+        // SSTORE(0, ORIGIN); SSTORE(1, CALLER); STOP
+        let report = [0x32, 0x60, 0x00, 0x55, 0x33, 0x60, 0x01, 0x55, 0x00];
+        // CALL(gas 1_000_000, target, 0, 0, 0, 0, 0); POP; then report.
+        let call_then_report = |target: Address| {
+            let mut code = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00];
+            code.extend([0x60, target.0[19], 0x62, 0x0f, 0x42, 0x40, 0xF1, 0x50]);
+            code.extend(report);
+            code
+        };
+        let mut evm = Evm::default().with_gas_limit(Some(1_000_000));
+        evm.tx.from = sender;
+        evm.tx.to = a;
+        evm.storage.set_code(a, call_then_report(b));
+        evm.storage.set_code(b, call_then_report(c));
+        evm.storage.set_code(c, report.to_vec());
+        evm.transact_commit();
+
+        assert!(evm.status.is_success());
+        // Depth 2: the sender two calls up is still ORIGIN, CALLER is the contract that called.
+        expect_storage!(evm, c, 0 => address_to_word(sender), 1 => address_to_word(b));
+        expect_storage!(evm, b, 0 => address_to_word(sender), 1 => address_to_word(a));
+        // Only the top-level frame sees ORIGIN == CALLER.
+        expect_storage!(evm, a, 0 => address_to_word(sender), 1 => address_to_word(sender));
+    }
+
+    #[test]
+    fn recursion_stops_at_depth_limit() {
+        // CALL(gas 2^64 - 1, ADDRESS, 0, 0, 0, 0, 0); STOP -- calls itself until the depth limit.
+        let mut code = vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x30, 0x67,
+        ];
+        code.extend([0xff; 8]);
+        code.extend([0xF1, 0x00]);
+        let contract = Address::left_padding_from(&[CALLEE]);
+        let mut evm = Evm::default();
+        evm.tx.to = contract;
+        evm.storage.set_code(contract, code);
+        evm.execute();
+
+        let mut deepest = 0;
+        while evm.status == ProgramExitStatus::default() {
+            evm.step();
+            deepest = deepest.max(evm.depth());
+        }
+
+        assert!(evm.status.is_success());
+        assert_eq!(deepest, CALL_DEPTH_LIMIT + 1);
+        expect_stack!(evm, [1]);
+    }
+
+    /// Top-level code: `init` stored at the end of memory word 0, then `<opcode>(0, 32 - len, len[, salt])`
+    /// run `times` times; the results stay on the stack.
+    fn create_code(opcode: u8, init: &[u8], times: usize) -> Vec<u8> {
+        let mut code = vec![0x7F];
+        code.extend(std::iter::repeat_n(0, 32 - init.len()));
+        code.extend(init);
+        code.extend([0x5F, 0x52]);
+        for _ in 0..times {
+            if opcode == 0xF5 {
+                code.extend([0x60, 0x05]);
+            }
+            code.extend([
+                0x60,
+                init.len() as u8,
+                0x60,
+                32 - init.len() as u8,
+                0x5F,
+                opcode,
+            ]);
+        }
+        code.push(0x00);
+        code
+    }
+
+    fn run_create(opcode: u8, init: &[u8], times: usize) -> Evm {
+        let mut evm = Evm {
+            code: Bytecode::new(create_code(opcode, init, times)),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(1_000_000));
+        evm.context.address = Address::left_padding_from(&[CALLEE]);
+        evm.run();
+        evm
+    }
+
+    /// Init code returning the one-byte runtime code `0x2a`.
+    const INIT: [u8; 8] = [0x60, 0x2a, 0x5F, 0x53, 0x60, 0x01, 0x5F, 0xF3];
+
+    #[test]
+    fn create_deploys_returned_code_at_nonce_address() {
+        let evm = run_create(0xF0, &INIT, 2);
+        let sender = Address::left_padding_from(&[CALLEE]);
+        let (first, second) = (sender.create(0), sender.create(1));
+
+        assert!(evm.status.is_success());
+        assert_eq!(
+            evm.stack.as_slice(),
+            &[
+                U256::from_be_slice(first.as_slice()),
+                U256::from_be_slice(second.as_slice())
+            ]
+        );
+        assert_eq!(evm.storage.code(first).as_slice(), &[0x2a]);
+        assert_eq!(evm.storage.data[&first].info.nonce, U256::ONE);
+        assert_eq!(evm.storage.data[&sender].info.nonce, U256::from(2));
+        assert!(evm.return_data.is_empty());
+    }
+
+    #[test]
+    fn create2_uses_salt_and_rejects_collisions() {
+        let evm = run_create(0xF5, &INIT, 2);
+        let sender = Address::left_padding_from(&[CALLEE]);
+        let created = sender.create2_from_code(U256::from(5).to_be_bytes::<32>(), INIT);
+
+        assert!(evm.status.is_success());
+        // The second CREATE2 derives the same address, which already has code.
+        assert_eq!(
+            evm.stack.as_slice(),
+            &[U256::from_be_slice(created.as_slice()), U256::ZERO]
+        );
+        assert_eq!(evm.storage.code(created).as_slice(), &[0x2a]);
+    }
+
+    #[test]
+    fn reverted_init_code_deploys_nothing() {
+        // Same as INIT, ending in REVERT.
+        let mut init = INIT;
+        init[7] = 0xFD;
+        let evm = run_create(0xF0, &init, 1);
+        let created = Address::left_padding_from(&[CALLEE]).create(0);
+
+        expect_stack!(evm, [0]);
+        assert!(evm.storage.code(created).is_empty());
+        assert_eq!(evm.return_data, vec![0x2a]);
+    }
+
+    fn create_transaction(init: &[u8], storage: EvmStorage) -> (Evm, TransactResult) {
+        let tx = Transaction {
+            from: Address::with_last_byte(0x01),
+            nonce: U256::from(3),
+            data: init.to_vec().into(),
+            gas_limit: U256::from(100_000),
+            ..Default::default()
+        };
+        let mut evm = Evm::new(
+            BlockEnv::default(),
+            tx,
+            Memory::new(),
+            Stack::default(),
+            storage,
+        );
+        let result = evm.transact_commit();
+        (evm, result)
+    }
+
+    #[test]
+    fn creation_transaction_deploys_what_init_code_returns() {
+        let created = Address::with_last_byte(0x01).create(3);
+
+        // STOP: the account is created with empty code.
+        let (evm, stopped) = create_transaction(&[0x00], EvmStorage::default());
+        assert!(stopped.status.is_success());
+        assert!(evm.storage.code(created).is_empty());
+        assert_eq!(evm.storage.data[&created].info.nonce, U256::ONE);
+
+        // RETURN: the returned buffer becomes the code, paying the deposit on top of the init code's gas.
+        let (evm, returned) = create_transaction(&INIT, EvmStorage::default());
+        assert_eq!(
+            returned.status,
+            ProgramExitStatus::Success {
+                output: Bytes::from(vec![0x2a]),
+                gas_used: returned.gas_used,
+            }
+        );
+        assert_eq!(evm.storage.code(created).as_slice(), &[0x2a]);
+        assert_eq!(returned.gas_used, stopped.gas_used + 16 + CODE_DEPOSIT_GAS);
+
+        // REVERT: nothing deployed or kept, and the gas left is not spent.
+        let mut init = INIT;
+        init[7] = 0xFD;
+        let (evm, reverted) = create_transaction(&init, EvmStorage::default());
+        assert_eq!(
+            reverted.status,
+            ProgramExitStatus::Revert {
+                output: Bytes::from(vec![0x2a])
+            }
+        );
+        assert!(!evm.storage.data.contains_key(&created));
+        assert!(reverted.state_diff.accounts.is_empty());
+        assert_eq!(reverted.gas_used, 16);
+    }
+
+    #[test]
+    fn creation_transaction_halts_on_undeployable_code_or_collision() {
+        // Returns 0xEF.
+        let mut init = INIT;
+        init[1] = 0xEF;
+        let (evm, result) = create_transaction(&init, EvmStorage::default());
+        assert_eq!(
+            result.status,
+            ProgramExitStatus::Halt(EvmErrors::InvalidCodePrefix)
+        );
+        assert_eq!(result.gas_used, 100_000);
+        assert!(evm.storage.data.is_empty());
+
+        let created = Address::with_last_byte(0x01).create(3);
+        let mut storage = EvmStorage::default();
+        storage.set_code(created, vec![0x00]);
+        let (evm, result) = create_transaction(&INIT, storage);
+        assert_eq!(
+            result.status,
+            ProgramExitStatus::Halt(EvmErrors::CreateCollision { address: created })
+        );
+        assert_eq!(evm.instructions_executed, 0);
+        assert_eq!(evm.storage.code(created).as_slice(), &[0x00]);
+    }
+}
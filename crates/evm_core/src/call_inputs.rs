@@ -0,0 +1,211 @@
+//! Typed operands for the CALL and CREATE families.
+//!
+//! CALL pops seven operands and mis-ordering them is a classic bug, so every handler (and anything observing calls)
+//! should decode them through `CallInputs::pop` / `CreateInputs::pop` instead of popping raw values.
+
+use alloy::primitives::{Address, U256};
+use primitives::{errors::EvmErrors, stack::Stack, utils::word_to_address};
+
+/// Which CALL-family opcode produced a `CallInputs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallScheme {
+    Call,
+    CallCode,
+    DelegateCall,
+    StaticCall,
+}
+
+impl CallScheme {
+    /// Whether the opcode takes a `value` operand (only CALL and CALLCODE do).
+    pub fn has_value(&self) -> bool {
+        matches!(self, CallScheme::Call | CallScheme::CallCode)
+    }
+
+    /// Number of stack items the opcode consumes.
+    pub fn operand_count(&self) -> usize {
+        if self.has_value() { 7 } else { 6 }
+    }
+}
+
+/// Operands of CALL / CALLCODE / DELEGATECALL / STATICCALL.
+/// `value` is zero for schemes without a value operand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallInputs {
+    pub scheme: CallScheme,
+    pub gas: U256,
+    pub target: Address,
+    pub value: U256,
+    pub args_offset: U256,
+    pub args_size: U256,
+    pub ret_offset: U256,
+    pub ret_size: U256,
+}
+
+impl CallInputs {
+    /// Pop the operands in EVM order (top first): `gas, address, [value,] argsOffset, argsSize, retOffset, retSize`.
+    /// Returns `Err(EvmErrors::StackUnderflow { .. })` without touching the stack if too few items are present.
+    pub fn pop(stack: &mut Stack, scheme: CallScheme) -> Result<Self, EvmErrors> {
+        let operands = stack.pop_n(scheme.operand_count())?;
+        let (value, rest) = if scheme.has_value() {
+            (operands[2], &operands[3..])
+        } else {
+            (U256::ZERO, &operands[2..])
+        };
+
+        Ok(CallInputs {
+            scheme,
+            gas: operands[0],
+            target: word_to_address(operands[1]),
+            value,
+            args_offset: rest[0],
+            args_size: rest[1],
+            ret_offset: rest[2],
+            ret_size: rest[3],
+        })
+    }
+}
+
+/// Which CREATE-family opcode produced a `CreateInputs`; CREATE2 carries its salt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreateScheme {
+    Create,
+    Create2 { salt: U256 },
+}
+
+/// Operands of CREATE / CREATE2.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateInputs {
+    pub scheme: CreateScheme,
+    pub value: U256,
+    pub offset: U256,
+    pub size: U256,
+}
+
+impl CreateInputs {
+    /// Pop the operands in EVM order (top first): `value, offset, size` and, for CREATE2, `salt`.
+    /// `is_create2` selects the opcode. Returns `Err(EvmErrors::StackUnderflow { .. })` without touching the
+    /// stack if too few items are present.
+    pub fn pop(stack: &mut Stack, is_create2: bool) -> Result<Self, EvmErrors> {
+        let operands = stack.pop_n(if is_create2 { 4 } else { 3 })?;
+        let (value, offset, size) = (operands[0], operands[1], operands[2]);
+        let scheme = if is_create2 {
+            CreateScheme::Create2 { salt: operands[3] }
+        } else {
+            CreateScheme::Create
+        };
+
+        Ok(CreateInputs {
+            scheme,
+            value,
+            offset,
+            size,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a stack whose top-to-bottom order is `items`.
+    fn stack_top_first(items: &[u64]) -> Stack {
+        let mut stack = Stack::default();
+        for item in items.iter().rev() {
+            stack.push(U256::from(*item)).unwrap();
+        }
+        stack
+    }
+
+    #[test]
+    fn call_operands_are_decoded_in_order() {
+        let mut stack = stack_top_first(&[1, 2, 3, 4, 5, 6, 7, 99]);
+        let inputs = CallInputs::pop(&mut stack, CallScheme::Call).unwrap();
+
+        assert_eq!(
+            inputs,
+            CallInputs {
+                scheme: CallScheme::Call,
+                gas: U256::from(1),
+                target: Address::left_padding_from(&[2]),
+                value: U256::from(3),
+                args_offset: U256::from(4),
+                args_size: U256::from(5),
+                ret_offset: U256::from(6),
+                ret_size: U256::from(7),
+            }
+        );
+        assert_eq!(stack.as_slice(), &[U256::from(99)]);
+    }
+
+    #[test]
+    fn delegatecall_and_staticcall_have_no_value_operand() {
+        for scheme in [CallScheme::DelegateCall, CallScheme::StaticCall] {
+            let mut stack = stack_top_first(&[1, 2, 3, 4, 5, 6]);
+            let inputs = CallInputs::pop(&mut stack, scheme).unwrap();
+
+            assert_eq!(inputs.value, U256::ZERO);
+            assert_eq!(inputs.args_offset, U256::from(3));
+            assert_eq!(inputs.args_size, U256::from(4));
+            assert_eq!(inputs.ret_offset, U256::from(5));
+            assert_eq!(inputs.ret_size, U256::from(6));
+            assert!(stack.is_empty());
+        }
+    }
+
+    #[test]
+    fn call_target_uses_low_twenty_bytes() {
+        let mut stack = stack_top_first(&[0, 0, 0, 0, 0, 0, 0]);
+        stack.as_mut_slice()[5] = U256::MAX;
+        let inputs = CallInputs::pop(&mut stack, CallScheme::Call).unwrap();
+
+        assert_eq!(inputs.target, Address::repeat_byte(0xff));
+    }
+
+    #[test]
+    fn call_underflow_leaves_stack_untouched() {
+        let mut stack = stack_top_first(&[1, 2, 3, 4, 5, 6]);
+
+        assert_eq!(
+            CallInputs::pop(&mut stack, CallScheme::CallCode),
+            Err(EvmErrors::StackUnderflow {
+                required: 7,
+                present: 6
+            })
+        );
+        assert_eq!(stack.len(), 6);
+    }
+
+    #[test]
+    fn create_and_create2_operands_are_decoded_in_order() {
+        let mut stack = stack_top_first(&[1, 2, 3]);
+        let create = CreateInputs::pop(&mut stack, false).unwrap();
+        assert_eq!(
+            create,
+            CreateInputs {
+                scheme: CreateScheme::Create,
+                value: U256::from(1),
+                offset: U256::from(2),
+                size: U256::from(3),
+            }
+        );
+
+        let mut stack = stack_top_first(&[1, 2, 3, 4]);
+        let create2 = CreateInputs::pop(&mut stack, true).unwrap();
+        assert_eq!(
+            create2.scheme,
+            CreateScheme::Create2 {
+                salt: U256::from(4)
+            }
+        );
+        assert_eq!(create2.size, U256::from(3));
+
+        let mut stack = stack_top_first(&[1, 2, 3]);
+        assert_eq!(
+            CreateInputs::pop(&mut stack, true),
+            Err(EvmErrors::StackUnderflow {
+                required: 4,
+                present: 3
+            })
+        );
+    }
+}
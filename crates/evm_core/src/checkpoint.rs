@@ -0,0 +1,178 @@
+//! Pausing an interpreter mid-execution and resuming it later, possibly in another process.
+//!
+//! `Evm::checkpoint` captures everything `step()` reads or writes into a `Checkpoint`, which serializes to JSON
+//! bytes (`to_bytes` / `from_bytes`). `Evm::restore` rebuilds an `Evm` that continues exactly where the original
+//! paused, including suspended callers with the state journal they would revert to, and the per-transaction gas
+//! bookkeeping (SSTORE original values and the warm access set).
+//!
+//! Not captured: `etch` overrides (test-only) and sinks attached by the caller, such as a `Tracer`.
+
+use std::collections::HashMap;
+
+use alloy::primitives::{Address, Bytes, Log, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Evm, ProgramExitStatus, Trap,
+    access::AccessSet,
+    call_frame::{CallContext, CallStack},
+    gas::Gas,
+    limits::Limits,
+    spec::SpecId,
+};
+use primitives::{
+    bytecode::Bytecode,
+    errors::EvmErrors,
+    evm_types::{BlockEnv, EvmStorage, Transaction},
+    journal::{JournalEntry, JournaledState},
+    memory::Memory,
+    stack::Stack,
+    state_dump::SerializableState,
+};
+
+/// A paused `Evm`. Build with `Evm::checkpoint`, resume with `Evm::restore`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    pub spec: SpecId,
+    pub limits: Limits,
+    pub block_env: BlockEnv,
+    pub tx: Transaction,
+    pub context: CallContext,
+    pub code: Bytes,
+    pub pc: usize,
+    /// Stack, bottom first.
+    pub stack: Vec<U256>,
+    pub memory: Bytes,
+    pub return_data: Bytes,
+    pub state: SerializableState,
+    /// `EvmStorage::original_values`, which `state` doesn't carry.
+    pub original_values: HashMap<Address, HashMap<U256, U256>>,
+    /// Undo log the suspended callers' storage checkpoints point into.
+    pub journal: Vec<JournalEntry>,
+    /// Transient storage (EIP-1153) of the running transaction.
+    pub transient: HashMap<Address, HashMap<U256, U256>>,
+    pub accessed: AccessSet,
+    pub logs: Vec<Log>,
+    pub gas: Gas,
+    pub status: ProgramExitStatus,
+    pub recover_traps: bool,
+    pub traps: Vec<Trap>,
+    pub instruction_budget: Option<u64>,
+    pub instructions_executed: u64,
+    /// Callers suspended below the executing frame.
+    pub call_stack: CallStack,
+    /// The account a creation transaction's init code deploys to.
+    pub created: Option<Address>,
+}
+
+impl Checkpoint {
+    pub fn to_bytes(&self) -> serde_json::Result<Vec<u8>> {
+        serde_json::to_vec(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> serde_json::Result<Self> {
+        serde_json::from_slice(bytes)
+    }
+}
+
+impl Evm {
+    /// Capture the interpreter as it stands between two instructions.
+    /// Fails only if an account's nonce doesn't fit in a `u64` (`NonceTooLarge`, see `SerializableState`).
+    pub fn checkpoint(&self) -> Result<Checkpoint, EvmErrors> {
+        Ok(Checkpoint {
+            spec: self.spec,
+            limits: self.limits.clone(),
+            block_env: self.block_env.clone(),
+            tx: self.tx.clone(),
+            context: self.context.clone(),
+            code: Bytes::copy_from_slice(self.code.as_slice()),
+            pc: self.pc,
+            stack: self.stack.as_slice().to_vec(),
+            memory: Bytes::copy_from_slice(&self.memory.data),
+            return_data: Bytes::copy_from_slice(&self.return_data),
+            state: SerializableState::try_from(&*self.storage)?,
+            original_values: self.storage.original_values.clone(),
+            journal: self.storage.journal().to_vec(),
+            transient: self.storage.transient().clone(),
+            accessed: self.accessed.clone(),
+            logs: self.logs.clone(),
+            gas: self.gas,
+            status: self.status.clone(),
+            recover_traps: self.recover_traps,
+            traps: self.traps.clone(),
+            instruction_budget: self.instruction_budget,
+            instructions_executed: self.instructions_executed,
+            call_stack: self.call_stack.clone(),
+            created: self.created,
+        })
+    }
+
+    /// Rebuild the interpreter captured by `checkpoint`; `run()` (or `step()`) continues from its `pc`.
+    /// Fails only if the checkpoint holds more than `STACK_LIMIT` stack items.
+    pub fn restore(checkpoint: Checkpoint) -> Result<Evm, EvmErrors> {
+        let mut stack = Stack::default();
+        for value in checkpoint.stack {
+            stack.push(value)?;
+        }
+        let mut storage: EvmStorage = checkpoint.state.into();
+        storage.original_values = checkpoint.original_values;
+        Ok(Evm {
+            block_env: checkpoint.block_env,
+            tx: checkpoint.tx,
+            context: checkpoint.context,
+            code: Bytecode::new(checkpoint.code.to_vec()),
+            memory: Memory::new_with_data(checkpoint.memory.to_vec()),
+            return_data: checkpoint.return_data.to_vec(),
+            stack,
+            storage: JournaledState::from_parts(storage, checkpoint.journal, checkpoint.transient),
+            pc: checkpoint.pc,
+            status: checkpoint.status,
+            recover_traps: checkpoint.recover_traps,
+            traps: checkpoint.traps,
+            instruction_budget: checkpoint.instruction_budget,
+            instructions_executed: checkpoint.instructions_executed,
+            gas: checkpoint.gas,
+            spec: checkpoint.spec,
+            limits: checkpoint.limits,
+            call_stack: checkpoint.call_stack,
+            accessed: checkpoint.accessed,
+            logs: checkpoint.logs,
+            created: checkpoint.created,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expect_stack;
+
+    #[test]
+    fn resumes_from_serialized_checkpoint() {
+        // PUSH1 2; PUSH1 3; MSTORE; PUSH1 4; PUSH1 5; ADD; STOP
+        let code = vec![
+            0x60, 0x02, 0x60, 0x03, 0x52, 0x60, 0x04, 0x60, 0x05, 0x01, 0x00,
+        ];
+        let mut evm = Evm {
+            code: Bytecode::new(code),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(100_000));
+        for _ in 0..4 {
+            evm.step();
+        }
+
+        let bytes = evm.checkpoint().unwrap().to_bytes().unwrap();
+        let mut resumed = Evm::restore(Checkpoint::from_bytes(&bytes).unwrap()).unwrap();
+        assert_eq!(resumed.checkpoint(), evm.checkpoint());
+
+        evm.run();
+        resumed.run();
+
+        assert!(resumed.status.is_success());
+        assert_eq!(resumed.gas, evm.gas);
+        assert_eq!(resumed.memory.load_word(3), U256::from(2));
+        expect_stack!(resumed, [9]);
+    }
+}
@@ -0,0 +1,273 @@
+//! ERC-7562 validation-rule inspector: run an account-abstraction validation frame and report what it did
+//! that a bundler would reject.
+//!
+//! Covers the opcode rules (the environment opcodes banned during validation, `GAS` unless it feeds a call,
+//! `CREATE`/`CREATE2` and `SELFDESTRUCT`) and the storage rules: a validation frame may touch the storage of
+//! the entity being validated, and the slots of other contracts that are "associated" with it — the slot
+//! equal to the entity's address, or `keccak256(entity || x) + n` for `n < 128`, as produced by a Solidity
+//! `mapping(address => ...)`. The associated slots are recognised by watching the `KECCAK256` preimages the
+//! frame itself computes, as the ERC prescribes.
+
+use std::collections::BTreeSet;
+
+use alloy::primitives::{Address, U256, keccak256};
+use primitives::utils::address_to_word;
+use serde::Serialize;
+
+use crate::{Evm, ProgramExitStatus, opcodes::Opcode};
+
+/// How far past an associated `keccak256(entity || x)` slot a struct member may live.
+pub const ASSOCIATED_SLOT_RANGE: u64 = 128;
+
+/// Opcodes that read the block or transaction environment and are banned outright during validation.
+pub const BANNED_OPCODES: &[Opcode] = &[
+    Opcode::GASPRICE,
+    Opcode::GASLIMIT,
+    Opcode::DIFFICULTY,
+    Opcode::TIMESTAMP,
+    Opcode::BASEFEE,
+    Opcode::BLOCKHASH,
+    Opcode::NUMBER,
+    Opcode::SELFBALANCE,
+    Opcode::BALANCE,
+    Opcode::ORIGIN,
+    Opcode::COINBASE,
+    Opcode::BLOBHASH,
+    Opcode::BLOBBASEFEE,
+    Opcode::INVALID,
+    Opcode::SELFDESTRUCT,
+];
+
+/// What rule a step broke.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum ViolationKind {
+    /// One of `BANNED_OPCODES`.
+    BannedOpcode,
+    /// `GAS` not immediately followed by a `CALL`, `CALLCODE`, `DELEGATECALL` or `STATICCALL`.
+    GasNotFollowedByCall,
+    /// `CREATE` or `CREATE2`; only the sender's factory may deploy, and that happens outside this frame.
+    Create,
+    /// `SLOAD`/`SSTORE` on a slot of another contract that is not associated with the entity.
+    UnassociatedStorage { address: Address, slot: U256 },
+}
+
+/// One offending step.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Violation {
+    pub pc: usize,
+    pub depth: usize,
+    pub op_name: String,
+    #[serde(flatten)]
+    pub kind: ViolationKind,
+}
+
+/// Result of `inspect_validation`. The validation passes the rules when `violations` is empty.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+    pub entity: Address,
+    pub status: ProgramExitStatus,
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Run `evm` (with its code already loaded, see `Evm::execute`) to completion as the validation frame of
+/// `entity` — the account, paymaster or factory being validated — and collect every rule violation.
+///
+/// Execution is not stopped at the first violation, so one run reports them all.
+pub fn inspect_validation(evm: &mut Evm, entity: Address) -> ValidationReport {
+    let entity_word = address_to_word(entity);
+    let mut associated = BTreeSet::new();
+    let mut violations = Vec::new();
+
+    while evm.status == ProgramExitStatus::default() {
+        let pc = evm.pc;
+        let Some(op) = evm
+            .code
+            .as_slice()
+            .get(pc)
+            .and_then(|byte| Opcode::from_u8(*byte))
+        else {
+            evm.step();
+            continue;
+        };
+
+        let kind = match op {
+            op if BANNED_OPCODES.contains(&op) => Some(ViolationKind::BannedOpcode),
+            Opcode::GAS if !next_is_call(evm, pc) => Some(ViolationKind::GasNotFollowedByCall),
+            Opcode::CREATE | Opcode::CREATE2 => Some(ViolationKind::Create),
+            Opcode::SLOAD | Opcode::SSTORE => top(evm, 0).and_then(|slot| {
+                let address = evm.context.address;
+                let allowed = address == entity
+                    || slot == entity_word
+                    || associated.iter().any(|base: &U256| {
+                        slot.checked_sub(*base)
+                            .is_some_and(|n| n < U256::from(ASSOCIATED_SLOT_RANGE))
+                    });
+                (!allowed).then_some(ViolationKind::UnassociatedStorage { address, slot })
+            }),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            violations.push(Violation {
+                pc,
+                depth: evm.depth(),
+                op_name: op.info().name.to_string(),
+                kind,
+            });
+        }
+
+        // Operands have to be read before the step pops them; the memory is read after it, once the
+        // handler has expanded it.
+        let preimage = match (op, top(evm, 0), top(evm, 1)) {
+            (Opcode::KECCAK256, Some(offset), Some(len)) => Some((offset, len)),
+            _ => None,
+        };
+        evm.step();
+        if let Some((offset, len)) = preimage
+            && evm.pc != pc
+            && len >= U256::from(32)
+        {
+            let offset: usize = offset.saturating_to();
+            if evm.memory.data.get(offset..offset + 32)
+                == Some(&entity_word.to_be_bytes::<32>()[..])
+            {
+                let len: usize = len.saturating_to();
+                let digest = keccak256(&evm.memory.data[offset..offset + len]);
+                associated.insert(U256::from_be_bytes(digest.0));
+            }
+        }
+    }
+
+    ValidationReport {
+        entity,
+        status: evm.status.clone(),
+        violations,
+    }
+}
+
+/// The `n`-th stack item from the top, if there is one.
+fn top(evm: &Evm, n: usize) -> Option<U256> {
+    let stack = evm.stack.as_slice();
+    stack.len().checked_sub(n + 1).map(|i| stack[i])
+}
+
+fn next_is_call(evm: &Evm, pc: usize) -> bool {
+    matches!(
+        evm.code
+            .as_slice()
+            .get(pc + 1)
+            .and_then(|b| Opcode::from_u8(*b)),
+        Some(Opcode::CALL | Opcode::CALLCODE | Opcode::DELEGATECALL | Opcode::STATICCALL)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use primitives::bytecode::Bytecode;
+
+    use super::*;
+
+    fn validation_evm(code: Vec<u8>, address: Address) -> Evm {
+        let mut evm = Evm {
+            code: Bytecode::new(code),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(1_000_000));
+        evm.context.address = address;
+        evm
+    }
+
+    #[test]
+    fn flags_environment_opcodes_and_bare_gas() {
+        let entity = Address::repeat_byte(0xAA);
+        // ORIGIN; ORIGIN; GAS; POP; POP; POP; STOP
+        let mut evm = validation_evm(vec![0x32, 0x32, 0x5A, 0x50, 0x50, 0x50, 0x00], entity);
+
+        let report = inspect_validation(&mut evm, entity);
+
+        let ops: Vec<_> = report
+            .violations
+            .iter()
+            .map(|v| (v.pc, v.op_name.as_str(), v.kind.clone()))
+            .collect();
+        assert_eq!(
+            ops,
+            vec![
+                (0, "ORIGIN", ViolationKind::BannedOpcode),
+                (1, "ORIGIN", ViolationKind::BannedOpcode),
+                (2, "GAS", ViolationKind::GasNotFollowedByCall),
+            ]
+        );
+        assert!(!report.is_valid());
+        // Flagging an opcode doesn't stop the frame: GAS ran and the POPs after it consumed its output.
+        assert!(report.status.is_success());
+        assert!(evm.stack.is_empty());
+    }
+
+    #[test]
+    fn flags_timestamp_and_number_and_runs_on() {
+        let entity = Address::repeat_byte(0xAA);
+        // TIMESTAMP; NUMBER; STOP
+        let mut evm = validation_evm(vec![0x42, 0x43, 0x00], entity);
+        evm.block_env.timestamp = U256::from(1_700_000_000);
+        evm.block_env.number = U256::from(100);
+
+        let report = inspect_validation(&mut evm, entity);
+
+        let ops: Vec<_> = report
+            .violations
+            .iter()
+            .map(|v| (v.pc, v.op_name.as_str(), v.kind.clone()))
+            .collect();
+        assert_eq!(
+            ops,
+            vec![
+                (0, "TIMESTAMP", ViolationKind::BannedOpcode),
+                (1, "NUMBER", ViolationKind::BannedOpcode),
+            ]
+        );
+        assert!(report.status.is_success());
+        assert_eq!(
+            evm.stack.as_slice(),
+            [U256::from(1_700_000_000), U256::from(100)]
+        );
+    }
+
+    #[test]
+    fn storage_of_other_contracts_must_be_associated() {
+        let entity = Address::repeat_byte(0xAA);
+        let token = Address::repeat_byte(0xBB);
+        let mut code = vec![0x73];
+        code.extend_from_slice(entity.as_slice());
+        code.extend_from_slice(&[
+            0x5F, 0x52, // PUSH20 entity; PUSH0; MSTORE        -> memory[0..32] = entity
+            0x60, 0x40, 0x5F, 0x20, // PUSH1 64; PUSH0; KECCAK256 -> keccak(entity || 0)
+            0x60, 0x01, 0x01, // PUSH1 1; ADD                    -> associated slot + 1
+            0x60, 0x07, 0x90, 0x55, // PUSH1 7; SWAP1; SSTORE   -> allowed
+            0x60, 0x07, 0x60, 0x05,
+            0x55, // PUSH1 7; PUSH1 5; SSTORE -> slot 5 is not associated
+            0x00,
+        ]);
+        let mut evm = validation_evm(code, token);
+
+        let report = inspect_validation(&mut evm, entity);
+
+        assert!(report.status.is_success());
+        assert_eq!(report.violations.len(), 1);
+        assert_eq!(
+            report.violations[0].kind,
+            ViolationKind::UnassociatedStorage {
+                address: token,
+                slot: U256::from(5)
+            }
+        );
+    }
+}
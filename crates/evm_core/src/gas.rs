@@ -0,0 +1,435 @@
+use alloy::primitives::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::{opcodes::Opcode, spec::SpecId};
+
+/// Gas accounting for a single execution frame.
+///
+/// All gas arithmetic goes through this type instead of ad hoc `u64` math in handlers:
+/// - `record_cost`: charge gas; fails (returns `false`, charging nothing) when not enough is left.
+/// - `erase_cost`: give gas back, e.g. the unspent part of what was forwarded to a sub-call.
+/// - `record_refund`: accumulate (or, with a negative value, reduce) the SSTORE/SELFDESTRUCT refund counter.
+/// - `set_final_refund`: apply the refund cap once execution ends.
+/// - `record_memory_expansion`: charge the quadratic memory cost when the highest touched memory word grows.
+///
+/// `Default` is unlimited gas (see `Gas::unlimited`), so an `Evm::default()` still runs metered but can't run dry.
+///
+/// Debug builds assert the invariants: remaining never exceeds the limit and the final refund is never negative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Gas {
+    limit: u64,
+    remaining: u64,
+    refunded: i64,
+    memory_words: u64,
+}
+
+impl Default for Gas {
+    fn default() -> Self {
+        Gas::unlimited()
+    }
+}
+
+/// Dynamic EXP cost per byte of the exponent (EIP-160, since Spurious Dragon).
+pub const EXP_BYTE_GAS: u64 = 50;
+
+/// EXP cost per exponent byte under `spec`: 10 before Spurious Dragon, `EXP_BYTE_GAS` since.
+pub fn exp_byte_gas(spec: SpecId) -> u64 {
+    if spec >= SpecId::SpuriousDragon {
+        EXP_BYTE_GAS
+    } else {
+        10
+    }
+}
+
+/// Static gas of `opcode` under `spec`: `OpcodeInfo::base_gas` (the Cancun schedule) except for the state-access
+/// opcodes earlier forks priced differently. Their prices as of Frontier, Tangerine Whistle (EIP-150) and
+/// Istanbul (EIP-1884) apply until Berlin, whose base is the warm price (EIP-2929; handlers add the cold surcharge).
+pub fn static_gas(opcode: Opcode, spec: SpecId) -> u64 {
+    let (frontier, tangerine_whistle, istanbul) = match opcode {
+        _ if spec >= SpecId::Berlin => return opcode.base_gas(),
+        Opcode::SLOAD => (50, 200, 800),
+        Opcode::BALANCE => (20, 400, 700),
+        Opcode::EXTCODESIZE | Opcode::EXTCODECOPY => (20, 700, 700),
+        Opcode::EXTCODEHASH => (400, 400, 700),
+        Opcode::CALL | Opcode::CALLCODE | Opcode::DELEGATECALL | Opcode::STATICCALL => {
+            (40, 700, 700)
+        }
+        Opcode::SELFDESTRUCT => (0, 5_000, 5_000),
+        _ => return opcode.base_gas(),
+    };
+    if spec < SpecId::TangerineWhistle {
+        frontier
+    } else if spec < SpecId::Istanbul {
+        tangerine_whistle
+    } else {
+        istanbul
+    }
+}
+
+/// Dynamic KECCAK256 cost per 32-byte word hashed.
+pub const KECCAK256_WORD_GAS: u64 = 6;
+
+/// Extra cost of a CALL / CALLCODE that transfers a non-zero value.
+pub const CALL_VALUE_GAS: u64 = 9_000;
+
+/// Extra cost of a value-bearing CALL to an account that doesn't exist yet.
+pub const NEW_ACCOUNT_GAS: u64 = 25_000;
+
+/// Gas handed to the callee for free on top of what the caller forwards when value is transferred.
+pub const CALL_STIPEND: u64 = 2_300;
+
+/// Cost per byte of runtime code stored by a successful CREATE / CREATE2.
+pub const CODE_DEPOSIT_GAS: u64 = 200;
+
+/// Cost per 32-byte word of CREATE / CREATE2 init code (EIP-3860, since Shanghai).
+pub const INITCODE_WORD_GAS: u64 = 2;
+
+/// Dynamic cost per 32-byte word copied by CALLDATACOPY, CODECOPY, EXTCODECOPY, RETURNDATACOPY and MCOPY.
+pub const COPY_WORD_GAS: u64 = 3;
+
+/// LOG0..LOG4 cost per topic. Part of their static cost (LOGn is 375 + n * `LOG_TOPIC_GAS`), not charged again.
+pub const LOG_TOPIC_GAS: u64 = 375;
+
+/// Dynamic LOG0..LOG4 cost per byte of data.
+pub const LOG_DATA_GAS: u64 = 8;
+
+/// SSTORE of a zero slot to non-zero.
+pub const SSTORE_SET_GAS: u64 = 20_000;
+
+/// SSTORE changing a non-zero slot, before EIP-2929 split off the cold-access part (2100) in Berlin.
+pub const SSTORE_RESET_GAS: u64 = 5_000;
+
+/// Refund for clearing a slot: 15000 until London, 4800 since (EIP-3529).
+pub fn sstore_clears_refund(spec: SpecId) -> i64 {
+    if spec >= SpecId::London {
+        4_800
+    } else {
+        15_000
+    }
+}
+
+/// Divisor of the refund cap: a transaction gets back at most its gas used over this, a half until London and a
+/// fifth since (EIP-3529).
+pub fn max_refund_quotient(spec: SpecId) -> u64 {
+    if spec >= SpecId::London { 5 } else { 2 }
+}
+
+/// Cost of an SSTORE that doesn't change the slot's original value, or only rewrites a dirty slot:
+/// the warm SLOAD price (EIP-2200 in Istanbul, EIP-2929 since Berlin).
+fn sstore_noop_gas(spec: SpecId) -> u64 {
+    if spec >= SpecId::Berlin { 100 } else { 800 }
+}
+
+fn sstore_reset_gas(spec: SpecId) -> u64 {
+    if spec >= SpecId::Berlin {
+        SSTORE_RESET_GAS - 2_100
+    } else {
+        SSTORE_RESET_GAS
+    }
+}
+
+/// Total SSTORE cost writing `new` over `present`, given the slot's `original` (pre-transaction) value.
+/// Net metering (EIP-2200) since Istanbul; before that a flat set/reset price.
+pub fn sstore_cost(spec: SpecId, original: U256, present: U256, new: U256) -> u64 {
+    if spec < SpecId::Istanbul {
+        return if present.is_zero() && !new.is_zero() {
+            SSTORE_SET_GAS
+        } else {
+            SSTORE_RESET_GAS
+        };
+    }
+    if new == present || original != present {
+        sstore_noop_gas(spec)
+    } else if original.is_zero() {
+        SSTORE_SET_GAS
+    } else {
+        sstore_reset_gas(spec)
+    }
+}
+
+/// Refund counter change for the same write as `sstore_cost`; negative when an earlier refund is taken back.
+pub fn sstore_refund(spec: SpecId, original: U256, present: U256, new: U256) -> i64 {
+    let clears = sstore_clears_refund(spec);
+    if spec < SpecId::Istanbul {
+        return if !present.is_zero() && new.is_zero() {
+            clears
+        } else {
+            0
+        };
+    }
+    if new == present {
+        return 0;
+    }
+    if original == present {
+        return if !original.is_zero() && new.is_zero() {
+            clears
+        } else {
+            0
+        };
+    }
+
+    let mut refund = 0;
+    if !original.is_zero() {
+        if present.is_zero() {
+            refund -= clears;
+        } else if new.is_zero() {
+            refund += clears;
+        }
+    }
+    if original == new {
+        let restored = if original.is_zero() {
+            SSTORE_SET_GAS
+        } else {
+            sstore_reset_gas(spec)
+        };
+        refund += (restored - sstore_noop_gas(spec)) as i64;
+    }
+    refund
+}
+
+/// Most gas a call can forward out of `remaining` (EIP-150): everything but one 64th.
+pub fn all_but_one_64th(remaining: u64) -> u64 {
+    remaining - remaining / 64
+}
+
+/// Total cost of a memory of `words` 32-byte words: `3 * words + words² / 512`.
+pub fn memory_gas(words: u64) -> u64 {
+    words
+        .saturating_mul(3)
+        .saturating_add(words.saturating_mul(words) / 512)
+}
+
+impl Gas {
+    /// A fresh frame with `limit` gas, none spent.
+    pub fn new(limit: u64) -> Self {
+        Gas {
+            limit,
+            remaining: limit,
+            refunded: 0,
+            memory_words: 0,
+        }
+    }
+
+    /// A frame with no effective limit (`u64::MAX`), for read-only calls that don't specify gas
+    /// (RPC `eth_call` without `gas`). Costs are still recorded so `spent()` reports gas used.
+    pub fn unlimited() -> Self {
+        Gas::new(u64::MAX)
+    }
+
+    /// Whether this frame was started with the `u64::MAX` sentinel limit.
+    pub fn is_unlimited(&self) -> bool {
+        self.limit == u64::MAX
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    pub fn refunded(&self) -> i64 {
+        self.refunded
+    }
+
+    /// Gas used so far (`limit - remaining`), before refunds.
+    pub fn spent(&self) -> u64 {
+        self.limit - self.remaining
+    }
+
+    /// Gas used after subtracting the refund counter.
+    pub fn spent_sub_refunded(&self) -> u64 {
+        self.spent().saturating_sub(self.refunded.max(0) as u64)
+    }
+
+    /// Charge `cost`. Returns `false` and leaves the counter untouched when `cost` exceeds what is left.
+    #[must_use]
+    pub fn record_cost(&mut self, cost: u64) -> bool {
+        match self.remaining.checked_sub(cost) {
+            Some(remaining) => {
+                self.remaining = remaining;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Charge for memory growing to cover `end` bytes (`offset + len` of an access).
+    /// Only the difference between the new and the current `memory_gas` is charged; accesses inside the
+    /// already-paid region are free. Returns `false`, charging nothing, when not enough gas is left.
+    #[must_use]
+    pub fn record_memory_expansion(&mut self, end: u64) -> bool {
+        let words = end.div_ceil(32);
+        if words <= self.memory_words {
+            return true;
+        }
+        if !self.record_cost(memory_gas(words) - memory_gas(self.memory_words)) {
+            return false;
+        }
+        self.memory_words = words;
+        true
+    }
+
+    /// Highest memory size paid for so far, in 32-byte words.
+    pub fn memory_words(&self) -> u64 {
+        self.memory_words
+    }
+
+    /// Return `returned` previously charged gas to the frame (e.g. unused gas coming back from a sub-call).
+    pub fn erase_cost(&mut self, returned: u64) {
+        self.remaining += returned;
+        debug_assert!(
+            self.remaining <= self.limit,
+            "erased more gas than was spent: remaining {} > limit {}",
+            self.remaining,
+            self.limit
+        );
+    }
+
+    /// Adjust the refund counter. Individual adjustments may be negative (EIP-2200 un-refunds);
+    /// only the final total has to be non-negative.
+    pub fn record_refund(&mut self, refund: i64) {
+        self.refunded += refund;
+    }
+
+    /// Cap the refund at `spent / max_refund_quotient` (2 before London, 5 from London per EIP-3529).
+    pub fn set_final_refund(&mut self, max_refund_quotient: u64) {
+        debug_assert!(
+            self.refunded >= 0,
+            "final refund counter is negative: {}",
+            self.refunded
+        );
+        let cap = (self.spent() / max_refund_quotient) as i64;
+        self.refunded = self.refunded.clamp(0, cap);
+    }
+
+    /// Charge everything that is left, as exceptional halts do.
+    pub fn spend_all(&mut self) {
+        self.remaining = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn static_gas_follows_the_fork() {
+        let sload: Vec<u64> = [
+            SpecId::Frontier,
+            SpecId::TangerineWhistle,
+            SpecId::Istanbul,
+            SpecId::Berlin,
+        ]
+        .into_iter()
+        .map(|spec| static_gas(Opcode::SLOAD, spec))
+        .collect();
+        assert_eq!(sload, [50, 200, 800, 100]);
+
+        assert_eq!(static_gas(Opcode::CALL, SpecId::Homestead), 40);
+        assert_eq!(static_gas(Opcode::EXTCODESIZE, SpecId::London), 100);
+        assert_eq!(static_gas(Opcode::ADD, SpecId::Frontier), 3);
+        assert_eq!(exp_byte_gas(SpecId::Homestead), 10);
+    }
+
+    #[test]
+    fn record_cost_charges_until_exhausted() {
+        let mut gas = Gas::new(100);
+
+        assert!(gas.record_cost(60));
+        assert!(gas.record_cost(40));
+        assert_eq!(gas.remaining(), 0);
+        assert_eq!(gas.spent(), 100);
+    }
+
+    #[test]
+    fn unlimited_still_meters() {
+        let mut gas = Gas::unlimited();
+
+        assert!(gas.is_unlimited());
+        assert!(gas.record_cost(21_000));
+        assert_eq!(gas.spent(), 21_000);
+        assert!(!Gas::new(100).is_unlimited());
+    }
+
+    #[test]
+    fn memory_expansion_charges_only_growth() {
+        let mut gas = Gas::new(10_000);
+
+        assert!(gas.record_memory_expansion(32));
+        assert_eq!(gas.spent(), 3);
+        assert!(gas.record_memory_expansion(20));
+        assert_eq!(gas.spent(), 3);
+        // 1024 words: 3 * 1024 + 1024² / 512 = 5120 in total.
+        assert!(gas.record_memory_expansion(1024 * 32));
+        assert_eq!(gas.spent(), 5120);
+        assert_eq!(gas.memory_words(), 1024);
+
+        assert!(!gas.record_memory_expansion(u64::MAX));
+        assert_eq!(gas.memory_words(), 1024);
+    }
+
+    #[test]
+    fn failed_charge_leaves_gas_untouched() {
+        let mut gas = Gas::new(10);
+
+        assert!(!gas.record_cost(11));
+        assert_eq!(gas.remaining(), 10);
+    }
+
+    #[test]
+    fn erase_cost_returns_gas() {
+        let mut gas = Gas::new(100);
+        assert!(gas.record_cost(70));
+        gas.erase_cost(30);
+
+        assert_eq!(gas.spent(), 40);
+    }
+
+    #[test]
+    #[should_panic(expected = "erased more gas than was spent")]
+    #[cfg(debug_assertions)]
+    fn erase_cost_beyond_limit_panics_in_debug() {
+        let mut gas = Gas::new(100);
+        gas.erase_cost(1);
+    }
+
+    #[test]
+    fn final_refund_is_capped() {
+        let mut gas = Gas::new(100_000);
+        assert!(gas.record_cost(50_000));
+        gas.record_refund(20_000);
+        gas.record_refund(-5_000);
+
+        gas.set_final_refund(5);
+        assert_eq!(gas.refunded(), 10_000);
+        assert_eq!(gas.spent_sub_refunded(), 40_000);
+
+        gas.record_refund(10_000);
+        gas.set_final_refund(2);
+        assert_eq!(gas.refunded(), 20_000);
+    }
+
+    #[test]
+    fn sstore_net_metering_follows_eip2200_and_eip3529() {
+        let cost = |spec, o: u64, p: u64, n: u64| {
+            let (o, p, n) = (U256::from(o), U256::from(p), U256::from(n));
+            (sstore_cost(spec, o, p, n), sstore_refund(spec, o, p, n))
+        };
+
+        assert_eq!(cost(SpecId::Istanbul, 0, 0, 0), (800, 0));
+        assert_eq!(cost(SpecId::Istanbul, 0, 0, 1), (20_000, 0));
+        assert_eq!(cost(SpecId::Istanbul, 1, 1, 0), (5_000, 15_000));
+        // Dirty slot restored to its original value: the earlier clear refund is taken back.
+        assert_eq!(cost(SpecId::Istanbul, 1, 0, 1), (800, -15_000 + 4_200));
+        assert_eq!(cost(SpecId::Istanbul, 0, 1, 0), (800, 19_200));
+
+        assert_eq!(cost(SpecId::London, 1, 1, 0), (2_900, 4_800));
+        assert_eq!(cost(SpecId::London, 0, 1, 0), (100, 19_900));
+
+        assert_eq!(cost(SpecId::Petersburg, 0, 0, 1), (20_000, 0));
+        assert_eq!(cost(SpecId::Petersburg, 1, 1, 0), (5_000, 15_000));
+        assert_eq!(cost(SpecId::Petersburg, 1, 2, 2), (5_000, 0));
+    }
+}
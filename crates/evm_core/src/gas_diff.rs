@@ -0,0 +1,190 @@
+//! Differential gas report: run the same prepared `Evm` under two forks and compare what each opcode cost.
+//!
+//! Useful to see how a fork changes a contract's costs, and which opcodes stop working (they trap as
+//! invalid under a fork that hasn't activated them).
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+
+use crate::{
+    Evm, ProgramExitStatus,
+    spec::SpecId,
+    tracer::{TraceSink, Tracer},
+};
+
+/// Executions and total gas of one opcode in one run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct OpGas {
+    pub count: u64,
+    pub gas: u64,
+}
+
+/// One opcode's cost under both forks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpGasDiff {
+    pub op_name: String,
+    pub base: OpGas,
+    pub target: OpGas,
+}
+
+impl OpGasDiff {
+    /// `target.gas - base.gas`.
+    pub fn delta(&self) -> i128 {
+        self.target.gas as i128 - self.base.gas as i128
+    }
+}
+
+/// Result of `gas_diff`. `ops` is sorted by opcode name.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasDiffReport {
+    pub base_spec: SpecId,
+    pub target_spec: SpecId,
+    pub base_status: ProgramExitStatus,
+    pub target_status: ProgramExitStatus,
+    pub base_total: u64,
+    pub target_total: u64,
+    pub ops: Vec<OpGasDiff>,
+}
+
+impl GasDiffReport {
+    /// `target_total - base_total`.
+    pub fn total_delta(&self) -> i128 {
+        self.target_total as i128 - self.base_total as i128
+    }
+}
+
+impl fmt::Display for GasDiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let base = format!("{:?}", self.base_spec);
+        let target = format!("{:?}", self.target_spec);
+        writeln!(
+            f,
+            "{:<14} {:>8} {:>12} {:>8} {:>12} {:>10}",
+            "opcode", "count", base, "count", target, "delta"
+        )?;
+        for op in &self.ops {
+            writeln!(
+                f,
+                "{:<14} {:>8} {:>12} {:>8} {:>12} {:>+10}",
+                op.op_name,
+                op.base.count,
+                op.base.gas,
+                op.target.count,
+                op.target.gas,
+                op.delta()
+            )?;
+        }
+        writeln!(
+            f,
+            "{:<14} {:>8} {:>12} {:>8} {:>12} {:>+10}",
+            "total",
+            "",
+            self.base_total,
+            "",
+            self.target_total,
+            self.total_delta()
+        )?;
+        write!(
+            f,
+            "status: {base} {:?}, {target} {:?}",
+            self.base_status, self.target_status
+        )
+    }
+}
+
+/// Run a copy of `evm` to completion under `spec`, returning its final state and per-opcode gas.
+fn run_under(evm: &Evm, spec: SpecId) -> (Evm, BTreeMap<String, OpGas>) {
+    let per_op = Arc::new(Mutex::new(BTreeMap::<String, OpGas>::new()));
+    let sink = Arc::clone(&per_op);
+    let mut tracer = Tracer::new(TraceSink::Callback(Box::new(move |step| {
+        let mut per_op = sink.lock().unwrap();
+        let entry = per_op.entry(step.op_name.clone()).or_default();
+        entry.count += 1;
+        entry.gas += step.gas_cost;
+    })));
+
+    let mut evm = evm.clone().with_spec(spec);
+    // A callback sink never fails.
+    let _ = evm.run_traced(&mut tracer);
+    drop(tracer);
+
+    let per_op = Arc::try_unwrap(per_op)
+        .expect("tracer dropped")
+        .into_inner()
+        .unwrap();
+    (evm, per_op)
+}
+
+/// Run `evm` (with its code already loaded, see `Evm::execute`) once under `base` and once under `target`,
+/// and compare gas per opcode and in total. `evm` itself is left untouched.
+pub fn gas_diff(evm: &Evm, base: SpecId, target: SpecId) -> GasDiffReport {
+    let (base_run, base_ops) = run_under(evm, base);
+    let (target_run, target_ops) = run_under(evm, target);
+
+    let mut ops: BTreeMap<String, OpGasDiff> = BTreeMap::new();
+    for (op_name, gas) in base_ops {
+        ops.entry(op_name.clone())
+            .or_insert_with(|| OpGasDiff {
+                op_name,
+                base: OpGas::default(),
+                target: OpGas::default(),
+            })
+            .base = gas;
+    }
+    for (op_name, gas) in target_ops {
+        ops.entry(op_name.clone())
+            .or_insert_with(|| OpGasDiff {
+                op_name,
+                base: OpGas::default(),
+                target: OpGas::default(),
+            })
+            .target = gas;
+    }
+
+    GasDiffReport {
+        base_spec: base,
+        target_spec: target,
+        base_status: base_run.status,
+        target_status: target_run.status,
+        base_total: base_run.gas.spent(),
+        target_total: target_run.gas.spent(),
+        ops: ops.into_values().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use primitives::bytecode::Bytecode;
+
+    use super::*;
+
+    #[test]
+    fn push0_diverges_before_shanghai() {
+        // PUSH0; PUSH1 1; ADD; STOP
+        let evm = Evm {
+            code: Bytecode::new(vec![0x5F, 0x60, 0x01, 0x01, 0x00]),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(1_000));
+
+        let report = gas_diff(&evm, SpecId::London, SpecId::Shanghai);
+
+        assert!(matches!(report.base_status, ProgramExitStatus::Halt(_)));
+        assert!(report.target_status.is_success());
+        assert_eq!(report.target_total, 2 + 3 + 3);
+        let push0 = report.ops.iter().find(|op| op.op_name == "PUSH0").unwrap();
+        assert_eq!(push0.base.count, 1);
+        assert_eq!(push0.target, OpGas { count: 1, gas: 2 });
+        let add = report.ops.iter().find(|op| op.op_name == "ADD").unwrap();
+        assert_eq!(add.base, OpGas::default());
+        assert_eq!(add.delta(), 3);
+        assert!(report.to_string().contains("total"));
+    }
+}
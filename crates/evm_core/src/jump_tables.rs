@@ -1,11 +1,53 @@
-use crate::{Evm, opcodes::Opcode, operations::ariths::*};
+use std::sync::OnceLock;
 
-pub type OpcodeFn = fn(&mut Evm);
+use primitives::errors::EvmErrors;
 
-pub fn noop(_evm: &mut Evm) {}
+use crate::{Evm, opcodes::Opcode, operations::ariths::*, spec::SpecId};
 
-pub fn build_jump_table() -> [OpcodeFn; 256] {
-    let mut jump_table: [fn(&mut Evm); 256] = [noop as OpcodeFn; 256];
+/// An opcode handler. Errors halt the run (see `Evm::step`) instead of panicking.
+pub type OpcodeFn = fn(&mut Evm) -> Result<(), EvmErrors>;
+
+/// Handlers indexed by opcode byte.
+pub type JumpTable = [OpcodeFn; 256];
+
+/// Handler for defined opcodes this crate doesn't implement yet: they fail like undefined bytes rather than
+/// being skipped.
+pub fn unimplemented(_evm: &mut Evm) -> Result<(), EvmErrors> {
+    Err(EvmErrors::InvalidOpcode)
+}
+
+/// Handler for opcodes the running spec hasn't activated: they behave like undefined bytes.
+pub fn not_activated(_evm: &mut Evm) -> Result<(), EvmErrors> {
+    Err(EvmErrors::InvalidOpcode)
+}
+
+/// Array of `$handler::<n>` for each listed `n`, for opcode families generic over their index.
+macro_rules! family {
+    ($handler:ident: $($n:literal)*) => {
+        [$($handler::<$n> as OpcodeFn),*]
+    };
+}
+
+/// `PUSH1..=PUSH32` handlers, indexed by `n - 1`.
+const PUSHES: [OpcodeFn; 32] = family!(push:
+    1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32
+);
+/// `DUP1..=DUP16` handlers, indexed by `n - 1`.
+const DUPS: [OpcodeFn; 16] = family!(dup: 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16);
+/// `SWAP1..=SWAP16` handlers, indexed by `n - 1`.
+const SWAPS: [OpcodeFn; 16] = family!(swap: 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16);
+/// `LOG0..=LOG4` handlers, indexed by topic count.
+const LOGS: [OpcodeFn; 5] = family!(log: 0 1 2 3 4);
+
+/// Every handler this crate implements, regardless of fork, with `unimplemented` everywhere else. Start from this
+/// to patch in custom opcodes.
+pub fn build_jump_table() -> JumpTable {
+    jump_table_with(unimplemented)
+}
+
+/// The registered handlers, with `fallback` for every byte that has none.
+fn jump_table_with(fallback: OpcodeFn) -> JumpTable {
+    let mut jump_table: JumpTable = [fallback; 256];
     jump_table[Opcode::STOP as usize] = stop;
     jump_table[Opcode::ADD as usize] = add;
     jump_table[Opcode::SUB as usize] = sub;
@@ -14,9 +56,220 @@ pub fn build_jump_table() -> [OpcodeFn; 256] {
     jump_table[Opcode::SDIV as usize] = sdiv;
     jump_table[Opcode::SMOD as usize] = smod;
     jump_table[Opcode::MOD as usize] = modulo;
-    // jump_table[Opcode::MLOAD as usize] = m_load;
-    // jump_table[Opcode::CHAINID as usize] = chain_id;
-    // jump_table[Opcode::COINBASE as usize] = coin_base;
+    jump_table[Opcode::ADDMOD as usize] = addmod;
+    jump_table[Opcode::MULMOD as usize] = mulmod;
+    jump_table[Opcode::EXP as usize] = exp;
+    jump_table[Opcode::SIGNEXTEND as usize] = signextend;
+    jump_table[Opcode::LT as usize] = lt;
+    jump_table[Opcode::GT as usize] = gt;
+    jump_table[Opcode::SLT as usize] = slt;
+    jump_table[Opcode::SGT as usize] = sgt;
+    jump_table[Opcode::EQ as usize] = eq;
+    jump_table[Opcode::ISZERO as usize] = is_zero;
+    jump_table[Opcode::AND as usize] = and;
+    jump_table[Opcode::OR as usize] = or;
+    jump_table[Opcode::XOR as usize] = xor;
+    jump_table[Opcode::NOT as usize] = not;
+    jump_table[Opcode::BYTE as usize] = byte;
+    jump_table[Opcode::SHL as usize] = shl;
+    jump_table[Opcode::SHR as usize] = shr;
+    jump_table[Opcode::SAR as usize] = sar;
+    jump_table[Opcode::KECCAK256 as usize] = keccak_256;
+    jump_table[Opcode::ADDRESS as usize] = address;
+    jump_table[Opcode::BALANCE as usize] = balance;
+    jump_table[Opcode::ORIGIN as usize] = origin;
+    jump_table[Opcode::CALLER as usize] = caller;
+    jump_table[Opcode::CALLVALUE as usize] = call_value;
+    jump_table[Opcode::CALLDATALOAD as usize] = call_data_load;
+    jump_table[Opcode::CALLDATASIZE as usize] = call_data_size;
+    jump_table[Opcode::CALLDATACOPY as usize] = call_data_copy;
+    jump_table[Opcode::CODESIZE as usize] = code_size;
+    jump_table[Opcode::CODECOPY as usize] = code_copy;
+    jump_table[Opcode::EXTCODESIZE as usize] = ext_code_size;
+    jump_table[Opcode::GASPRICE as usize] = gas_price;
+    jump_table[Opcode::EXTCODECOPY as usize] = ext_code_copy;
+    jump_table[Opcode::RETURNDATASIZE as usize] = return_data_size;
+    jump_table[Opcode::RETURNDATACOPY as usize] = return_data_copy;
+    jump_table[Opcode::EXTCODEHASH as usize] = ext_code_hash;
+    jump_table[Opcode::BLOCKHASH as usize] = block_hash;
+    jump_table[Opcode::COINBASE as usize] = coin_base;
+    jump_table[Opcode::TIMESTAMP as usize] = timestamp;
+    jump_table[Opcode::NUMBER as usize] = number;
+    jump_table[Opcode::GASLIMIT as usize] = gas_limit;
+    jump_table[Opcode::CHAINID as usize] = chain_id;
+    jump_table[Opcode::SELFBALANCE as usize] = self_balance;
+    jump_table[Opcode::BASEFEE as usize] = base_fee;
+    jump_table[Opcode::POP as usize] = pop;
+    jump_table[Opcode::MLOAD as usize] = m_load;
+    jump_table[Opcode::MSTORE as usize] = m_store;
+    jump_table[Opcode::MSTORE8 as usize] = m_store8;
+    jump_table[Opcode::SLOAD as usize] = s_load;
+    jump_table[Opcode::SSTORE as usize] = s_store;
+    jump_table[Opcode::TLOAD as usize] = t_load;
+    jump_table[Opcode::TSTORE as usize] = t_store;
+    jump_table[Opcode::JUMP as usize] = jump;
+    jump_table[Opcode::JUMPI as usize] = jumpi;
+    jump_table[Opcode::PC as usize] = pc;
+    jump_table[Opcode::MSIZE as usize] = m_size;
+    jump_table[Opcode::GAS as usize] = gas;
+    jump_table[Opcode::JUMPDEST as usize] = jump_dest;
+    jump_table[Opcode::MCOPY as usize] = m_copy;
+    jump_table[Opcode::PUSH0 as usize] = push_0;
+    jump_table[Opcode::CREATE as usize] = create;
+    jump_table[Opcode::CALL as usize] = call;
+    jump_table[Opcode::CALLCODE as usize] = call_code;
+    jump_table[Opcode::RETURN as usize] = ret;
+    jump_table[Opcode::DELEGATECALL as usize] = delegate_call;
+    jump_table[Opcode::CREATE2 as usize] = create2;
+    jump_table[Opcode::STATICCALL as usize] = static_call;
+    jump_table[Opcode::REVERT as usize] = revert;
+    jump_table[Opcode::INVALID as usize] = invalid;
+    for (n, handler) in PUSHES.into_iter().enumerate() {
+        jump_table[Opcode::PUSH1 as usize + n] = handler;
+    }
+    for (n, handler) in DUPS.into_iter().enumerate() {
+        jump_table[Opcode::DUP1 as usize + n] = handler;
+    }
+    for (n, handler) in SWAPS.into_iter().enumerate() {
+        jump_table[Opcode::SWAP1 as usize + n] = handler;
+    }
+    for (n, handler) in LOGS.into_iter().enumerate() {
+        jump_table[Opcode::LOG0 as usize + n] = handler;
+    }
+
+    jump_table
+}
 
+/// Defined opcodes that have a handler in `build_jump_table()`, in byte order. Bytes without one fall through to
+/// `unimplemented`, so this is what a conformance report counts as implemented.
+pub fn implemented_opcodes() -> Vec<Opcode> {
+    // A fallback whose body matches no handler, so the compiler can't merge it with one (as it could
+    // `unimplemented` with `invalid`) and the address comparison stays exact.
+    fn unregistered(_evm: &mut Evm) -> Result<(), EvmErrors> {
+        unreachable!("only compared by address")
+    }
+    let jump_table = jump_table_with(unregistered);
+    (0..=255u8)
+        .filter_map(Opcode::from_u8)
+        .filter(|opcode| {
+            !std::ptr::fn_addr_eq(jump_table[*opcode as usize], unregistered as OpcodeFn)
+        })
+        .collect()
+}
+
+/// `build_jump_table()` with every opcode `spec` hasn't activated (see `OpcodeInfo::introduced`) swapped for
+/// `not_activated`, e.g. `PUSH0` before Shanghai.
+pub fn build_jump_table_for(spec: SpecId) -> JumpTable {
+    let mut jump_table = build_jump_table();
+    for (byte, handler) in jump_table.iter_mut().enumerate() {
+        let Some(opcode) = Opcode::from_u8(byte as u8) else {
+            continue;
+        };
+        if opcode
+            .info()
+            .introduced
+            .is_none_or(|introduced| introduced > spec)
+        {
+            *handler = not_activated;
+        }
+    }
     jump_table
 }
+
+/// The jump table for `spec`, built on first use and shared by every `Evm` after that.
+pub fn jump_table(spec: SpecId) -> &'static JumpTable {
+    static TABLES: [OnceLock<JumpTable>; SpecId::COUNT] =
+        [const { OnceLock::new() }; SpecId::COUNT];
+    TABLES[spec as usize].get_or_init(|| build_jump_table_for(spec))
+}
+
+#[cfg(test)]
+mod tests {
+    use primitives::bytecode::Bytecode;
+
+    use super::*;
+    use crate::ProgramExitStatus;
+
+    #[test]
+    fn tables_are_cached_per_spec() {
+        assert!(std::ptr::eq(
+            jump_table(SpecId::Cancun),
+            jump_table(SpecId::Cancun)
+        ));
+        assert!(!std::ptr::eq(
+            jump_table(SpecId::Cancun),
+            jump_table(SpecId::London)
+        ));
+    }
+
+    #[test]
+    fn opcodes_from_later_forks_are_not_activated() {
+        let push0 = Opcode::PUSH0 as usize;
+        let mut evm = Evm::default();
+
+        assert_eq!(
+            jump_table(SpecId::London)[push0](&mut evm),
+            Err(EvmErrors::InvalidOpcode)
+        );
+        assert_eq!(jump_table(SpecId::Shanghai)[push0](&mut evm), Ok(()));
+        assert_eq!(evm.stack.len(), 1);
+    }
+
+    #[test]
+    fn fork_gated_opcodes_run_from_their_fork_on() {
+        // (code, fork that introduced its last opcode, the fork before it)
+        let cases = [
+            // PUSH1 0; DUP1; DUP1; MCOPY; STOP
+            (
+                vec![0x60, 0x00, 0x80, 0x80, 0x5E, 0x00],
+                SpecId::Cancun,
+                SpecId::Shanghai,
+            ),
+            // PUSH1 1; PUSH1 0; TSTORE; STOP
+            (
+                vec![0x60, 0x01, 0x60, 0x00, 0x5D, 0x00],
+                SpecId::Cancun,
+                SpecId::Shanghai,
+            ),
+            // BASEFEE; STOP
+            (vec![0x48, 0x00], SpecId::London, SpecId::Berlin),
+            // CHAINID; SELFBALANCE; STOP
+            (vec![0x46, 0x47, 0x00], SpecId::Istanbul, SpecId::Petersburg),
+        ];
+        for (code, introduced, before) in cases {
+            let run = |spec| {
+                let mut evm = Evm {
+                    code: Bytecode::new(code.clone()),
+                    ..Default::default()
+                }
+                .with_spec(spec);
+                evm.run();
+                evm.status
+            };
+
+            assert_eq!(
+                run(before),
+                ProgramExitStatus::Halt(EvmErrors::InvalidOpcode),
+                "{code:02x?} under {before:?}"
+            );
+            assert!(
+                run(introduced).is_success(),
+                "{code:02x?} under {introduced:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn implemented_opcodes_skip_unregistered_bytes() {
+        let implemented = implemented_opcodes();
+
+        assert!(implemented.contains(&Opcode::ADD));
+        assert!(implemented.contains(&Opcode::PUSH32));
+        assert!(implemented.contains(&Opcode::INVALID));
+        assert!(!implemented.contains(&Opcode::EXTCALL));
+        assert_eq!(
+            build_jump_table()[Opcode::EXTCALL as usize](&mut Evm::default()),
+            Err(EvmErrors::InvalidOpcode)
+        );
+    }
+}
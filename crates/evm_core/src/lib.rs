@@ -1,41 +1,147 @@
+//! EVM interpreter core.
+//!
+//! The stable public surface is what `prelude` re-exports; `use evm_core::prelude::*;` is the supported
+//! way in. Modules such as `jump_tables` and `operations` stay public for experimentation (custom
+//! handlers, tests) but may change between releases.
+
+pub mod access;
+pub mod call_frame;
+pub mod call_inputs;
+pub mod checkpoint;
+pub mod erc7562;
+pub mod gas;
+pub mod gas_diff;
 pub mod jump_tables;
+pub mod limits;
+mod macros;
+pub mod op_stats;
 pub mod opcodes;
+pub mod precompiles;
+pub mod prelude;
+pub mod spec;
+pub mod tracer;
 pub mod operations {
     pub mod ariths;
 }
 
-use alloy::primitives::Address;
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+};
+
+use alloy::primitives::{Address, Bytes, Log, U256};
 use primitives::{
-    evm_types::{BlockEnv, EvmStorage, Transaction},
+    bytecode::Bytecode,
+    errors::EvmErrors,
+    evm_types::{BlockEnv, EvmStorage, StateDiff, Transaction},
+    journal::{JournalCheckpoint, JournaledState},
     memory::Memory,
     stack::Stack,
 };
+use serde::{Deserialize, Serialize};
 
-use crate::{jump_tables::build_jump_table, opcodes::Opcode};
+use crate::{
+    access::AccessSet,
+    call_frame::{CallContext, CallStack},
+    gas::{Gas, max_refund_quotient, static_gas},
+    jump_tables::jump_table,
+    limits::{Limits, Profile},
+    opcodes::Opcode,
+    spec::SpecId,
+};
 
 /// Program exit states for the VM. The VM loop (`run`) uses this to determine when to stop.
-/// - `Success` indicates the program stopped successfully (for example via `STOP` opcode).
-/// - `Failure` indicates a trap/exception (e.g. invalid opcode, maybe an out-of-gas ).
+/// - `Success` indicates the program halted normally: `STOP` or the end of the code (empty `output`), or `RETURN`
+///   (`output` is the memory slice it handed back, also kept in `Evm::return_data`). `gas_used` is what the frame
+///   had spent when it halted, before refunds.
+/// - `Revert` indicates the program ended with `REVERT`; `output` is the revert data, also kept in
+///   `Evm::return_data`.
+/// - `Halt` indicates an exceptional halt, which consumes all gas left in the frame, with the reason: `OutOfGas`,
+///   `StackUnderflow`, `StackOverflow`, `InvalidOpcode` (undefined, unimplemented or `INVALID`), `InvalidJump`,
+///   `StateChangeDuringStaticCall`, ...
+/// - `InstructionBudgetExhausted` indicates the run hit `Evm::instruction_budget` before halting.
+/// - `Rejected` indicates the transaction was refused before any code ran (e.g. calldata over `Limits`).
+/// - `InternalError` indicates the interpreter itself panicked during `run_catching`; it carries the panic message.
 /// - `Default` means "still running" or uninitialized status; the run loop continues while status is `Default`.
-#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub enum ProgramExitStatus {
-    Success,
-    Failure,
+    Success {
+        output: Bytes,
+        gas_used: u64,
+    },
+    Revert {
+        output: Bytes,
+    },
+    Halt(EvmErrors),
+    InstructionBudgetExhausted,
+    Rejected(EvmErrors),
+    InternalError(String),
     #[default]
     Default,
 }
 
+impl ProgramExitStatus {
+    pub fn is_success(&self) -> bool {
+        matches!(self, ProgramExitStatus::Success { .. })
+    }
+
+    /// Output of `RETURN` or `REVERT`; empty for every other status.
+    pub fn output(&self) -> &[u8] {
+        match self {
+            ProgramExitStatus::Success { output, .. } | ProgramExitStatus::Revert { output } => {
+                output
+            }
+            _ => &[],
+        }
+    }
+}
+
+/// A trap recorded while running in recovery mode (see `Evm::recover_traps`).
+/// - `pc`: position of the offending instruction.
+/// - `opcode`: the raw byte found at `pc`.
+/// - `error`: why the instruction could not execute (e.g. `InvalidOpcode`, `StackUnderflow`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trap {
+    pub pc: usize,
+    pub opcode: u8,
+    pub error: EvmErrors,
+}
+
+/// Outcome of `Evm::transact` / `Evm::transact_commit`.
+/// - `status`: how the run ended.
+/// - `state_diff`: every account and slot the transaction changed.
+/// - `gas_used`: gas spent by the frame, reported even when it ran with unlimited gas. A successful transaction
+///   has its refund counter (capped by `gas::max_refund_quotient`) taken off; a failed one keeps no refund.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactResult {
+    pub status: ProgramExitStatus,
+    pub state_diff: StateDiff,
+    pub gas_used: u64,
+}
+
 /// The EVM runtime structure.
 ///
 /// This struct aggregates all pieces of state the interpreter needs to execute bytecode:
 /// - `block_env`: on-chain block/environment information used by environment opcodes (timestamp, number, coinbase,)
 /// - `tx`: the transaction context (caller, callee, call value, calldata). This crate uses `tx.data` for code when
 ///   `tx.to == Address::ZERO` (contract creation / init-style behavior).
+/// - `context`: caller, address, value and calldata of the executing frame.
+/// - `code`: the bytecode being executed, kept apart from `memory` so a program can't overwrite its own code.
 /// - `memory`: linear byte-addressable memory used by MSTORE/MLOAD and other memory ops.
+/// - `return_data`: output of the last `RETURN` / `REVERT`.
 /// - `stack`: the 1024-deep evaluation stack used by all stack-based opcodes.
 /// - `storage`: persistent per-account contract storage accessible via SLOAD/SSTORE (map keyed by Address).
-/// - `pc`: program counter (index into `memory` where current instruction is read).
+/// - `pc`: program counter (index into `code` where current instruction is read).
 /// - `status`: current program exit status (controls `run()` loop).
+/// - `recover_traps`: continue-on-trap mode for analysis tooling.
+/// - `traps`: traps recorded while `recover_traps` is enabled.
+/// - `instruction_budget` / `instructions_executed`: optional fuel limit for gas-free runs.
+/// - `gas`: gas accounting for the current frame.
+/// - `spec`: the fork whose rules apply; selects the jump table.
+/// - `limits`: sandbox caps (payload sizes, memory, call depth, deployed code size, denied opcodes).
+/// - `call_stack`: callers suspended while a nested call runs.
+/// - `accessed`: accounts and slots touched so far in the transaction (EIP-2929 warm/cold pricing).
+/// - `created`: the account a creation transaction is deploying to, while its init code runs.
 ///
 /// - `block_env: BlockEnv`
 ///     - Contains block-scoped values such as `number`, `timestamp`, `coinbase`, `gas_limit`, `base_fee`, `block_hash`, `chain_id`.
@@ -45,50 +151,116 @@ pub enum ProgramExitStatus {
 ///     - Transaction-level context: `from` (caller), `to` (destination), `value` (wei), `nonce`, `data` (calldata or init code), and `gas_limit`.
 ///     - Used by CALL* opcodes, `CALLVALUE`, `CALLER`, `CALLDATALOAD`, and for contract creation the `data` can be treated as creation code.
 ///     - Example: when testing a contract call that sends 1 ether, set `tx.value = U256::from(1_000_000_000_000_000_000u128)` and `tx.from` to the caller address.
+/// - `context: CallContext`
+///     - What `CALLER`, `ADDRESS`, `CALLVALUE` and the calldata opcodes see, and whose storage `SLOAD`/`SSTORE` use.
+///     - `execute()` fills it from `tx` for the top-level frame; CALL-family opcodes replace it for the callee.
+///     - `tx` keeps describing the whole transaction (e.g. `ORIGIN` reads `tx.from` at any depth).
+/// - `code: Bytecode`
+///     - Loaded by `execute()`: `tx.data` for creation, the callee's code (including `etch` overrides) for calls.
+///     - Instructions and `PUSHn` immediates are read from here only; MSTORE and friends never touch it.
 /// - `memory: Memory`
 ///     - Linear, zero-indexed byte array that is transient during execution (not persisted between transactions).
 ///     - Used by `MSTORE`, `MLOAD`, `CALLDATACOPY`, `CODECOPY`, etc.
 ///     - Important: this implementation expects memory to have sufficient length before reads/writes.
 ///     - Example: to store a 32-byte word at offset 0 call `MSTORE` with offset `0` and the word; `memory.store_word(0, word)` writes 32 bytes starting at `memory.data[0]`.
+/// - `return_data: Vec<u8>`
+///     - The memory slice `RETURN` or `REVERT` copied out, e.g. the runtime code of a creation or an ABI-encoded revert reason.
+///     - Empty until one of them runs; `STOP` leaves it untouched.
 /// - `stack: Stack`
 ///     - LIFO stack that holds 256-bit values (`U256`). EVM opcodes push/pop values here.
 ///     - Typical opcodes: `PUSH1..PUSH32` push values, arithmetic opcodes `ADD,SUB` pop operands and push results, `POP` discards top value.
 ///     - Example: after `PUSH1 0x05; PUSH1 0x03; ADD`, the top of the stack contains `0x08`.
-/// - `storage: EvmStorage`
-///     - Persistent mapping from account address -> account storage (account fields include `code`, `balance`, `word` map).
+/// - `storage: JournaledState`
+///     - The world state (`EvmStorage`, readable through `Deref`) plus an undo log of what execution changed, so a
+///       failed or reverted callee is rolled back by `revert_to` instead of restoring a copy (see `primitives::journal`).
+///     - Also holds the transaction's transient storage (TLOAD/TSTORE); `execute()` starts it empty.
+///     - Persistent mapping from account address -> account (`info` holds `balance`, `nonce`, `code_hash`; slots live in the account `storage` map and code in the shared `codes` store keyed by hash).
 ///     - Used by `SLOAD`/`SSTORE` to persist contract state across transactions. Must be keyed by the contract address that is being executed.
 ///     - Example: after `SSTORE` of key `k` to value `v` for contract address `A`, subsequent calls to the same contract can read it with `SLOAD` and get `v`.
 /// - `pc: usize`
-///     - Program counter (index into `memory.data` where the next opcode byte is read).
+///     - Program counter (index into `code` where the next opcode byte is read).
 ///     - `pc` must point at the first byte of an opcode. For `PUSHn` opcodes handlers must advance `pc` by the size of immediates they consumed (or set `pc` appropriately); the `step()` here increments by 1 after the handler by design so handlers that mutate `pc` should account for that.
 /// - `status: ProgramExitStatus`
-///     - Controls the `run()` loop. When a handler sets `status` to `Success` or `Failure`, `run()` will stop.
+///     - Controls the `run()` loop. Once a handler sets anything but `Default` (`Success`, `Revert`, `Halt`, ...),
+///       `run()` will stop.
+/// - `recover_traps: bool`
+///     - When `false` (default) a trap (undefined opcode, stack underflow, or any handler error such as a bad jump)
+///       sets `status` to `Halt` and ends the run.
+///     - When `true` the trap is pushed onto `traps`, the offending instruction is skipped and its stack outputs are
+///       patched with zeros, so disassembler/coverage tooling can make a "best effort" pass over adversarial bytecode.
+///       Running out of gas still ends the run.
+/// - `traps: Vec<Trap>`
+///     - Every trap hit in recovery mode, in execution order.
+/// - `instruction_budget: Option<u64>` and `instructions_executed: u64`
+///     - "No-gas mode" for teaching: with a budget set, `step()` refuses to run more than `instruction_budget`
+///       instructions and sets `status` to `InstructionBudgetExhausted`, so examples without gas setup can't hang.
+///     - `None` (default) means unlimited. Set it with `Evm::with_instruction_budget`.
+/// - `gas: Gas`
+///     - Remaining gas, refunds and spent gas for the executing frame; `Evm::new` starts it at `tx.gas_limit`.
+///     - A `tx.gas_limit` of `u64::MAX` or more is the unlimited-gas sentinel (see `Gas::unlimited`);
+///       `with_gas_limit(None)` selects it explicitly for `eth_call`-style runs.
+///     - Handlers and call logic charge through `Gas::record_cost` / `Gas::erase_cost` rather than raw arithmetic.
+/// - `spec: SpecId`
+///     - Defaults to Cancun. Opcodes introduced after `spec` trap as `InvalidOpcode` (see `jump_tables::jump_table`),
+///       and static and dynamic gas follow the fork's schedule (see `gas::static_gas`).
+///     - Set it with `Evm::with_spec`.
+/// - `limits: Limits`
+///     - Optional caps on calldata and return-data size, memory, call depth and deployed code size, and opcodes to
+///       refuse, for embedders exposed to arbitrary payloads. None by default.
+///     - Set them with `Evm::with_limits`, or pick a preset (strict, mainnet, permissive) with `Evm::with_profile`.
+/// - `call_stack: CallStack`
+///     - While a CALL-family callee runs, its callers wait here with their memory, stack, pc and gas
+///       (see `call_frame`). Empty while the top-level frame executes; at most `CALL_DEPTH_LIMIT` deep.
+/// - `logs: Vec<Log>`
+///     - Logs emitted by LOG0..LOG4 in the current transaction, oldest first; read them after `run()`.
+///     - `execute()` clears them. Logs of a callee that fails or reverts are dropped, as are all of them when
+///       `transact_commit` sees the transaction fail or revert.
+/// - `accessed: AccessSet`
+///     - Warm accounts and slots (see `access`). `execute()` resets it for the new transaction; since Berlin,
+///       touching anything outside it costs the cold price.
+/// - `created: Option<Address>`
+///     - Set by `execute()` for a creation transaction (`tx.to == Address::ZERO`) to the new account, whose code
+///       is deployed from the init code's output when the top-level frame ends (see `call_frame`).
 ///
 /// The EVM struct stores all of the runtime state required to fetch, decode, and execute opcodes.
 #[derive(Debug, Clone, Default)]
 pub struct Evm {
     pub block_env: BlockEnv,
     pub tx: Transaction,
+    pub context: CallContext,
+    pub code: Bytecode,
     pub memory: Memory,
+    pub return_data: Vec<u8>,
     pub stack: Stack,
-    pub storage: EvmStorage,
+    pub storage: JournaledState,
     pub pc: usize,
     pub status: ProgramExitStatus,
+    pub recover_traps: bool,
+    pub traps: Vec<Trap>,
+    pub instruction_budget: Option<u64>,
+    pub instructions_executed: u64,
+    pub gas: Gas,
+    pub spec: SpecId,
+    pub limits: Limits,
+    pub call_stack: CallStack,
+    pub logs: Vec<Log>,
+    pub accessed: AccessSet,
+    pub created: Option<Address>,
 }
 
 impl Evm {
     /// Construct a new EVM instance.
-
+    ///
     /// - `block_env`: pass the block environment you want opcodes to observe. For tests set values explicitly:
-    ///     BlockEnv { number: U256::from(123), timestamp: U256::from(1_700_000_000), coinbase: addr, gas_limit: U256::from(30_000_000), ... }
+    ///   BlockEnv { number: U256::from(123), timestamp: U256::from(1_700_000_000), coinbase: addr, gas_limit: U256::from(30_000_000), ... }
     /// - `tx`: transaction payload. For contract creation put creation bytecode in `tx.data` and `tx.to = Address::ZERO`.
-    /// - `memory`: linear memory buffer. It's acceptable to provide a pre-allocated buffer (e.g. 1 KiB) for convenience.
+    /// - `memory`: linear memory buffer. `Memory::new()` is enough: it grows word by word as opcodes touch it.
     /// - `stack`: initial stack - normally empty, but tests may pre-populate it for synthetic runs.
     /// - `storage`: the node's account storage map. Provide pre-existing accounts if needed (e.g. balances, code).
     ///
     /// Example usage:
-    /// ```
-    /// let evm = Evm::new(block_env, tx, Memory::new_with_data(vec![0u8;1024]), Stack::default(), EvmStorage::default());
+    /// ```ignore
+    /// let evm = Evm::new(block_env, tx, Memory::new(), Stack::default(), EvmStorage::default());
     /// ```
     pub fn new(
         block_env: BlockEnv,
@@ -97,79 +269,286 @@ impl Evm {
         stack: Stack,
         storage: EvmStorage,
     ) -> Self {
+        let gas = Gas::new(tx.gas_limit.saturating_to());
         Evm {
             block_env,
             tx,
+            context: CallContext::default(),
+            code: Bytecode::default(),
             memory,
+            return_data: Vec::new(),
             stack,
-            storage,
+            storage: JournaledState::new(storage),
             pc: 0,
             status: ProgramExitStatus::default(),
+            recover_traps: false,
+            traps: Vec::new(),
+            instruction_budget: None,
+            instructions_executed: 0,
+            gas,
+            spec: SpecId::default(),
+            limits: Limits::default(),
+            call_stack: CallStack::default(),
+            logs: Vec::new(),
+            accessed: AccessSet::default(),
+            created: None,
         }
     }
 
-    /// What this `execute()` currently does (implementation-specific):
-    /// - If `tx.to == Address::ZERO` (contract creation / deployment), it attempts to copy `tx.data` into memory so the init code is available for execution.
-    /// - If `tx.to != Address::ZERO`, it attempts to load the touched contract's `code` from `storage` into memory.
+    /// Run under `spec`'s rules instead of the default (Cancun).
+    ///
+    /// Example usage:
+    /// ```ignore
+    /// let mut evm = Evm::new(block_env, tx, memory, stack, storage).with_spec(SpecId::Shanghai);
+    /// ```
+    pub fn with_spec(mut self, spec: SpecId) -> Self {
+        self.spec = spec;
+        self
+    }
 
-    /// - The current `execute()` uses `self.stack.data.is_empty()` NB: this does not reflect real-world checks.
-    /// - This Implementation uses the unwrap which means errors are not handled.
+    /// Enforce `limits` on calldata and return data.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Apply `profile`'s limits and instruction budget (see `limits::Profile`), replacing any set before.
+    ///
+    /// Example usage:
+    /// ```ignore
+    /// let mut evm = Evm::new(block_env, tx, memory, stack, storage).with_profile(Profile::Strict);
+    /// ```
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        self.limits = profile.limits();
+        self.instruction_budget = profile.instruction_budget();
+        self
+    }
+
+    /// Stop execution after `budget` instructions (see `ProgramExitStatus::InstructionBudgetExhausted`).
+    ///
+    /// Example usage:
+    /// ```ignore
+    /// let mut evm = Evm::new(block_env, tx, memory, stack, storage).with_instruction_budget(10_000);
+    /// evm.run();
+    /// ```
+    pub fn with_instruction_budget(mut self, budget: u64) -> Self {
+        self.instruction_budget = Some(budget);
+        self
+    }
+
+    /// Restart gas accounting with `limit`, or with unlimited gas for `None`
+    /// (an `eth_call` with no `gas` field). Gas used is still reported in `TransactResult::gas_used`.
+    pub fn with_gas_limit(mut self, limit: Option<u64>) -> Self {
+        self.gas = limit.map_or_else(Gas::unlimited, Gas::new);
+        self
+    }
+
+    /// Load the code to run into `code` and set up the top-level `context` from `tx`, and start a new
+    /// transaction for gas purposes (the current storage becomes the original values, the access set is reset):
+    /// - If `tx.to == Address::ZERO` (contract creation / deployment), the init code is `tx.data` and there is no
+    ///   calldata. It runs as the new account `tx.from.create(tx.nonce)` (nonce 1 since Spurious Dragon), which
+    ///   gets the init code's output as its code when it ends (see `call_frame::end_create_transaction`). An
+    ///   address that already has code or a nonce halts with `CreateCollision` without running anything.
+    /// - Otherwise the callee's code is loaded from `storage` (honouring `etch` overrides) and `tx.data` is the
+    ///   calldata; an account without code runs empty code, which stops immediately.
     pub fn execute(&mut self) {
-        // If transaction is a contract creation (to == ZERO), copy tx.data into memory as initial code.
-        if self.tx.to == Address::ZERO && !self.stack.data.is_empty() {
-            for (i, value) in self.tx.data.iter().enumerate() {
-                // Writing each byte of tx.data into memory at its corresponding offset.
-                println!("Value at index {}: {}", i, value);
-                self.memory.store_byte(i, *value);
-            }
-        } else if self.tx.to != Address::ZERO {
-            // If tx.to is set, we are calling an existing contract: load its code into memory.
-            let touched_contract: Address = self.tx.to;
-            // The code is expected to be found in storage.data[address].code
-            // NOTE: .unwrap() will panic if address not present; not production frienly
-            for (i, v) in self
-                .storage
-                .data
-                .get(&touched_contract)
-                .unwrap()
-                .code
-                .iter()
-                .enumerate()
-            {
-                self.memory.store_byte(i, *v);
-            }
+        self.storage.commit();
+        self.storage.clear_original_values();
+        self.logs.clear();
+        self.reset_accessed();
+        self.created = None;
+        let mut address = self.tx.to;
+        let calldata = if self.tx.to == Address::ZERO {
+            address = self.tx.from.create(self.tx.nonce.saturating_to());
+            self.begin_create_transaction(address);
+            Bytes::new()
         } else {
-        }
+            let touched_contract: Address = self.tx.to;
+            self.storage.touch(touched_contract);
+            self.code = self.storage.code(touched_contract).clone();
+            self.tx.data.clone()
+        };
+        self.context = CallContext {
+            caller: self.tx.from,
+            address,
+            code_address: address,
+            value: self.tx.value,
+            calldata,
+            is_static: false,
+        };
     }
 
     /// Execute a single instruction at the current `pc`.
     ///
-    /// 1. `raw_instruction = self.memory.load_byte(self.pc)`:
-    ///    - The VM reads a single byte from linear `memory` at position `pc`.
+    /// 1. `raw_instruction = self.code.as_slice()[self.pc]`:
+    ///    - The VM reads a single byte from `code` at position `pc`.
     ///    - This byte is the opcode code (0x00..0xff). For example 0x60 is `PUSH1`.
-    ///    - Ensure `memory` has been seeded with code (via `execute()` ) and `pc` points to the correct start.
-    /// 2. `instruction = Opcode::from_u8(raw_instruction).unwrap()`:
-    ///    - Convert the raw byte into the typed `Opcode` enum. If the byte is unknown, `from_u8` returns `None`.
-    ///    - `unwrap()` will panic on undefined bytes — not production friendly,
-    /// 3. `let jump_tables = build_jump_table()`:
-    ///    - Builds (currently on every `step`) a 256-entry table that maps opcode numeric values to handler functions (`fn(&mut Evm)`).
-    /// 4. `jump_tables[instruction as usize](self)`:
+    ///    - Ensure `code` has been loaded (via `execute()`) and `pc` points to the correct start.
+    ///    - Running off the end of `code` behaves like `STOP`.
+    /// 2. `instruction = Opcode::from_u8(raw_instruction)`:
+    ///    - Convert the raw byte into the typed `Opcode` enum. If the byte is unknown, `from_u8` returns `None`
+    ///      and the instruction traps with `InvalidOpcode`.
+    ///    - The stack is checked against `Opcode::stack_io()` before dispatch; too few items traps with `StackUnderflow`.
+    ///    - The static cost `gas::static_gas(instruction, spec)` is charged before dispatch; handlers charge dynamic costs
+    ///      (memory expansion, copies) themselves. Either running dry halts with `OutOfGas`.
+    /// 3. `let jump_table = jump_table(self.spec)`:
+    ///    - The 256-entry table that maps opcode numeric values to handler functions
+    ///      (`fn(&mut Evm) -> Result<(), EvmErrors>`), built once per spec and cached.
+    /// 4. `self.pc += 1` then `jump_table[instruction as usize](self)`:
+    ///    - `pc` is advanced past the opcode byte *before* the handler runs, so handlers see `pc` pointing at the
+    ///      first immediate byte (or the next instruction).
     ///    - Call the handler function for the current opcode. Handlers mutate `stack`, `memory`, `pc`, `status`, and other parts of the EVM as needed.
-    ///    - Handlers that consume immediate bytes (e.g., `PUSH1..PUSH32`)
-
+    ///    - A handler error halts the run with `Halt(error)`, consuming the gas left.
+    ///    - Handlers that consume immediate bytes (e.g., `PUSH1..PUSH32`) advance `pc` past them; jumps overwrite `pc`.
+    /// 5. If the instruction ended a callee's frame (see `call_frame`), the caller resumes right after its CALL.
+    ///    If it ended a creation transaction's init code, the returned code is deployed.
     pub fn step(&mut self) {
-        // Fetch the byte at the program counter from memory.
-        let raw_instruction = self.memory.load_byte(self.pc);
+        self.execute_instruction();
+        self.end_frame();
+    }
+
+    /// Step 5 of `step`: once the executing frame's status ends it, hand control back to the caller, or finish
+    /// the creation transaction.
+    fn end_frame(&mut self) {
+        if !self.status.ends_frame() {
+            return;
+        }
+        if !self.call_stack.is_empty() {
+            self.end_call();
+        } else if let Some(created) = self.created.take() {
+            self.end_create_transaction(created);
+        }
+    }
+
+    /// Steps 1-4 of `step`, for the executing frame only.
+    fn execute_instruction(&mut self) {
+        // Enforce the instruction budget before doing any work.
+        if self
+            .instruction_budget
+            .is_some_and(|budget| self.instructions_executed >= budget)
+        {
+            self.status = ProgramExitStatus::InstructionBudgetExhausted;
+            return;
+        }
+        self.instructions_executed += 1;
+
+        // Past the end of the code: implicit STOP.
+        if self.pc >= self.code.len() {
+            self.status = ProgramExitStatus::Success {
+                output: Bytes::new(),
+                gas_used: self.gas.spent(),
+            };
+            return;
+        }
+
+        // Fetch the byte at the program counter from the code.
+        let raw_instruction = self.code.as_slice()[self.pc];
 
         // Decode: map the raw byte into a strongly typed Opcode enum.
-        let instruction: Opcode = Opcode::from_u8(raw_instruction).unwrap();
+        let Some(instruction) = Opcode::from_u8(raw_instruction) else {
+            self.trap(raw_instruction, None, EvmErrors::InvalidOpcode);
+            return;
+        };
+        if let Err(error) = self.limits.check_opcode(raw_instruction) {
+            self.trap(raw_instruction, Some(instruction), error);
+            return;
+        }
+
+        let (inputs, _) = instruction.stack_io();
+        if let Err(error) = self.stack.require(inputs) {
+            self.trap(raw_instruction, Some(instruction), error);
+            return;
+        }
+
+        if !self.gas.record_cost(static_gas(instruction, self.spec)) {
+            self.out_of_gas();
+            return;
+        }
+
+        // Look up the cached dispatch table and call the handler for the decoded instruction.
+        let jump_table = jump_table(self.spec);
+        let pc = self.pc;
+        let depth = self.stack.len();
+        self.pc += 1;
+        if let Err(error) = jump_table[instruction as usize](self) {
+            self.halt(pc, instruction, depth, error);
+        }
+    }
+
+    /// Halt with `OutOfGas`, consuming everything left in the frame.
+    fn out_of_gas(&mut self) {
+        self.exceptional_halt(EvmErrors::OutOfGas);
+    }
+
+    /// End the frame with `Halt(error)`. Like every exceptional halt it consumes all gas left in the frame.
+    fn exceptional_halt(&mut self, error: EvmErrors) {
+        self.gas.spend_all();
+        self.status = ProgramExitStatus::Halt(error);
+    }
+
+    /// Handle a handler's `error` for `instruction` at `pc`, dispatched with `depth` items on the stack.
+    ///
+    /// Outside recovery mode, and for `OutOfGas` in any mode, the run ends with `Halt(error)`, consuming the gas
+    /// left. In recovery mode the trap is recorded and the instruction skipped like in `trap`. The handler may
+    /// already have popped some of its inputs, so the stack is cut back to `depth` minus the inputs rather than
+    /// popped again.
+    fn halt(&mut self, pc: usize, instruction: Opcode, depth: usize, error: EvmErrors) {
+        if !self.recover_traps || error == EvmErrors::OutOfGas {
+            self.exceptional_halt(error);
+            return;
+        }
+
+        self.traps.push(Trap {
+            pc,
+            opcode: instruction as u8,
+            error,
+        });
 
-        // Build dispatch table and call the handler for the decoded instruction.
-        // Note: building the table on every step is simple but inefficient; use a cached static table for performance.
-        let jump_tables: [fn(&mut Evm); 256] = build_jump_table();
-        jump_tables[instruction as usize](self);
+        let (inputs, outputs) = instruction.stack_io();
+        while self.stack.len() > depth - inputs {
+            self.stack.pop();
+        }
+        self.pc = pc;
+        self.skip_instruction(outputs, instruction.immediate_size());
+    }
+
+    /// Handle an instruction that cannot execute.
+    ///
+    /// Outside recovery mode the run ends with `ProgramExitStatus::Halt(error)`. In recovery mode the trap is recorded,
+    /// the instruction's available inputs are popped, zeros are pushed for each of its outputs, and `pc` moves past
+    /// the instruction (including any immediates). Undefined bytes are treated as having no inputs or outputs.
+    fn trap(&mut self, raw_instruction: u8, instruction: Option<Opcode>, error: EvmErrors) {
+        if !self.recover_traps {
+            self.exceptional_halt(error);
+            return;
+        }
+
+        self.traps.push(Trap {
+            pc: self.pc,
+            opcode: raw_instruction,
+            error,
+        });
 
-        // self.pc += 1; // to be handled in the opcode handler
+        let ((inputs, outputs), immediates) = instruction
+            .map(|op| (op.stack_io(), op.immediate_size()))
+            .unwrap_or(((0, 0), 0));
+
+        for _ in 0..inputs.min(self.stack.len()) {
+            self.stack.pop();
+        }
+        self.skip_instruction(outputs, immediates);
+    }
+
+    /// Recovery mode: push a zero for each of the skipped instruction's `outputs` and move `pc` past it and its
+    /// `immediates`.
+    fn skip_instruction(&mut self, outputs: usize, immediates: usize) {
+        for _ in 0..outputs {
+            if self.stack.push(U256::ZERO).is_err() {
+                break;
+            }
+        }
+
+        self.pc += 1 + immediates;
     }
 
     pub fn run(&mut self) {
@@ -177,4 +556,540 @@ impl Evm {
             self.step();
         }
     }
+
+    /// Like `run`, but an interpreter panic ends the run with `ProgramExitStatus::InternalError` instead of
+    /// unwinding into the host. Meant for services that execute untrusted bytecode while some code paths
+    /// still `unwrap()`.
+    ///
+    /// After an `InternalError` the rest of the state (stack, memory, storage) is whatever the panicking
+    /// handler left behind and should not be trusted. The panic hook still runs, so the message is also
+    /// printed to stderr unless the host installed its own hook.
+    pub fn run_catching(&mut self) {
+        self.contain_panics(Evm::run);
+    }
+
+    /// Run `f`, turning a panic inside it into `ProgramExitStatus::InternalError`.
+    fn contain_panics(&mut self, f: impl FnOnce(&mut Evm)) {
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| f(self))) {
+            self.status = ProgramExitStatus::InternalError(panic_message(&*payload));
+        }
+    }
+
+    /// Execute the transaction as a preview: load the code, `run()`, and report the state diff,
+    /// then restore `storage` to what it was before. Use this for simulations against a shared state.
+    pub fn transact(&mut self) -> TransactResult {
+        let original = self.storage.clone();
+        let result = self.transact_commit();
+        self.storage = original;
+        result
+    }
+
+    /// Execute the transaction and keep its state changes in `storage`, returning the same diff as `transact()`.
+    /// A transaction that doesn't succeed (`REVERT`, or an exceptional halt such as out of gas or an invalid jump)
+    /// keeps nothing: `storage` and the logs are rolled back and the diff is empty, as for a failed sub-call.
+    /// A transaction over `limits` is rejected without running: status `Rejected`, nothing changed, no gas used.
+    pub fn transact_commit(&mut self) -> TransactResult {
+        if let Err(error) = self.limits.check_calldata(self.tx.data.len()) {
+            self.status = ProgramExitStatus::Rejected(error);
+            return TransactResult {
+                status: self.status.clone(),
+                state_diff: StateDiff::default(),
+                gas_used: 0,
+            };
+        }
+        let original = self.storage.clone().into_inner();
+        self.execute();
+        self.run();
+        if !self.status.is_success() {
+            self.storage.revert_to(JournalCheckpoint::default());
+            self.logs.clear();
+        }
+        self.storage.commit();
+        let gas_used = if self.status.is_success() {
+            self.gas.set_final_refund(max_refund_quotient(self.spec));
+            self.gas.spent_sub_refunded()
+        } else {
+            self.gas.spent()
+        };
+
+        TransactResult {
+            status: self.status.clone(),
+            state_diff: original.diff(&self.storage),
+            gas_used,
+        }
+    }
+}
+
+/// The message of a panic payload (`panic!("...")` carries a `&str` or a `String`).
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evm_with_code(code: Vec<u8>) -> Evm {
+        Evm {
+            code: Bytecode::new(code),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn trap_ends_run_by_default() {
+        // An undefined byte, the designated INVALID and an opcode without a handler (BLOBHASH) all halt the
+        // same way, consuming every unit of gas.
+        for invalid in [0x0C, 0xFE, 0x49] {
+            let mut evm =
+                evm_with_code(vec![0x60, 0x01, invalid, 0x00]).with_gas_limit(Some(1_000));
+            evm.run();
+
+            assert_eq!(
+                evm.status,
+                ProgramExitStatus::Halt(EvmErrors::InvalidOpcode)
+            );
+            assert_eq!(evm.gas.remaining(), 0);
+            assert!(evm.traps.is_empty());
+        }
+    }
+
+    #[test]
+    fn push_onto_a_full_stack_overflows() {
+        // 1025 PUSH0s: the last one finds 1024 items already there.
+        let mut evm = evm_with_code(vec![0x5F; 1025]);
+        evm.run();
+
+        assert_eq!(
+            evm.status,
+            ProgramExitStatus::Halt(EvmErrors::StackOverflow)
+        );
+        assert_eq!(evm.stack.len(), 1024);
+    }
+
+    #[test]
+    fn exit_status_carries_output_gas_and_halt_reason() {
+        // PUSH1 0x2a; PUSH1 0; MSTORE8; PUSH1 1; PUSH1 0; RETURN
+        let mut evm = evm_with_code(vec![
+            0x60, 0x2a, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xF3,
+        ])
+        .with_gas_limit(Some(1_000));
+        evm.run();
+        assert_eq!(
+            evm.status,
+            ProgramExitStatus::Success {
+                output: Bytes::from(vec![0x2a]),
+                gas_used: 3 * 5 + 3
+            }
+        );
+        assert_eq!(evm.status.output(), [0x2a]);
+
+        // PUSH1 3; JUMP; STOP (no JUMPDEST at 3)
+        let mut evm = evm_with_code(vec![0x60, 0x03, 0x56, 0x00]);
+        evm.run();
+        assert_eq!(
+            evm.status,
+            ProgramExitStatus::Halt(EvmErrors::InvalidJump {
+                target: U256::from(3)
+            })
+        );
+    }
+
+    #[test]
+    fn recover_traps_skips_and_patches_stack() {
+        // 0x0C is undefined, ADD underflows on an empty stack, then STOP.
+        let mut evm = evm_with_code(vec![0x0C, 0x01, 0x00]);
+        evm.recover_traps = true;
+        evm.run();
+
+        assert!(evm.status.is_success());
+        assert_eq!(
+            evm.traps,
+            vec![
+                Trap {
+                    pc: 0,
+                    opcode: 0x0C,
+                    error: EvmErrors::InvalidOpcode,
+                },
+                Trap {
+                    pc: 1,
+                    opcode: 0x01,
+                    error: EvmErrors::StackUnderflow {
+                        required: 2,
+                        present: 0,
+                    },
+                },
+            ]
+        );
+        expect_stack!(evm, [0]);
+    }
+
+    #[test]
+    fn recover_traps_continues_past_handler_errors() {
+        // Each instruction fails inside its handler: a JUMP into PUSH data, INVALID, and BLOBHASH, which has no
+        // handler. Recovery mode skips it and runs on to the trailing PUSH1 7; STOP.
+        for (code, expected) in [
+            // PUSH1 1; PUSH1 3; JUMP: the handler has already popped the target.
+            (vec![0x60, 0x01, 0x60, 0x03, 0x56], [1, 7]),
+            // PUSH1 1; INVALID
+            (vec![0x60, 0x01, 0xFE], [1, 7]),
+            // PUSH1 1; BLOBHASH: its input is dropped and its output patched with a zero.
+            (vec![0x60, 0x01, 0x49], [0, 7]),
+        ] {
+            let pc = code.len() - 1;
+            let opcode = code[pc];
+            let mut code = code;
+            code.extend([0x60, 0x07, 0x00]);
+            let mut evm = evm_with_code(code).with_gas_limit(Some(1_000));
+            evm.recover_traps = true;
+            evm.run();
+
+            assert!(evm.status.is_success(), "{opcode:#04x}: {:?}", evm.status);
+            assert_eq!(evm.traps.len(), 1);
+            assert_eq!((evm.traps[0].pc, evm.traps[0].opcode), (pc, opcode));
+            assert_eq!(evm.stack.as_slice(), expected.map(U256::from));
+            assert!(evm.gas.remaining() > 0);
+        }
+    }
+
+    #[test]
+    fn transact_does_not_mutate_storage() {
+        let contract = Address::left_padding_from(&[0xc0]);
+        let mut evm = evm_with_code(Vec::new());
+        evm.tx.to = contract;
+        // STOP; the account exists so `execute()` can load its code.
+        evm.storage.set_code(contract, vec![0x00]);
+        let before = evm.storage.clone();
+
+        let result = evm.transact();
+
+        assert!(result.status.is_success());
+        assert!(result.state_diff.is_empty());
+        assert!(before.diff(&evm.storage).is_empty());
+    }
+
+    #[test]
+    fn transact_takes_the_capped_refund_off_gas_used() {
+        let contract = Address::left_padding_from(&[0xc0]);
+        // Clear slot 0, which starts at 1: PUSH1 0; PUSH1 0; SSTORE; then STOP or PUSH1 0; PUSH1 0; REVERT.
+        // (PUSH0 postdates both forks.)
+        let run = |spec, end: &[u8]| {
+            let mut code = vec![0x60, 0x00, 0x60, 0x00, 0x55];
+            code.extend_from_slice(end);
+            let mut evm = evm_with_code(Vec::new())
+                .with_spec(spec)
+                .with_gas_limit(Some(100_000));
+            evm.tx.to = contract;
+            evm.storage.set_code(contract, code);
+            evm.storage.s_store(contract, U256::ZERO, U256::ONE);
+            let result = evm.transact_commit();
+            (evm, result)
+        };
+
+        // London: 6 + 5000 spent (2100 cold slot + 2900 reset); the 4800 clear refund is capped at a fifth.
+        let (evm, result) = run(SpecId::London, &[0x00]);
+        assert!(result.status.is_success());
+        assert_eq!(evm.gas.spent(), 5_006);
+        assert_eq!(evm.gas.refunded(), 5_006 / 5);
+        assert_eq!(result.gas_used, 5_006 - 5_006 / 5);
+
+        // Istanbul: the 15000 clear refund is capped at half of the 5004 spent.
+        let (_, result) = run(SpecId::Istanbul, &[0x00]);
+        assert_eq!(result.gas_used, 5_006 - 5_006 / 2);
+
+        // A reverted transaction gets nothing back.
+        let (evm, result) = run(SpecId::London, &[0x60, 0x00, 0x60, 0x00, 0xFD]);
+        assert!(matches!(result.status, ProgramExitStatus::Revert { .. }));
+        assert_eq!(result.gas_used, evm.gas.spent());
+        assert_eq!(result.gas_used, 5_006 + 3 + 3);
+    }
+
+    #[test]
+    fn gas_limit_none_is_unlimited() {
+        let evm = evm_with_code(vec![0x00]).with_gas_limit(None);
+        assert!(evm.gas.is_unlimited());
+
+        let tx = Transaction {
+            gas_limit: U256::MAX,
+            ..Default::default()
+        };
+        assert!(
+            Evm::new(
+                BlockEnv::default(),
+                tx,
+                Memory::new(),
+                Stack::default(),
+                EvmStorage::default()
+            )
+            .gas
+            .is_unlimited()
+        );
+
+        let evm = evm_with_code(vec![0x00]).with_gas_limit(Some(50_000));
+        assert_eq!(evm.gas.limit(), 50_000);
+    }
+
+    #[test]
+    fn static_costs_are_charged_until_out_of_gas() {
+        // PUSH1 1; PUSH1 2; ADD; STOP
+        let code = vec![0x60, 0x01, 0x60, 0x02, 0x01, 0x00];
+
+        let mut evm = evm_with_code(code.clone()).with_gas_limit(Some(100));
+        evm.run();
+        assert!(evm.status.is_success());
+        assert_eq!(evm.gas.spent(), 9);
+
+        let mut evm = evm_with_code(code).with_gas_limit(Some(8));
+        evm.run();
+        assert_eq!(evm.status, ProgramExitStatus::Halt(EvmErrors::OutOfGas));
+        assert_eq!(evm.gas.remaining(), 0);
+        expect_stack!(evm, [1, 2]);
+    }
+
+    #[test]
+    fn memory_expansion_is_charged() {
+        // PUSH1 0; PUSH1 0x40; MSTORE (offset 0x40 -> 3 words); STOP
+        let mut evm =
+            evm_with_code(vec![0x60, 0x00, 0x60, 0x40, 0x52, 0x00]).with_gas_limit(Some(1_000));
+        evm.memory = Memory::new_with_data(vec![0u8; 0x60]);
+        evm.run();
+
+        assert!(evm.status.is_success());
+        assert_eq!(evm.gas.spent(), 3 + 3 + 3 + 9);
+    }
+
+    #[test]
+    fn mstore_grows_memory_on_demand() {
+        // PUSH1 0x2a; PUSH2 0x1000; MSTORE; MSIZE
+        let mut evm = evm_with_code(vec![0x60, 0x2a, 0x61, 0x10, 0x00, 0x52, 0x59]);
+        evm.run();
+
+        assert!(evm.status.is_success());
+        assert_eq!(evm.memory.load_word(0x1000), U256::from(0x2a));
+        expect_stack!(evm, [0x1020]);
+    }
+
+    #[test]
+    fn push0_traps_before_shanghai() {
+        let mut evm = evm_with_code(vec![0x5F, 0x00]).with_spec(SpecId::London);
+        evm.run();
+        assert!(matches!(evm.status, ProgramExitStatus::Halt(_)));
+
+        let mut evm = evm_with_code(vec![0x5F, 0x00]).with_spec(SpecId::Shanghai);
+        evm.run();
+        assert!(evm.status.is_success());
+        expect_stack!(evm, [0]);
+    }
+
+    #[test]
+    fn revert_returns_output_and_discards_state() {
+        let contract = Address::left_padding_from(&[0xc0]);
+        // PUSH1 1; PUSH1 0; SSTORE; PUSH1 0xaa; PUSH1 0; MSTORE8; PUSH1 1; PUSH1 0; REVERT
+        let code = vec![
+            0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0xaa, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00,
+            0xFD,
+        ];
+        let mut evm = evm_with_code(Vec::new());
+        evm.tx.to = contract;
+        evm.storage.set_code(contract, code);
+
+        let result = evm.transact_commit();
+
+        assert_eq!(
+            result.status,
+            ProgramExitStatus::Revert {
+                output: Bytes::from(vec![0xaa])
+            }
+        );
+        assert_eq!(evm.return_data, vec![0xaa]);
+        assert_eq!(result.state_diff, StateDiff::default());
+        assert!(evm.storage.data[&contract].storage.is_empty());
+    }
+
+    #[test]
+    fn failed_transaction_keeps_no_state_or_logs() {
+        let contract = Address::left_padding_from(&[0xc0]);
+        // SSTORE(1, 1); LOG0(0, 0); then a failing tail.
+        let prefix = [0x60, 0x01, 0x60, 0x01, 0x55, 0x5F, 0x5F, 0xA0];
+        let tails: [(&[u8], EvmErrors); 3] = [
+            // INVALID
+            (&[0xFE], EvmErrors::InvalidOpcode),
+            // SSTORE(2, 1): a second fresh slot the gas limit can't pay for.
+            (&[0x60, 0x01, 0x60, 0x02, 0x55], EvmErrors::OutOfGas),
+            // JUMP(0)
+            (&[0x5F, 0x56], EvmErrors::InvalidJump { target: U256::ZERO }),
+        ];
+        for (tail, error) in tails {
+            let mut evm = evm_with_code(Vec::new()).with_gas_limit(Some(30_000));
+            evm.tx.to = contract;
+            evm.storage.set_code(contract, [&prefix[..], tail].concat());
+
+            let result = evm.transact_commit();
+
+            assert_eq!(result.status, ProgramExitStatus::Halt(error));
+            assert_eq!(result.state_diff, StateDiff::default());
+            expect_storage!(evm, contract, 1 => 0);
+            assert!(evm.logs.is_empty());
+        }
+    }
+
+    #[test]
+    fn run_catching_contains_panics() {
+        let mut evm = evm_with_code(vec![0x60, 0x01, 0x00]);
+        evm.contain_panics(|evm| {
+            evm.step();
+            panic!("handler bug at pc {}", evm.pc);
+        });
+        assert_eq!(
+            evm.status,
+            ProgramExitStatus::InternalError("handler bug at pc 2".to_string())
+        );
+
+        let mut evm = evm_with_code(vec![0x60, 0x01, 0x00]);
+        evm.run_catching();
+        assert!(evm.status.is_success());
+    }
+
+    #[test]
+    fn shifts_are_dispatched_and_gated() {
+        // PUSH1 1; PUSH1 4; SHL; NOT
+        let code = vec![0x60, 0x01, 0x60, 0x04, 0x1B, 0x19];
+        let mut evm = evm_with_code(code.clone());
+        evm.run();
+        expect_stack!(evm, [!U256::from(16)]);
+
+        // Shifts arrived in Constantinople.
+        let mut evm = evm_with_code(code).with_spec(SpecId::Byzantium);
+        evm.run();
+        assert!(matches!(evm.status, ProgramExitStatus::Halt(_)));
+    }
+
+    #[test]
+    fn size_limits_reject_calldata_and_fail_large_returns() {
+        let limits = Limits {
+            max_calldata_size: Some(4),
+            max_return_data_size: Some(32),
+            ..Limits::default()
+        };
+        let mut evm = evm_with_code(Vec::new()).with_limits(limits.clone());
+        evm.tx.data = vec![0x00; 5].into();
+        let result = evm.transact();
+        assert_eq!(
+            result.status,
+            ProgramExitStatus::Rejected(EvmErrors::CalldataTooLarge { size: 5, limit: 4 })
+        );
+
+        // PUSH1 33; PUSH1 0; RETURN
+        let mut evm = evm_with_code(vec![0x60, 0x21, 0x60, 0x00, 0xF3]).with_limits(limits);
+        evm.run();
+        assert_eq!(
+            evm.status,
+            ProgramExitStatus::Halt(EvmErrors::ReturnDataTooLarge {
+                size: 33,
+                limit: 32
+            })
+        );
+    }
+
+    #[test]
+    fn strict_profile_caps_memory_depth_and_opcodes() {
+        assert_eq!("STRICT".parse(), Ok(Profile::Strict));
+        assert!("lax".parse::<Profile>().is_err());
+        assert_eq!(Profile::Mainnet.limits(), Limits::default());
+
+        // MLOAD at 1 MiB: affordable, but past the strict memory cap.
+        let code = vec![0x62, 0x10, 0x00, 0x00, 0x51, 0x00];
+        let mut mainnet = evm_with_code(code.clone()).with_profile(Profile::Mainnet);
+        mainnet.run();
+        assert!(mainnet.status.is_success());
+        let mut strict = evm_with_code(code).with_profile(Profile::Strict);
+        strict.run();
+        assert_eq!(
+            strict.status,
+            ProgramExitStatus::Halt(EvmErrors::MemoryOutOfBounds {
+                offset: 0x10_0000,
+                len: 32
+            })
+        );
+        assert_eq!(strict.instruction_budget, Some(10_000_000));
+
+        // SELFDESTRUCT(0)
+        let mut strict = evm_with_code(vec![0x5F, 0xFF]).with_profile(Profile::Strict);
+        strict.run();
+        assert_eq!(
+            strict.status,
+            ProgramExitStatus::Halt(EvmErrors::OpcodeNotAllowed { opcode: 0xFF })
+        );
+
+        // A call past the depth cap fails without starting, like one at the protocol limit.
+        let limits = Limits {
+            max_call_depth: Some(0),
+            ..Limits::default()
+        };
+        // CALL(gas 0xffff, 0xc0, 0, 0, 0, 0, 0); STOP
+        let mut evm = evm_with_code(vec![
+            0x5F, 0x5F, 0x5F, 0x5F, 0x5F, 0x60, 0xc0, 0x61, 0xff, 0xff, 0xF1, 0x00,
+        ])
+        .with_limits(limits);
+        evm.run();
+        expect_stack!(evm, [0]);
+    }
+
+    #[test]
+    fn call_runs_etched_code() {
+        let contract = Address::left_padding_from(&[0xc0]);
+        let mut evm = evm_with_code(Vec::new());
+        evm.tx.to = contract;
+        evm.storage.set_code(contract, vec![0x00]);
+        // PUSH1 7; STOP
+        evm.storage.etch(contract, vec![0x60, 0x07, 0x00]);
+
+        let result = evm.transact();
+
+        assert!(result.status.is_success());
+        expect_stack!(evm, [7]);
+    }
+
+    #[test]
+    fn handler_errors_fail_instead_of_panicking() {
+        // PUSH5 0x0100000000; MLOAD: reads past the highest address memory may grow to.
+        let code = vec![0x64, 0x01, 0x00, 0x00, 0x00, 0x00, 0x51];
+        let error = EvmErrors::MemoryOutOfBounds {
+            offset: 1 << 32,
+            len: 32,
+        };
+        let mut evm = evm_with_code(code.clone());
+        evm.run();
+        assert_eq!(evm.status, ProgramExitStatus::Halt(error.clone()));
+
+        // Recovery mode records it and carries on with MLOAD's output patched to zero.
+        let mut evm = evm_with_code(code);
+        evm.recover_traps = true;
+        evm.run();
+
+        assert!(evm.status.is_success());
+        expect_stack!(evm, [0]);
+        assert_eq!(
+            evm.traps,
+            vec![Trap {
+                pc: 6,
+                opcode: 0x51,
+                error,
+            }]
+        );
+    }
+
+    #[test]
+    fn instruction_budget_stops_endless_loop() {
+        // JUMPDEST; PUSH1 0x00; JUMP -- loops forever without a budget.
+        let mut evm = evm_with_code(vec![0x5B, 0x60, 0x00, 0x56]).with_instruction_budget(10);
+        evm.run();
+
+        assert_eq!(evm.status, ProgramExitStatus::InstructionBudgetExhausted);
+        assert_eq!(evm.instructions_executed, 10);
+    }
 }
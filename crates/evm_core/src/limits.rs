@@ -0,0 +1,120 @@
+//! Caps an embedder can put on what a transaction sends in, gets back and may do while it runs, and the named
+//! profiles that bundle them.
+
+use std::str::FromStr;
+
+use primitives::errors::EvmErrors;
+use serde::{Deserialize, Serialize};
+
+use crate::call_frame::{CALL_DEPTH_LIMIT, MAX_CODE_SIZE};
+
+/// Sandbox caps, on top of what gas and the fork's rules already bound.
+/// - `max_calldata_size`: `transact` / `transact_commit` reject a transaction whose `tx.data` is larger, before
+///   running it (`ProgramExitStatus::Rejected(CalldataTooLarge)`).
+/// - `max_return_data_size`: `RETURN` / `REVERT` with a larger output fail with `ReturnDataTooLarge`.
+/// - `max_memory_size`: a memory access reaching past this many bytes fails with `MemoryOutOfBounds`.
+/// - `max_call_depth`: most frames that can be suspended below the executing one; deeper calls and creations fail
+///   (push 0) as at `CALL_DEPTH_LIMIT`, which still applies.
+/// - `max_code_size`: replaces the fork's cap on the code a creation may deploy (`MAX_CODE_SIZE` since Spurious
+///   Dragon).
+/// - `denied_opcodes`: opcode bytes that fail with `OpcodeNotAllowed` instead of running.
+///
+/// `None` (or an empty list) means no cap. The default sets none, as on mainnet.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Limits {
+    pub max_calldata_size: Option<usize>,
+    pub max_return_data_size: Option<usize>,
+    pub max_memory_size: Option<usize>,
+    pub max_call_depth: Option<usize>,
+    pub max_code_size: Option<usize>,
+    pub denied_opcodes: Vec<u8>,
+}
+
+impl Limits {
+    pub fn check_calldata(&self, size: usize) -> Result<(), EvmErrors> {
+        match self.max_calldata_size {
+            Some(limit) if size > limit => Err(EvmErrors::CalldataTooLarge { size, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_return_data(&self, size: usize) -> Result<(), EvmErrors> {
+        match self.max_return_data_size {
+            Some(limit) if size > limit => Err(EvmErrors::ReturnDataTooLarge { size, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_opcode(&self, opcode: u8) -> Result<(), EvmErrors> {
+        if self.denied_opcodes.contains(&opcode) {
+            Err(EvmErrors::OpcodeNotAllowed { opcode })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// `max_call_depth`, never above `CALL_DEPTH_LIMIT`.
+    pub fn call_depth_limit(&self) -> usize {
+        self.max_call_depth
+            .map_or(CALL_DEPTH_LIMIT, |limit| limit.min(CALL_DEPTH_LIMIT))
+    }
+}
+
+/// Named bundles of `Limits` and an instruction budget, applied with `Evm::with_profile` (or `evm --profile`).
+/// - `Mainnet` (the default): no caps beyond the protocol's, the same as an `Evm` without a profile.
+/// - `Strict`: for running untrusted code: 128 KiB of calldata, 64 KiB of return data, 1 MiB of memory, 64 frames
+///   deep, the mainnet code size cap under every fork, 10 million instructions, and no `SELFDESTRUCT` or
+///   `CALLCODE`.
+/// - `Permissive`: for local development: mainnet, except that creations may deploy code of any size.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Profile {
+    Strict,
+    #[default]
+    Mainnet,
+    Permissive,
+}
+
+impl Profile {
+    pub const ALL: [Profile; 3] = [Profile::Strict, Profile::Mainnet, Profile::Permissive];
+
+    pub fn limits(self) -> Limits {
+        match self {
+            Profile::Strict => Limits {
+                max_calldata_size: Some(128 * 1024),
+                max_return_data_size: Some(64 * 1024),
+                max_memory_size: Some(1024 * 1024),
+                max_call_depth: Some(64),
+                max_code_size: Some(MAX_CODE_SIZE),
+                // SELFDESTRUCT, CALLCODE
+                denied_opcodes: vec![0xFF, 0xF2],
+            },
+            Profile::Mainnet => Limits::default(),
+            Profile::Permissive => Limits {
+                max_code_size: Some(usize::MAX),
+                ..Limits::default()
+            },
+        }
+    }
+
+    /// See `Evm::instruction_budget`.
+    pub fn instruction_budget(self) -> Option<u64> {
+        match self {
+            Profile::Strict => Some(10_000_000),
+            Profile::Mainnet | Profile::Permissive => None,
+        }
+    }
+}
+
+/// Parses profile names case-insensitively (`strict`, `Mainnet`).
+impl FromStr for Profile {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Profile::ALL
+            .into_iter()
+            .find(|profile| format!("{profile:?}").eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                format!("unknown profile `{name}` (expected strict, mainnet or permissive)")
+            })
+    }
+}
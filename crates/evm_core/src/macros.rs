@@ -0,0 +1,44 @@
+/// Assert the stack contents after a run, bottom of stack first. Items are anything `U256::from` accepts.
+///
+/// ```ignore
+/// expect_stack!(evm, [6, 13]);
+/// ```
+#[macro_export]
+macro_rules! expect_stack {
+    ($evm:expr, [$($item:expr),* $(,)?]) => {{
+        let expected: ::std::vec::Vec<::alloy::primitives::U256> =
+            ::std::vec![$(::alloy::primitives::U256::from($item)),*];
+        assert_eq!(
+            $evm.stack.as_slice(),
+            expected.as_slice(),
+            "stack (bottom first) after run"
+        );
+    }};
+}
+
+/// Assert storage slots of one account. A missing account or slot reads as zero, as SLOAD does.
+///
+/// ```ignore
+/// expect_storage!(evm, contract, 0 => 42, 1 => 0);
+/// ```
+#[macro_export]
+macro_rules! expect_storage {
+    ($evm:expr, $address:expr, $($slot:expr => $value:expr),+ $(,)?) => {{
+        let address: ::alloy::primitives::Address = $address;
+        $(
+            let slot = ::alloy::primitives::U256::from($slot);
+            let actual = $evm
+                .storage
+                .data
+                .get(&address)
+                .and_then(|account| account.storage.get(&slot))
+                .copied()
+                .unwrap_or_default();
+            assert_eq!(
+                actual,
+                ::alloy::primitives::U256::from($value),
+                "storage of {address} at slot {slot}"
+            );
+        )+
+    }};
+}
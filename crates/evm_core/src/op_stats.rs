@@ -0,0 +1,173 @@
+//! Opcode sequence statistics: which pairs and triples of instructions run back to back most often across a
+//! workload, ranked, as input for choosing which fused instructions (superinstructions) pay off first.
+//!
+//! Off unless asked for: feed each prepared `Evm` of the corpus to `OpStats::profile`, then call `report`.
+//! Sequences never span a call boundary, since a fused instruction can't either.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+
+use crate::{
+    Evm, ProgramExitStatus,
+    opcodes::Opcode,
+    tracer::{TraceSink, Tracer},
+};
+
+/// How often one opcode sequence ran.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SequenceCount {
+    pub ops: Vec<String>,
+    pub count: u64,
+}
+
+/// Result of `OpStats::report`. Sequences are sorted by count, most frequent first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OpStatsReport {
+    pub runs: u64,
+    pub instructions: u64,
+    pub bigrams: Vec<SequenceCount>,
+    pub trigrams: Vec<SequenceCount>,
+}
+
+impl fmt::Display for OpStatsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} instructions in {} runs",
+            self.instructions, self.runs
+        )?;
+        for (title, sequences) in [("bigrams", &self.bigrams), ("trigrams", &self.trigrams)] {
+            writeln!(f, "\n{title}")?;
+            for sequence in sequences {
+                let share = 100.0 * sequence.count as f64 / self.instructions.max(1) as f64;
+                writeln!(
+                    f,
+                    "{:<36} {:>10} {:>6.2}%",
+                    sequence.ops.join(" "),
+                    sequence.count,
+                    share
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Running bigram and trigram counts over every run profiled so far.
+#[derive(Debug, Clone, Default)]
+pub struct OpStats {
+    runs: u64,
+    instructions: u64,
+    bigrams: HashMap<[u8; 2], u64>,
+    trigrams: HashMap<[u8; 3], u64>,
+}
+
+impl OpStats {
+    /// Count one executed instruction. `window` holds the opcodes just before it in the same frame, newest last.
+    fn record(&mut self, window: &mut Vec<u8>, op: u8) {
+        self.instructions += 1;
+        window.push(op);
+        if window.len() > 3 {
+            window.remove(0);
+        }
+        if let [.., a, b] = window[..] {
+            *self.bigrams.entry([a, b]).or_default() += 1;
+        }
+        if let [a, b, c] = window[..] {
+            *self.trigrams.entry([a, b, c]).or_default() += 1;
+        }
+    }
+
+    /// Run a copy of `evm` (with its code already loaded, see `Evm::execute`) to completion and add its
+    /// instruction sequences to the counts. `evm` itself is left untouched.
+    pub fn profile(&mut self, evm: &Evm) -> ProgramExitStatus {
+        let stats = Arc::new(Mutex::new(std::mem::take(self)));
+        let sink = Arc::clone(&stats);
+        let mut window = Vec::with_capacity(4);
+        let mut depth = 0;
+        let mut tracer = Tracer::new(TraceSink::Callback(Box::new(move |step| {
+            if step.depth != depth {
+                depth = step.depth;
+                window.clear();
+            }
+            sink.lock().unwrap().record(&mut window, step.op);
+        })));
+
+        let mut evm = evm.clone();
+        // A callback sink never fails.
+        let _ = evm.run_traced(&mut tracer);
+        drop(tracer);
+
+        *self = Arc::try_unwrap(stats)
+            .expect("tracer dropped")
+            .into_inner()
+            .unwrap();
+        self.runs += 1;
+        evm.status
+    }
+
+    /// The `top` most frequent bigrams and trigrams. Ties are broken by opcode value, so reports are stable.
+    pub fn report(&self, top: usize) -> OpStatsReport {
+        OpStatsReport {
+            runs: self.runs,
+            instructions: self.instructions,
+            bigrams: ranked(&self.bigrams, top),
+            trigrams: ranked(&self.trigrams, top),
+        }
+    }
+}
+
+fn ranked<const N: usize>(counts: &HashMap<[u8; N], u64>, top: usize) -> Vec<SequenceCount> {
+    let mut sorted: Vec<_> = counts.iter().collect();
+    sorted.sort_by(|(a_ops, a), (b_ops, b)| b.cmp(a).then(a_ops.cmp(b_ops)));
+    sorted
+        .into_iter()
+        .take(top)
+        .map(|(ops, count)| SequenceCount {
+            ops: ops
+                .iter()
+                .map(|op| {
+                    Opcode::from_u8(*op)
+                        .map_or_else(|| format!("0x{op:02x}"), |opcode| opcode.to_string())
+                })
+                .collect(),
+            count: *count,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use primitives::bytecode::Bytecode;
+
+    use super::*;
+
+    #[test]
+    fn ranks_sequences_across_runs() {
+        // PUSH1 1; PUSH1 2; ADD; PUSH1 3; ADD; STOP
+        let evm = Evm {
+            code: Bytecode::new(vec![0x60, 0x01, 0x60, 0x02, 0x01, 0x60, 0x03, 0x01, 0x00]),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(1_000));
+
+        let mut stats = OpStats::default();
+        assert!(stats.profile(&evm).is_success());
+        stats.profile(&evm);
+        let report = stats.report(2);
+
+        assert_eq!(report.runs, 2);
+        assert_eq!(report.instructions, 12);
+        assert_eq!(report.bigrams[0].ops, ["PUSH1", "ADD"]);
+        assert_eq!(report.bigrams[0].count, 4);
+        assert_eq!(report.bigrams.len(), 2);
+        assert_eq!(report.trigrams[0].ops, ["ADD", "PUSH1", "ADD"]);
+        assert_eq!(report.trigrams[0].count, 2);
+        assert!(report.to_string().contains("PUSH1 ADD"));
+    }
+}
@@ -1,380 +1,308 @@
-pub enum Opcode {
-    STOP = 0x00,
-    ADD = 0x01,
-    MUL = 0x02,
-    SUB = 0x03,
-    DIV = 0x04,
-    SDIV = 0x05,
-    MOD = 0x06,
-    SMOD = 0x07,
-    ADDMOD = 0x08,
-    MULMOD = 0x09,
-    EXP = 0x0A,
-    SIGNEXTEND = 0x0B,
-
-    LT = 0x10,
-    GT = 0x11,
-    SLT = 0x12,
-    SGT = 0x13,
-    EQ = 0x14,
-    ISZERO = 0x15,
-    AND = 0x16,
-    OR = 0x17,
-    XOR = 0x18,
-    NOT = 0x19,
-    BYTE = 0x1A,
-    SHL = 0x1B,
-    SHR = 0x1C,
-    SAR = 0x1D,
-
-    KECCAK256 = 0x20,
-
-    ADDRESS = 0x30,
-    BALANCE = 0x31,
-    ORIGIN = 0x32,
-    CALLER = 0x33,
-    CALLVALUE = 0x34,
-    CALLDATALOAD = 0x35,
-    CALLDATASIZE = 0x36,
-    CALLDATACOPY = 0x37,
-    CODESIZE = 0x38,
-    CODECOPY = 0x39,
-    GASPRICE = 0x3A,
-    EXTCODESIZE = 0x3B,
-    EXTCODECOPY = 0x3C,
-    RETURNDATASIZE = 0x3D,
-    RETURNDATACOPY = 0x3E,
-    EXTCODEHASH = 0x3F,
-
-    BLOCKHASH = 0x40,
-    COINBASE = 0x41,
-    TIMESTAMP = 0x42,
-    NUMBER = 0x43,
-    DIFFICULTY = 0x44,
-    GASLIMIT = 0x45,
-    CHAINID = 0x46,
-    SELFBALANCE = 0x47,
-    BASEFEE = 0x48,
-    BLOBHASH = 0x49,
-    BLOBBASEFEE = 0x4A,
-
-    POP = 0x50,
-    MLOAD = 0x51,
-    MSTORE = 0x52,
-    MSTORE8 = 0x53,
-    SLOAD = 0x54,
-    SSTORE = 0x55,
-    JUMP = 0x56,
-    JUMPI = 0x57,
-    PC = 0x58,
-    MSIZE = 0x59,
-    GAS = 0x5A,
-    JUMPDEST = 0x5B,
-    TLOAD = 0x5C,
-    TSTORE = 0x5D,
-    MCOPY = 0x5E,
-
-    PUSH0 = 0x5F,
-    PUSH1 = 0x60,
-    PUSH2 = 0x61,
-    PUSH3 = 0x62,
-    PUSH4 = 0x63,
-    PUSH5 = 0x64,
-    PUSH6 = 0x65,
-    PUSH7 = 0x66,
-    PUSH8 = 0x67,
-    PUSH9 = 0x68,
-    PUSH10 = 0x69,
-    PUSH11 = 0x6A,
-    PUSH12 = 0x6B,
-    PUSH13 = 0x6C,
-    PUSH14 = 0x6D,
-    PUSH15 = 0x6E,
-    PUSH16 = 0x6F,
-    PUSH17 = 0x70,
-    PUSH18 = 0x71,
-    PUSH19 = 0x72,
-    PUSH20 = 0x73,
-    PUSH21 = 0x74,
-    PUSH22 = 0x75,
-    PUSH23 = 0x76,
-    PUSH24 = 0x77,
-    PUSH25 = 0x78,
-    PUSH26 = 0x79,
-    PUSH27 = 0x7A,
-    PUSH28 = 0x7B,
-    PUSH29 = 0x7C,
-    PUSH30 = 0x7D,
-    PUSH31 = 0x7E,
-    PUSH32 = 0x7F,
-
-    DUP1 = 0x80,
-    DUP2 = 0x81,
-    DUP3 = 0x82,
-    DUP4 = 0x83,
-    DUP5 = 0x84,
-    DUP6 = 0x85,
-    DUP7 = 0x86,
-    DUP8 = 0x87,
-    DUP9 = 0x88,
-    DUP10 = 0x89,
-    DUP11 = 0x8A,
-    DUP12 = 0x8B,
-    DUP13 = 0x8C,
-    DUP14 = 0x8D,
-    DUP15 = 0x8E,
-    DUP16 = 0x8F,
-
-    SWAP1 = 0x90,
-    SWAP2 = 0x91,
-    SWAP3 = 0x92,
-    SWAP4 = 0x93,
-    SWAP5 = 0x94,
-    SWAP6 = 0x95,
-    SWAP7 = 0x96,
-    SWAP8 = 0x97,
-    SWAP9 = 0x98,
-    SWAP10 = 0x99,
-    SWAP11 = 0x9A,
-    SWAP12 = 0x9B,
-    SWAP13 = 0x9C,
-    SWAP14 = 0x9D,
-    SWAP15 = 0x9E,
-    SWAP16 = 0x9F,
-
-    LOG0 = 0xA0,
-    LOG1 = 0xA1,
-    LOG2 = 0xA2,
-    LOG3 = 0xA3,
-    LOG4 = 0xA4,
-
-    DATALOAD = 0xD0,
-    DATALOADN = 0xD1,
-    DATASIZE = 0xD2,
-    DATACOPY = 0xD3,
-
-    RJUMP = 0xE0,
-    RJUMPI = 0xE1,
-    RJUMPV = 0xE2,
-    CALLF = 0xE3,
-    RETF = 0xE4,
-    JUMPF = 0xE5,
-    DUPN = 0xE6,
-    SWAPN = 0xE7,
-    EXCHANGE = 0xE8,
-
-    EOFCREATE = 0xEC,
-    RETURNCONTRACT = 0xEE,
+use std::fmt;
+
+use crate::spec::SpecId::{self, *};
+
+/// Static metadata for one opcode, generated from the `opcodes!` table below.
+/// - `name`: mnemonic, as printed by `Display` and disassemblers.
+/// - `immediates`: bytes following the opcode in code (`n` for `PUSHn`).
+/// - `inputs` / `outputs`: stack items popped and pushed.
+/// - `base_gas`: static gas charged before any dynamic cost (the Cancun schedule).
+/// - `introduced`: first fork that enables the opcode; `None` for EOF-only opcodes that no mainnet fork enables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpcodeInfo {
+    pub name: &'static str,
+    pub immediates: u8,
+    pub inputs: u8,
+    pub outputs: u8,
+    pub base_gas: u16,
+    pub introduced: Option<SpecId>,
+}
 
-    CREATE = 0xF0,
-    CALL = 0xF1,
-    CALLCODE = 0xF2,
-    RETURN = 0xF3,
-    DELEGATECALL = 0xF4,
-    CREATE2 = 0xF5,
+/// Declares every opcode once and generates, from that single list:
+/// the `Opcode` enum, `Opcode::from_u8`, the `OPCODE_INFO` table indexed by byte, and `Display`.
+///
+/// Row format: `byte => NAME, immediates, inputs, outputs, base_gas, introduced;`
+macro_rules! opcodes {
+    ($($byte:literal => $name:ident, $immediates:literal, $inputs:literal, $outputs:literal, $gas:literal, $introduced:expr;)*) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[repr(u8)]
+        pub enum Opcode {
+            $($name = $byte,)*
+        }
 
-    RETURNDATALOAD = 0xF7,
-    EXTCALL = 0xF8,
-    EXTDELEGATECALL = 0xF9,
-    STATICCALL = 0xFA,
-    EXTSTATICCALL = 0xFB,
+        /// Metadata for every byte value; `None` for undefined bytes.
+        pub static OPCODE_INFO: [Option<OpcodeInfo>; 256] = {
+            let mut table: [Option<OpcodeInfo>; 256] = [None; 256];
+            $(
+                table[$byte] = Some(OpcodeInfo {
+                    name: stringify!($name),
+                    immediates: $immediates,
+                    inputs: $inputs,
+                    outputs: $outputs,
+                    base_gas: $gas,
+                    introduced: $introduced,
+                });
+            )*
+            table
+        };
+
+        impl Opcode {
+            /// Attempts to convert a byte into a defined Opcode.
+            /// Returns `None` if the byte does not correspond to a valid opcode.
+            pub fn from_u8(byte: u8) -> Option<Self> {
+                match byte {
+                    $($byte => Some(Self::$name),)*
+                    // All other byte values are undefined
+                    _ => None,
+                }
+            }
+        }
+    };
+}
 
-    REVERT = 0xFD,
-    INVALID = 0xFE,
-    SELFDESTRUCT = 0xFF,
+opcodes! {
+    0x00 => STOP, 0, 0, 0, 0, Some(Frontier);
+    0x01 => ADD, 0, 2, 1, 3, Some(Frontier);
+    0x02 => MUL, 0, 2, 1, 5, Some(Frontier);
+    0x03 => SUB, 0, 2, 1, 3, Some(Frontier);
+    0x04 => DIV, 0, 2, 1, 5, Some(Frontier);
+    0x05 => SDIV, 0, 2, 1, 5, Some(Frontier);
+    0x06 => MOD, 0, 2, 1, 5, Some(Frontier);
+    0x07 => SMOD, 0, 2, 1, 5, Some(Frontier);
+    0x08 => ADDMOD, 0, 3, 1, 8, Some(Frontier);
+    0x09 => MULMOD, 0, 3, 1, 8, Some(Frontier);
+    0x0A => EXP, 0, 2, 1, 10, Some(Frontier);
+    0x0B => SIGNEXTEND, 0, 2, 1, 5, Some(Frontier);
+
+    0x10 => LT, 0, 2, 1, 3, Some(Frontier);
+    0x11 => GT, 0, 2, 1, 3, Some(Frontier);
+    0x12 => SLT, 0, 2, 1, 3, Some(Frontier);
+    0x13 => SGT, 0, 2, 1, 3, Some(Frontier);
+    0x14 => EQ, 0, 2, 1, 3, Some(Frontier);
+    0x15 => ISZERO, 0, 1, 1, 3, Some(Frontier);
+    0x16 => AND, 0, 2, 1, 3, Some(Frontier);
+    0x17 => OR, 0, 2, 1, 3, Some(Frontier);
+    0x18 => XOR, 0, 2, 1, 3, Some(Frontier);
+    0x19 => NOT, 0, 1, 1, 3, Some(Frontier);
+    0x1A => BYTE, 0, 2, 1, 3, Some(Frontier);
+    0x1B => SHL, 0, 2, 1, 3, Some(Constantinople);
+    0x1C => SHR, 0, 2, 1, 3, Some(Constantinople);
+    0x1D => SAR, 0, 2, 1, 3, Some(Constantinople);
+
+    0x20 => KECCAK256, 0, 2, 1, 30, Some(Frontier);
+
+    0x30 => ADDRESS, 0, 0, 1, 2, Some(Frontier);
+    0x31 => BALANCE, 0, 1, 1, 100, Some(Frontier);
+    0x32 => ORIGIN, 0, 0, 1, 2, Some(Frontier);
+    0x33 => CALLER, 0, 0, 1, 2, Some(Frontier);
+    0x34 => CALLVALUE, 0, 0, 1, 2, Some(Frontier);
+    0x35 => CALLDATALOAD, 0, 1, 1, 3, Some(Frontier);
+    0x36 => CALLDATASIZE, 0, 0, 1, 2, Some(Frontier);
+    0x37 => CALLDATACOPY, 0, 3, 0, 3, Some(Frontier);
+    0x38 => CODESIZE, 0, 0, 1, 2, Some(Frontier);
+    0x39 => CODECOPY, 0, 3, 0, 3, Some(Frontier);
+    0x3A => GASPRICE, 0, 0, 1, 2, Some(Frontier);
+    0x3B => EXTCODESIZE, 0, 1, 1, 100, Some(Frontier);
+    0x3C => EXTCODECOPY, 0, 4, 0, 100, Some(Frontier);
+    0x3D => RETURNDATASIZE, 0, 0, 1, 2, Some(Byzantium);
+    0x3E => RETURNDATACOPY, 0, 3, 0, 3, Some(Byzantium);
+    0x3F => EXTCODEHASH, 0, 1, 1, 100, Some(Constantinople);
+
+    0x40 => BLOCKHASH, 0, 1, 1, 20, Some(Frontier);
+    0x41 => COINBASE, 0, 0, 1, 2, Some(Frontier);
+    0x42 => TIMESTAMP, 0, 0, 1, 2, Some(Frontier);
+    0x43 => NUMBER, 0, 0, 1, 2, Some(Frontier);
+    0x44 => DIFFICULTY, 0, 0, 1, 2, Some(Frontier);
+    0x45 => GASLIMIT, 0, 0, 1, 2, Some(Frontier);
+    0x46 => CHAINID, 0, 0, 1, 2, Some(Istanbul);
+    0x47 => SELFBALANCE, 0, 0, 1, 5, Some(Istanbul);
+    0x48 => BASEFEE, 0, 0, 1, 2, Some(London);
+    0x49 => BLOBHASH, 0, 1, 1, 3, Some(Cancun);
+    0x4A => BLOBBASEFEE, 0, 0, 1, 2, Some(Cancun);
+
+    0x50 => POP, 0, 1, 0, 2, Some(Frontier);
+    0x51 => MLOAD, 0, 1, 1, 3, Some(Frontier);
+    0x52 => MSTORE, 0, 2, 0, 3, Some(Frontier);
+    0x53 => MSTORE8, 0, 2, 0, 3, Some(Frontier);
+    0x54 => SLOAD, 0, 1, 1, 100, Some(Frontier);
+    0x55 => SSTORE, 0, 2, 0, 100, Some(Frontier);
+    0x56 => JUMP, 0, 1, 0, 8, Some(Frontier);
+    0x57 => JUMPI, 0, 2, 0, 10, Some(Frontier);
+    0x58 => PC, 0, 0, 1, 2, Some(Frontier);
+    0x59 => MSIZE, 0, 0, 1, 2, Some(Frontier);
+    0x5A => GAS, 0, 0, 1, 2, Some(Frontier);
+    0x5B => JUMPDEST, 0, 0, 0, 1, Some(Frontier);
+    0x5C => TLOAD, 0, 1, 1, 100, Some(Cancun);
+    0x5D => TSTORE, 0, 2, 0, 100, Some(Cancun);
+    0x5E => MCOPY, 0, 3, 0, 3, Some(Cancun);
+    0x5F => PUSH0, 0, 0, 1, 2, Some(Shanghai);
+
+    0x60 => PUSH1, 1, 0, 1, 3, Some(Frontier);
+    0x61 => PUSH2, 2, 0, 1, 3, Some(Frontier);
+    0x62 => PUSH3, 3, 0, 1, 3, Some(Frontier);
+    0x63 => PUSH4, 4, 0, 1, 3, Some(Frontier);
+    0x64 => PUSH5, 5, 0, 1, 3, Some(Frontier);
+    0x65 => PUSH6, 6, 0, 1, 3, Some(Frontier);
+    0x66 => PUSH7, 7, 0, 1, 3, Some(Frontier);
+    0x67 => PUSH8, 8, 0, 1, 3, Some(Frontier);
+    0x68 => PUSH9, 9, 0, 1, 3, Some(Frontier);
+    0x69 => PUSH10, 10, 0, 1, 3, Some(Frontier);
+    0x6A => PUSH11, 11, 0, 1, 3, Some(Frontier);
+    0x6B => PUSH12, 12, 0, 1, 3, Some(Frontier);
+    0x6C => PUSH13, 13, 0, 1, 3, Some(Frontier);
+    0x6D => PUSH14, 14, 0, 1, 3, Some(Frontier);
+    0x6E => PUSH15, 15, 0, 1, 3, Some(Frontier);
+    0x6F => PUSH16, 16, 0, 1, 3, Some(Frontier);
+    0x70 => PUSH17, 17, 0, 1, 3, Some(Frontier);
+    0x71 => PUSH18, 18, 0, 1, 3, Some(Frontier);
+    0x72 => PUSH19, 19, 0, 1, 3, Some(Frontier);
+    0x73 => PUSH20, 20, 0, 1, 3, Some(Frontier);
+    0x74 => PUSH21, 21, 0, 1, 3, Some(Frontier);
+    0x75 => PUSH22, 22, 0, 1, 3, Some(Frontier);
+    0x76 => PUSH23, 23, 0, 1, 3, Some(Frontier);
+    0x77 => PUSH24, 24, 0, 1, 3, Some(Frontier);
+    0x78 => PUSH25, 25, 0, 1, 3, Some(Frontier);
+    0x79 => PUSH26, 26, 0, 1, 3, Some(Frontier);
+    0x7A => PUSH27, 27, 0, 1, 3, Some(Frontier);
+    0x7B => PUSH28, 28, 0, 1, 3, Some(Frontier);
+    0x7C => PUSH29, 29, 0, 1, 3, Some(Frontier);
+    0x7D => PUSH30, 30, 0, 1, 3, Some(Frontier);
+    0x7E => PUSH31, 31, 0, 1, 3, Some(Frontier);
+    0x7F => PUSH32, 32, 0, 1, 3, Some(Frontier);
+
+    0x80 => DUP1, 0, 1, 2, 3, Some(Frontier);
+    0x81 => DUP2, 0, 2, 3, 3, Some(Frontier);
+    0x82 => DUP3, 0, 3, 4, 3, Some(Frontier);
+    0x83 => DUP4, 0, 4, 5, 3, Some(Frontier);
+    0x84 => DUP5, 0, 5, 6, 3, Some(Frontier);
+    0x85 => DUP6, 0, 6, 7, 3, Some(Frontier);
+    0x86 => DUP7, 0, 7, 8, 3, Some(Frontier);
+    0x87 => DUP8, 0, 8, 9, 3, Some(Frontier);
+    0x88 => DUP9, 0, 9, 10, 3, Some(Frontier);
+    0x89 => DUP10, 0, 10, 11, 3, Some(Frontier);
+    0x8A => DUP11, 0, 11, 12, 3, Some(Frontier);
+    0x8B => DUP12, 0, 12, 13, 3, Some(Frontier);
+    0x8C => DUP13, 0, 13, 14, 3, Some(Frontier);
+    0x8D => DUP14, 0, 14, 15, 3, Some(Frontier);
+    0x8E => DUP15, 0, 15, 16, 3, Some(Frontier);
+    0x8F => DUP16, 0, 16, 17, 3, Some(Frontier);
+
+    0x90 => SWAP1, 0, 2, 2, 3, Some(Frontier);
+    0x91 => SWAP2, 0, 3, 3, 3, Some(Frontier);
+    0x92 => SWAP3, 0, 4, 4, 3, Some(Frontier);
+    0x93 => SWAP4, 0, 5, 5, 3, Some(Frontier);
+    0x94 => SWAP5, 0, 6, 6, 3, Some(Frontier);
+    0x95 => SWAP6, 0, 7, 7, 3, Some(Frontier);
+    0x96 => SWAP7, 0, 8, 8, 3, Some(Frontier);
+    0x97 => SWAP8, 0, 9, 9, 3, Some(Frontier);
+    0x98 => SWAP9, 0, 10, 10, 3, Some(Frontier);
+    0x99 => SWAP10, 0, 11, 11, 3, Some(Frontier);
+    0x9A => SWAP11, 0, 12, 12, 3, Some(Frontier);
+    0x9B => SWAP12, 0, 13, 13, 3, Some(Frontier);
+    0x9C => SWAP13, 0, 14, 14, 3, Some(Frontier);
+    0x9D => SWAP14, 0, 15, 15, 3, Some(Frontier);
+    0x9E => SWAP15, 0, 16, 16, 3, Some(Frontier);
+    0x9F => SWAP16, 0, 17, 17, 3, Some(Frontier);
+
+    0xA0 => LOG0, 0, 2, 0, 375, Some(Frontier);
+    0xA1 => LOG1, 0, 3, 0, 750, Some(Frontier);
+    0xA2 => LOG2, 0, 4, 0, 1125, Some(Frontier);
+    0xA3 => LOG3, 0, 5, 0, 1500, Some(Frontier);
+    0xA4 => LOG4, 0, 6, 0, 1875, Some(Frontier);
+
+    0xD0 => DATALOAD, 0, 1, 1, 4, None;
+    0xD1 => DATALOADN, 2, 0, 1, 3, None;
+    0xD2 => DATASIZE, 0, 0, 1, 2, None;
+    0xD3 => DATACOPY, 0, 3, 0, 3, None;
+
+    0xE0 => RJUMP, 2, 0, 0, 2, None;
+    0xE1 => RJUMPI, 2, 1, 0, 4, None;
+    0xE2 => RJUMPV, 1, 1, 0, 4, None;
+    0xE3 => CALLF, 2, 0, 0, 5, None;
+    0xE4 => RETF, 0, 0, 0, 3, None;
+    0xE5 => JUMPF, 2, 0, 0, 5, None;
+    0xE6 => DUPN, 1, 0, 1, 3, None;
+    0xE7 => SWAPN, 1, 0, 0, 3, None;
+    0xE8 => EXCHANGE, 1, 0, 0, 3, None;
+    0xEC => EOFCREATE, 1, 4, 1, 32000, None;
+    0xEE => RETURNCONTRACT, 1, 2, 0, 0, None;
+
+    0xF0 => CREATE, 0, 3, 1, 32000, Some(Frontier);
+    0xF1 => CALL, 0, 7, 1, 100, Some(Frontier);
+    0xF2 => CALLCODE, 0, 7, 1, 100, Some(Frontier);
+    0xF3 => RETURN, 0, 2, 0, 0, Some(Frontier);
+    0xF4 => DELEGATECALL, 0, 6, 1, 100, Some(Homestead);
+    0xF5 => CREATE2, 0, 4, 1, 32000, Some(Constantinople);
+    0xF7 => RETURNDATALOAD, 0, 1, 1, 3, None;
+    0xF8 => EXTCALL, 0, 4, 1, 100, None;
+    0xF9 => EXTDELEGATECALL, 0, 3, 1, 100, None;
+    0xFA => STATICCALL, 0, 6, 1, 100, Some(Byzantium);
+    0xFB => EXTSTATICCALL, 0, 3, 1, 100, None;
+    0xFD => REVERT, 0, 2, 0, 0, Some(Byzantium);
+    0xFE => INVALID, 0, 0, 0, 0, Some(Frontier);
+    0xFF => SELFDESTRUCT, 0, 1, 0, 5000, Some(Frontier);
 }
 
 impl Opcode {
-    /// Attempts to convert a byte into a defined Opcode.
-    /// Returns `None` if the byte does not correspond to a valid opcode.
-    pub fn from_u8(byte: u8) -> Option<Self> {
-        match byte {
-            0x00 => Some(Self::STOP),
-            0x01 => Some(Self::ADD),
-            0x02 => Some(Self::MUL),
-            0x03 => Some(Self::SUB),
-            0x04 => Some(Self::DIV),
-            0x05 => Some(Self::SDIV),
-            0x06 => Some(Self::MOD),
-            0x07 => Some(Self::SMOD),
-            0x08 => Some(Self::ADDMOD),
-            0x09 => Some(Self::MULMOD),
-            0x0A => Some(Self::EXP),
-            0x0B => Some(Self::SIGNEXTEND),
-
-            0x10 => Some(Self::LT),
-            0x11 => Some(Self::GT),
-            0x12 => Some(Self::SLT),
-            0x13 => Some(Self::SGT),
-            0x14 => Some(Self::EQ),
-            0x15 => Some(Self::ISZERO),
-            0x16 => Some(Self::AND),
-            0x17 => Some(Self::OR),
-            0x18 => Some(Self::XOR),
-            0x19 => Some(Self::NOT),
-            0x1A => Some(Self::BYTE),
-            0x1B => Some(Self::SHL),
-            0x1C => Some(Self::SHR),
-            0x1D => Some(Self::SAR),
-
-            0x20 => Some(Self::KECCAK256),
-
-            0x30 => Some(Self::ADDRESS),
-            0x31 => Some(Self::BALANCE),
-            0x32 => Some(Self::ORIGIN),
-            0x33 => Some(Self::CALLER),
-            0x34 => Some(Self::CALLVALUE),
-            0x35 => Some(Self::CALLDATALOAD),
-            0x36 => Some(Self::CALLDATASIZE),
-            0x37 => Some(Self::CALLDATACOPY),
-            0x38 => Some(Self::CODESIZE),
-            0x39 => Some(Self::CODECOPY),
-            0x3A => Some(Self::GASPRICE),
-            0x3B => Some(Self::EXTCODESIZE),
-            0x3C => Some(Self::EXTCODECOPY),
-            0x3D => Some(Self::RETURNDATASIZE),
-            0x3E => Some(Self::RETURNDATACOPY),
-            0x3F => Some(Self::EXTCODEHASH),
-
-            0x40 => Some(Self::BLOCKHASH),
-            0x41 => Some(Self::COINBASE),
-            0x42 => Some(Self::TIMESTAMP),
-            0x43 => Some(Self::NUMBER),
-            0x44 => Some(Self::DIFFICULTY),
-            0x45 => Some(Self::GASLIMIT),
-            0x46 => Some(Self::CHAINID),
-            0x47 => Some(Self::SELFBALANCE),
-            0x48 => Some(Self::BASEFEE),
-            0x49 => Some(Self::BLOBHASH),
-            0x4A => Some(Self::BLOBBASEFEE),
-
-            0x50 => Some(Self::POP),
-            0x51 => Some(Self::MLOAD),
-            0x52 => Some(Self::MSTORE),
-            0x53 => Some(Self::MSTORE8),
-            0x54 => Some(Self::SLOAD),
-            0x55 => Some(Self::SSTORE),
-            0x56 => Some(Self::JUMP),
-            0x57 => Some(Self::JUMPI),
-            0x58 => Some(Self::PC),
-            0x59 => Some(Self::MSIZE),
-            0x5A => Some(Self::GAS),
-            0x5B => Some(Self::JUMPDEST),
-            0x5C => Some(Self::TLOAD),
-            0x5D => Some(Self::TSTORE),
-            0x5E => Some(Self::MCOPY),
-
-            0x5F => Some(Self::PUSH0),
-            0x60 => Some(Self::PUSH1),
-            0x61 => Some(Self::PUSH2),
-            0x62 => Some(Self::PUSH3),
-            0x63 => Some(Self::PUSH4),
-            0x64 => Some(Self::PUSH5),
-            0x65 => Some(Self::PUSH6),
-            0x66 => Some(Self::PUSH7),
-            0x67 => Some(Self::PUSH8),
-            0x68 => Some(Self::PUSH9),
-            0x69 => Some(Self::PUSH10),
-            0x6A => Some(Self::PUSH11),
-            0x6B => Some(Self::PUSH12),
-            0x6C => Some(Self::PUSH13),
-            0x6D => Some(Self::PUSH14),
-            0x6E => Some(Self::PUSH15),
-            0x6F => Some(Self::PUSH16),
-            0x70 => Some(Self::PUSH17),
-            0x71 => Some(Self::PUSH18),
-            0x72 => Some(Self::PUSH19),
-            0x73 => Some(Self::PUSH20),
-            0x74 => Some(Self::PUSH21),
-            0x75 => Some(Self::PUSH22),
-            0x76 => Some(Self::PUSH23),
-            0x77 => Some(Self::PUSH24),
-            0x78 => Some(Self::PUSH25),
-            0x79 => Some(Self::PUSH26),
-            0x7A => Some(Self::PUSH27),
-            0x7B => Some(Self::PUSH28),
-            0x7C => Some(Self::PUSH29),
-            0x7D => Some(Self::PUSH30),
-            0x7E => Some(Self::PUSH31),
-            0x7F => Some(Self::PUSH32),
-
-            0x80 => Some(Self::DUP1),
-            0x81 => Some(Self::DUP2),
-            0x82 => Some(Self::DUP3),
-            0x83 => Some(Self::DUP4),
-            0x84 => Some(Self::DUP5),
-            0x85 => Some(Self::DUP6),
-            0x86 => Some(Self::DUP7),
-            0x87 => Some(Self::DUP8),
-            0x88 => Some(Self::DUP9),
-            0x89 => Some(Self::DUP10),
-            0x8A => Some(Self::DUP11),
-            0x8B => Some(Self::DUP12),
-            0x8C => Some(Self::DUP13),
-            0x8D => Some(Self::DUP14),
-            0x8E => Some(Self::DUP15),
-            0x8F => Some(Self::DUP16),
-
-            0x90 => Some(Self::SWAP1),
-            0x91 => Some(Self::SWAP2),
-            0x92 => Some(Self::SWAP3),
-            0x93 => Some(Self::SWAP4),
-            0x94 => Some(Self::SWAP5),
-            0x95 => Some(Self::SWAP6),
-            0x96 => Some(Self::SWAP7),
-            0x97 => Some(Self::SWAP8),
-            0x98 => Some(Self::SWAP9),
-            0x99 => Some(Self::SWAP10),
-            0x9A => Some(Self::SWAP11),
-            0x9B => Some(Self::SWAP12),
-            0x9C => Some(Self::SWAP13),
-            0x9D => Some(Self::SWAP14),
-            0x9E => Some(Self::SWAP15),
-            0x9F => Some(Self::SWAP16),
-
-            0xA0 => Some(Self::LOG0),
-            0xA1 => Some(Self::LOG1),
-            0xA2 => Some(Self::LOG2),
-            0xA3 => Some(Self::LOG3),
-            0xA4 => Some(Self::LOG4),
-
-            0xD0 => Some(Self::DATALOAD),
-            0xD1 => Some(Self::DATALOADN),
-            0xD2 => Some(Self::DATASIZE),
-            0xD3 => Some(Self::DATACOPY),
-
-            0xE0 => Some(Self::RJUMP),
-            0xE1 => Some(Self::RJUMPI),
-            0xE2 => Some(Self::RJUMPV),
-            0xE3 => Some(Self::CALLF),
-            0xE4 => Some(Self::RETF),
-            0xE5 => Some(Self::JUMPF),
-            0xE6 => Some(Self::DUPN),
-            0xE7 => Some(Self::SWAPN),
-            0xE8 => Some(Self::EXCHANGE),
+    /// Static metadata for this opcode.
+    pub fn info(&self) -> &'static OpcodeInfo {
+        OPCODE_INFO[*self as usize]
+            .as_ref()
+            .expect("every Opcode variant has an OPCODE_INFO entry")
+    }
 
-            0xEC => Some(Self::EOFCREATE),
-            0xEE => Some(Self::RETURNCONTRACT),
+    /// Number of immediate bytes that follow this opcode in the code (`n` for `PUSHn`, zero otherwise).
+    pub fn immediate_size(&self) -> usize {
+        self.info().immediates as usize
+    }
 
-            0xF0 => Some(Self::CREATE),
-            0xF1 => Some(Self::CALL),
-            0xF2 => Some(Self::CALLCODE),
-            0xF3 => Some(Self::RETURN),
-            0xF4 => Some(Self::DELEGATECALL),
-            0xF5 => Some(Self::CREATE2),
+    /// Returns `(inputs, outputs)`: how many stack items this opcode pops and how many it pushes.
+    /// Used to validate the stack before dispatch and to patch the stack when a trapped instruction is skipped.
+    pub fn stack_io(&self) -> (usize, usize) {
+        let info = self.info();
+        (info.inputs as usize, info.outputs as usize)
+    }
 
-            0xF7 => Some(Self::RETURNDATALOAD),
-            0xF8 => Some(Self::EXTCALL),
-            0xF9 => Some(Self::EXTDELEGATECALL),
-            0xFA => Some(Self::STATICCALL),
-            0xFB => Some(Self::EXTSTATICCALL),
+    /// Static gas cost (see `OpcodeInfo::base_gas`).
+    pub fn base_gas(&self) -> u64 {
+        self.info().base_gas as u64
+    }
+}
 
-            0xFD => Some(Self::REVERT),
-            0xFE => Some(Self::INVALID),
-            0xFF => Some(Self::SELFDESTRUCT),
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.info().name)
+    }
+}
 
-            // All other byte values are undefined
-            _ => None,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_and_enum_agree_for_every_byte() {
+        for byte in 0..=255u8 {
+            match (Opcode::from_u8(byte), &OPCODE_INFO[byte as usize]) {
+                (Some(op), Some(info)) => {
+                    assert_eq!(op as u8, byte);
+                    assert_eq!(op.to_string(), info.name);
+                }
+                (None, None) => {}
+                (op, info) => panic!("byte {byte:#04x}: enum {op:?} vs table {info:?}"),
+            }
         }
     }
+
+    #[test]
+    fn families_have_expected_metadata() {
+        assert_eq!(Opcode::PUSH1.immediate_size(), 1);
+        assert_eq!(Opcode::PUSH32.immediate_size(), 32);
+        assert_eq!(Opcode::DUP16.stack_io(), (16, 17));
+        assert_eq!(Opcode::SWAP1.stack_io(), (2, 2));
+        assert_eq!(Opcode::LOG4.stack_io(), (6, 0));
+        assert_eq!(Opcode::CALL.stack_io(), (7, 1));
+        assert_eq!(Opcode::ADD.base_gas(), 3);
+        assert_eq!(Opcode::PUSH0.info().introduced, Some(SpecId::Shanghai));
+        assert_eq!(Opcode::RJUMP.info().introduced, None);
+    }
 }
@@ -1,28 +1,51 @@
-use alloy::primitives::{Address, I256, U256};
-
-use crate::{Evm, ProgramExitStatus};
+use alloy::primitives::{Address, B256, Bytes, I256, Log, U256, keccak256};
+
+use primitives::{
+    errors::EvmErrors,
+    utils::{address_to_word, be_byte, load_word_padded, word_to_address},
+};
+
+use crate::{
+    Evm, ProgramExitStatus,
+    access::{COLD_SLOAD_GAS, WARM_STORAGE_READ_GAS},
+    call_inputs::{CallInputs, CallScheme, CreateInputs},
+    gas::{
+        CALL_STIPEND, COPY_WORD_GAS, KECCAK256_WORD_GAS, LOG_DATA_GAS, exp_byte_gas, sstore_cost,
+        sstore_refund, static_gas,
+    },
+    opcodes::Opcode,
+    spec::SpecId,
+};
 
 // ref == https://www.evm.codes/
 
 /// STOP opcode handler
-/// - Semantics: halt execution and set program status to Success.
+/// - Semantics: halt execution and set program status to Success, with no output.
 /// - Stack effects: none.
-pub fn stop(evm: &mut Evm) {
-    evm.status = ProgramExitStatus::Success;
+pub fn stop(evm: &mut Evm) -> Result<(), EvmErrors> {
+    evm.status = ProgramExitStatus::Success {
+        output: Bytes::new(),
+        gas_used: evm.gas.spent(),
+    };
+
+    Ok(())
 }
 
 /// ADD opcode handler
 /// NB: No check made for overflow.
 /// - Semantics: pop two 256-bit values from the stack (call them `a` and `b`) and push `a + b`.
 /// - Stack order in this implementation:
-///   * `let a = evm.stack.pop().unwrap();` // top of stack
-///   * `let b = evm.stack.pop().unwrap();` // next item
+///   * `let a = evm.stack.try_pop()?;` // top of stack
+///   * `let b = evm.stack.try_pop()?;` // next item
+///
 ///   Result pushed: `a + b`.
 /// - Example: stack before [0x02, 0x03] (top = 0x03) after `add` -> [0x05] (top = 0x05).
-pub fn add(evm: &mut Evm) {
-    let a = evm.stack.pop().unwrap();
-    let b = evm.stack.pop().unwrap();
-    evm.stack.push(a + b).unwrap();
+pub fn add(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let a = evm.stack.try_pop()?;
+    let b = evm.stack.try_pop()?;
+    evm.stack.push(a + b)?;
+
+    Ok(())
 }
 
 /// SUB opcode handler
@@ -30,166 +53,200 @@ pub fn add(evm: &mut Evm) {
 /// - Note on order: because we pop `a` then `b`, the computed value is `a - b` where `a` is the top value.
 /// - Example: stack [0x05, 0x02] (top=0x02) -> after `sub` push (0x02 - 0x05) mod 2^256.
 /// - Caveat: the implementation uses `U256` arithmetic; negatives wrap around in unsigned interpretation.
-pub fn sub(evm: &mut Evm) {
-    let a = evm.stack.pop().unwrap();
-    let b = evm.stack.pop().unwrap();
-    evm.stack.push(a - b).unwrap();
+pub fn sub(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let a = evm.stack.try_pop()?;
+    let b = evm.stack.try_pop()?;
+    evm.stack.push(a - b)?;
+
+    Ok(())
 }
 
 /// MUL opcode handler
 /// - Semantics: pop `a`, pop `b`, push `a * b`.
 /// - Example: [2, 3] -> push 6.
-pub fn mul(evm: &mut Evm) {
-    let a = evm.stack.pop().unwrap();
-    let b = evm.stack.pop().unwrap();
-    evm.stack.push(a * b).unwrap();
+pub fn mul(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let a = evm.stack.try_pop()?;
+    let b = evm.stack.try_pop()?;
+    evm.stack.push(a * b)?;
+
+    Ok(())
 }
 
 /// DIV opcode handler (unsigned)
 /// - Semantics: pops `a` and `b`, if `b == 0` push 0, else push `a / b`.
 /// - Edge-case: Division by zero returns zero per EVM semantics implemented here.
 /// - Example: [10, 2] -> push 5. [10, 0] -> push 0.
-pub fn div(evm: &mut Evm) {
-    let a = evm.stack.pop().unwrap();
-    let b = evm.stack.pop().unwrap();
+pub fn div(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let a = evm.stack.try_pop()?;
+    let b = evm.stack.try_pop()?;
     if b == U256::ZERO {
-        evm.stack.push(U256::ZERO).unwrap();
+        evm.stack.push(U256::ZERO)?;
     } else {
-        evm.stack.push(a / b).unwrap();
+        evm.stack.push(a / b)?;
     }
+
+    Ok(())
 }
 
 /// SDIV opcode handler (signed division)
 /// - Semantics: treat stack values as signed 256-bit integers, divide, then push unsigned representation of result
 ///   * Converts `U256` limbs into `I256` for signed arithmetic and converts result back to `U256`.
-///   * Division by zero pushes `U256::ZERO`.
-pub fn sdiv(evm: &mut Evm) {
-    let a = evm.stack.pop().unwrap();
-    let b = evm.stack.pop().unwrap();
+///   * Division by zero pushes `U256::ZERO`; `MIN / -1` pushes `MIN`.
+pub fn sdiv(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let a = evm.stack.try_pop()?;
+    let b = evm.stack.try_pop()?;
 
     let a_int = I256::from_limbs(*a.as_limbs());
     let b_int = I256::from_limbs(*b.as_limbs());
 
     if b_int == I256::ZERO {
-        evm.stack.push(U256::ZERO).unwrap();
+        evm.stack.push(U256::ZERO)?;
     } else {
-        let result = a_int / b_int;
+        // MIN / -1 overflows; the EVM wraps it to MIN.
+        let result = a_int.wrapping_div(b_int);
         let result_unsigned = U256::from_limbs(*result.as_limbs());
-        evm.stack.push(result_unsigned).unwrap();
+        evm.stack.push(result_unsigned)?;
     }
+
+    Ok(())
 }
 
 /// ADDMOD opcode handler
-/// - Semantics: pop `a`, `b`, `c`, compute `(a + b) % c`. If `c == 0` push 0.
-/// - Notes: This implementation checks `b` for zero in the original code.
+/// - Semantics: pop `a`, `b`, `c`, compute `(a + b) % c` without wrapping the intermediate sum. If `c == 0` push 0.
 /// - Example: a=2,b=3,c=5 -> (2+3)%5 = 0.
-pub fn addmod(evm: &mut Evm) {
-    let a = evm.stack.pop().unwrap();
-    let b = evm.stack.pop().unwrap();
-    let c = evm.stack.pop().unwrap();
+pub fn addmod(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let a = evm.stack.try_pop()?;
+    let b = evm.stack.try_pop()?;
+    let c = evm.stack.try_pop()?;
 
-    if b == U256::ZERO {
-        evm.stack.push(U256::ZERO).unwrap();
-    } else {
-        let addition = a + b;
-        let result = addition % c;
-        evm.stack.push(result).unwrap();
-    }
+    evm.stack.push(a.add_mod(b, c))?;
+
+    Ok(())
 }
 
 /// MULMOD opcode handler
-/// - Semantics: pop `a`, `b`, `c`, compute `(a * b) % c`. If `c == 0` push 0.
+/// - Semantics: pop `a`, `b`, `c`, compute `(a * b) % c` without wrapping the intermediate product. If `c == 0` push 0.
 /// - Example: a=2,b=3,c=4 -> (2*3)%4 = 2.
-pub fn mulmod(evm: &mut Evm) {
-    let a = evm.stack.pop().unwrap();
-    let b = evm.stack.pop().unwrap();
-    let c = evm.stack.pop().unwrap();
+pub fn mulmod(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let a = evm.stack.try_pop()?;
+    let b = evm.stack.try_pop()?;
+    let c = evm.stack.try_pop()?;
 
-    if b == U256::ZERO {
-        evm.stack.push(U256::ZERO).unwrap();
-    } else {
-        let multiplication = a * b;
-        let result = multiplication % c;
-        evm.stack.push(result).unwrap();
-    }
+    evm.stack.push(a.mul_mod(b, c))?;
+
+    Ok(())
 }
 
 /// MOD opcode handler (unsigned modulo)
 /// - Semantics: pop `a`, pop `b`, if `b == 0` push 0 else push `a % b`.
 /// - Example: [10,3] -> push 1.
-pub fn modulo(evm: &mut Evm) {
-    let a = evm.stack.pop().unwrap();
-    let b = evm.stack.pop().unwrap();
+pub fn modulo(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let a = evm.stack.try_pop()?;
+    let b = evm.stack.try_pop()?;
     if b == U256::ZERO {
-        evm.stack.push(U256::ZERO).unwrap();
+        evm.stack.push(U256::ZERO)?;
     } else {
-        evm.stack.push(a % b).unwrap();
+        evm.stack.push(a % b)?;
     }
+
+    Ok(())
 }
 
 /// SMOD opcode handler (signed modulo)
-/// - Semantics: behaves similarly to MOD but for signed values. This implementation uses unsigned types directly.
-/// - Note: This implementation currently delegates to unsigned modulo; adjust if full signed semantics are required.
-pub fn smod(evm: &mut Evm) {
-    let a: U256 = evm.stack.pop().unwrap();
-    let b: U256 = evm.stack.pop().unwrap();
-    if b == U256::ZERO {
-        evm.stack.push(U256::ZERO).unwrap();
+/// - Semantics: pop `a`, pop `b`, treat both as two's-complement signed 256-bit integers and push `a % b`. The
+///   result takes the sign of the dividend `a`; if `b == 0` push 0.
+/// - Example: a=-8,b=3 -> push -2; a=-1,b=2 -> push -1.
+pub fn smod(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let a = evm.stack.try_pop()?;
+    let b = evm.stack.try_pop()?;
+
+    let a_int = I256::from_limbs(*a.as_limbs());
+    let b_int = I256::from_limbs(*b.as_limbs());
+
+    if b_int == I256::ZERO {
+        evm.stack.push(U256::ZERO)?;
     } else {
-        evm.stack.push(a % b).unwrap();
+        // `wrapping_rem` only differs from `%` for MIN % -1, which is 0 either way but would overflow.
+        let result = a_int.wrapping_rem(b_int);
+        evm.stack.push(U256::from_limbs(*result.as_limbs()))?;
     }
+
+    Ok(())
 }
 
 /// EXP opcode handler (exponentiation)
-/// - Semantics: pop base, pop exponent, compute base.pow(exponent) and push result.
-/// - Warning: exponentiation may be very expensive; no gas accounting here.
+/// - Semantics: pop base, pop exponent, compute base.pow(exponent) (mod 2^256) and push result.
+/// - Gas: `exp_byte_gas(spec)` per significant byte of the exponent, charged before any work is done, so the
+///   square-and-multiply loop (at most 256 rounds) is always paid for up front.
 /// - Example: base=2, exponent=3 -> push 8.
-pub fn exp(evm: &mut Evm) {
-    let base: U256 = evm.stack.pop().unwrap();
-    let exponent: U256 = evm.stack.pop().unwrap();
+pub fn exp(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let base: U256 = evm.stack.try_pop()?;
+    let exponent: U256 = evm.stack.try_pop()?;
+    if !evm
+        .gas
+        .record_cost(exp_byte_gas(evm.spec) * exponent.byte_len() as u64)
+    {
+        return Err(EvmErrors::OutOfGas);
+    }
     let result: U256 = base.pow(exponent);
-    evm.stack.push(result).unwrap();
+    evm.stack.push(result)?;
+
+    Ok(())
 }
 
-pub fn signextend(evm: &mut Evm) {
-    let size_in_byte = evm.stack.pop().unwrap();
-    let integer = evm.stack.pop().unwrap();
+/// SIGNEXTEND opcode handler
+/// - Semantics: pop b, pop x, push x sign-extended from its byte `b` (counted from the least significant end).
+///   For b >= 31 the value already fills the word and is pushed unchanged.
+/// - Example: [0, 0xff] -> push 2^256 - 1; [0, 0x7f] -> push 0x7f.
+pub fn signextend(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let size_in_byte = evm.stack.try_pop()?;
+    let integer = evm.stack.try_pop()?;
 
-    // size_in_byte.to_be_bytes().unwrap();
-    if size_in_byte >= U256::from(32) {
-        evm.stack.push(integer).unwrap();
+    if size_in_byte >= U256::from(31) {
+        evm.stack.push(integer)?;
     } else {
-        let mask = U256::from(2).pow(size_in_byte * U256::from(8)) - U256::ONE;
-        let extended = integer & mask;
-        evm.stack.push(extended).unwrap();
+        let sign_bit = size_in_byte.to::<usize>() * 8 + 7;
+        let mask = (U256::ONE << (sign_bit + 1)) - U256::ONE;
+        let extended = if integer.bit(sign_bit) {
+            integer | !mask
+        } else {
+            integer & mask
+        };
+        evm.stack.push(extended)?;
     }
+
+    Ok(())
 }
 
 /// LT opcode handler (unsigned less-than)
 /// - Semantics: pop left, pop right, push 1 if left < right else 0.
 /// - Example: [2,3] -> push 1.
-pub fn lt(evm: &mut Evm) {
-    let left = evm.stack.pop().unwrap();
-    let right = evm.stack.pop().unwrap();
+pub fn lt(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let left = evm.stack.try_pop()?;
+    let right = evm.stack.try_pop()?;
 
     let result = left.lt(&right);
-    evm.stack.push(U256::from(result)).unwrap();
+    evm.stack.push(U256::from(result))?;
+
+    Ok(())
 }
 
 /// GT opcode handler (unsigned greater-than)
 /// - Semantics: pop left, pop right, push 1 if left > right else 0.
-pub fn gt(evm: &mut Evm) {
-    let left = evm.stack.pop().unwrap();
-    let right = evm.stack.pop().unwrap();
+pub fn gt(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let left = evm.stack.try_pop()?;
+    let right = evm.stack.try_pop()?;
     let result = left.gt(&right);
-    evm.stack.push(U256::from(result)).unwrap();
+    evm.stack.push(U256::from(result))?;
+
+    Ok(())
 }
 
 /// SLT opcode handler (signed less-than)
 /// - Semantics: convert both operands to signed `I256`, compare, push 1 if left < right else 0.
-pub fn slt(evm: &mut Evm) {
-    let left: U256 = evm.stack.pop().unwrap();
-    let right: U256 = evm.stack.pop().unwrap();
+pub fn slt(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let left: U256 = evm.stack.try_pop()?;
+    let right: U256 = evm.stack.try_pop()?;
 
     let left_int = I256::from_limbs(*left.as_limbs());
     let right_int = I256::from_limbs(*right.as_limbs());
@@ -197,14 +254,16 @@ pub fn slt(evm: &mut Evm) {
     let result = left_int.lt(&right_int);
     let unsigned_result = U256::from(result);
 
-    evm.stack.push(unsigned_result).unwrap();
+    evm.stack.push(unsigned_result)?;
+
+    Ok(())
 }
 
 /// SGT opcode handler (signed greater-than)
 /// - Semantics: convert both operands to `I256` and compare.
-pub fn sgt(evm: &mut Evm) {
-    let left: U256 = evm.stack.pop().unwrap();
-    let right: U256 = evm.stack.pop().unwrap();
+pub fn sgt(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let left: U256 = evm.stack.try_pop()?;
+    let right: U256 = evm.stack.try_pop()?;
 
     let left_int = I256::from_limbs(*left.as_limbs());
     let right_int = I256::from_limbs(*right.as_limbs());
@@ -212,316 +271,1649 @@ pub fn sgt(evm: &mut Evm) {
     let result = left_int.gt(&right_int);
     let unsigned_result = U256::from(result);
 
-    evm.stack.push(unsigned_result).unwrap();
+    evm.stack.push(unsigned_result)?;
+
+    Ok(())
 }
 
 /// EQ opcode handler (equality)
 /// - Semantics: pop left, pop right, push 1 if equal else 0.
-pub fn eq(evm: &mut Evm) {
-    let left = evm.stack.pop().unwrap();
-    let right = evm.stack.pop().unwrap();
+pub fn eq(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let left = evm.stack.try_pop()?;
+    let right = evm.stack.try_pop()?;
 
     let result = left.eq(&right);
-    evm.stack.push(U256::from(result)).unwrap();
+    evm.stack.push(U256::from(result))?;
+
+    Ok(())
 }
 
 /// ISZERO opcode handler
 /// - Semantics: pop value, push 1 if value == 0 else 0.
-pub fn is_zero(evm: &mut Evm) {
-    let value = evm.stack.pop().unwrap();
+pub fn is_zero(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let value = evm.stack.try_pop()?;
 
     let result = value.is_zero();
-    evm.stack.push(U256::from(result)).unwrap();
+    evm.stack.push(U256::from(result))?;
+
+    Ok(())
 }
 
 /// AND opcode handler (bitwise)
 /// - Semantics: pop left, pop right, push bitwise-and result.
-pub fn and(evm: &mut Evm) {
-    let left = evm.stack.pop().unwrap();
-    let right = evm.stack.pop().unwrap();
+pub fn and(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let left = evm.stack.try_pop()?;
+    let right = evm.stack.try_pop()?;
 
     let result = left.bitand(right);
-    evm.stack.push(result).unwrap();
+    evm.stack.push(result)?;
+
+    Ok(())
 }
 
-pub fn byte(evm: &mut Evm) {
-    let index = evm.stack.pop().unwrap();
-    let value = evm.stack.pop().unwrap();
+/// OR opcode handler (bitwise)
+/// - Semantics: pop left, pop right, push bitwise-or result.
+pub fn or(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let left = evm.stack.try_pop()?;
+    let right = evm.stack.try_pop()?;
 
-    if index.as_limbs()[0] > 32 {
-        evm.stack.push(U256::ZERO).unwrap();
+    evm.stack.push(left | right)?;
+
+    Ok(())
+}
+
+/// XOR opcode handler (bitwise)
+/// - Semantics: pop left, pop right, push bitwise-xor result.
+pub fn xor(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let left = evm.stack.try_pop()?;
+    let right = evm.stack.try_pop()?;
+
+    evm.stack.push(left ^ right)?;
+
+    Ok(())
+}
+
+/// NOT opcode handler (bitwise)
+/// - Semantics: pop value, push its bitwise complement (`U256::MAX - value`).
+pub fn not(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let value = evm.stack.try_pop()?;
+
+    evm.stack.push(!value)?;
+
+    Ok(())
+}
+
+/// SHL opcode handler (EIP-145)
+/// - Semantics: pop shift, pop value, push `value << shift`. Shifts of 256 or more push 0.
+pub fn shl(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let shift = evm.stack.try_pop()?;
+    let value = evm.stack.try_pop()?;
+
+    if shift >= U256::from(256) {
+        evm.stack.push(U256::ZERO)?;
+    } else {
+        evm.stack.push(value << shift.as_limbs()[0] as usize)?;
+    }
+
+    Ok(())
+}
+
+/// SHR opcode handler (EIP-145, logical shift)
+/// - Semantics: pop shift, pop value, push `value >> shift` filling with zeros. Shifts of 256 or more push 0.
+pub fn shr(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let shift = evm.stack.try_pop()?;
+    let value = evm.stack.try_pop()?;
+
+    if shift >= U256::from(256) {
+        evm.stack.push(U256::ZERO)?;
     } else {
-        let byte_index = index.as_limbs()[0] as usize;
-        let byte = value.as_limbs()[byte_index];
-        evm.stack.push(U256::from(byte)).unwrap();
+        evm.stack.push(value >> shift.as_limbs()[0] as usize)?;
     }
+
+    Ok(())
+}
+
+/// SAR opcode handler (EIP-145, arithmetic shift)
+/// - Semantics: pop shift, pop value, push `value >> shift` treating value as a signed `I256`, so the sign bit is
+///   copied into the vacated bits.
+/// - Shifts of 256 or more saturate: 0 for non-negative values, all ones (-1) for negative values.
+pub fn sar(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let shift = evm.stack.try_pop()?;
+    let value = evm.stack.try_pop()?;
+
+    let value_int = I256::from_limbs(*value.as_limbs());
+
+    let result = if shift >= U256::from(256) {
+        if value_int.is_negative() {
+            I256::MINUS_ONE
+        } else {
+            I256::ZERO
+        }
+    } else {
+        value_int.asr(shift.as_limbs()[0] as usize)
+    };
+
+    evm.stack.push(U256::from_limbs(*result.as_limbs()))?;
+
+    Ok(())
+}
+
+/// BYTE opcode handler
+/// - Semantics: pop index `i`, pop value, push the `i`-th byte of value counting from the most significant (0)
+///   end. Indices of 32 or more push 0.
+pub fn byte(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let index = evm.stack.try_pop()?;
+    let value = evm.stack.try_pop()?;
+
+    evm.stack.push(U256::from(be_byte(value, index)))?;
+
+    Ok(())
 }
 
 /// MSTORE opcode handler
 /// - Semantics: pop offset, pop value, store 32-byte word `value` at memory[offset..offset+32].
 /// - Stack order: this handler pops `offset` first and then `value`, matching the call-site convention
 ///   where offset was pushed after value (e.g., push value; push offset; MSTORE).
-pub fn mstore(evm: &mut Evm) {
-    let offset = evm.stack.pop().unwrap();
-    let value = evm.stack.pop().unwrap();
-
-    let offset = offset.as_limbs()[0] as usize;
+pub fn mstore(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let offset = evm.stack.try_pop()?;
+    let value = evm.stack.try_pop()?;
+    let offset = memory_access(evm, offset, 32)?;
 
     evm.memory.store_word(offset, value);
+
+    Ok(())
 }
 
-/// ADDRESS opcode handler
-/// - Semantics: push the current executing contract's address (tx.to) as a 32-byte left-padded value.
-/// - Implementation: pads the 20-byte address into a 32-byte big-endian word and pushes it.
-pub fn address(evm: &mut Evm) {
-    let address: Address = evm.tx.to;
+/// KECCAK256 opcode handler
+/// - Semantics: pop offset, pop length, push `keccak256(memory[offset..offset+length])`.
+/// - Gas: `KECCAK256_WORD_GAS` per word hashed on top of the static 30, plus memory expansion.
+pub fn keccak_256(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let offset = evm.stack.try_pop()?;
+    let length = evm.stack.try_pop()?;
+
+    let len: usize = length.saturating_to();
+    let word_cost = (len as u64).div_ceil(32).saturating_mul(KECCAK256_WORD_GAS);
+    if !evm.gas.record_cost(word_cost) {
+        return Err(EvmErrors::OutOfGas);
+    }
+    let offset = memory_access(evm, offset, len)?;
+    let digest = if len == 0 {
+        keccak256([])
+    } else {
+        keccak256(&evm.memory.data[offset..offset + len])
+    };
 
-    let mut padded = [0u8; 32]; // length is 32 bytes
+    evm.stack.push(U256::from_be_bytes(digest.0))?;
 
-    // the address is 20bytes long, hence, padded with zero
-    padded[12..].copy_from_slice(address.as_slice()); //
+    Ok(())
+}
 
-    let value = U256::from_be_bytes(padded);
-    evm.stack.push(value).unwrap();
+/// ADDRESS opcode handler
+/// - Semantics: push the current executing contract's address (`context.address`) as a 32-byte left-padded value.
+pub fn address(evm: &mut Evm) -> Result<(), EvmErrors> {
+    evm.stack.push(address_to_word(evm.context.address))?;
+
+    Ok(())
 }
 
 /// BALANCE opcode handler
-/// - Semantics: push the balance of the account (usually the account specified by `evm.tx.from` here).
-/// - Note: this implementation unwraps the account entry;
-pub fn balance(evm: &mut Evm) {
-    let address: Address = evm.tx.from;
+/// - Semantics: pop an address (low 20 bytes of the word), push that account's balance; 0 for accounts that don't
+///   exist.
+/// - Gas: the cold-account surcharge on the address's first access since Berlin.
+pub fn balance(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let address = pop_account(evm)?;
+    let balance = evm
+        .storage
+        .data
+        .get(&address)
+        .map(|account| account.info.balance)
+        .unwrap_or_default();
+    evm.stack.push(balance)?;
+
+    Ok(())
+}
+
+/// Pop an address operand for BALANCE and the EXTCODE* opcodes, charge the cold-account surcharge on its first
+/// access since Berlin and mark it touched.
+fn pop_account(evm: &mut Evm) -> Result<Address, EvmErrors> {
+    let address = word_to_address(evm.stack.try_pop()?);
+    let surcharge = evm.account_access_surcharge(address);
+    if !evm.gas.record_cost(surcharge) {
+        return Err(EvmErrors::OutOfGas);
+    }
+    evm.storage.touch(address);
+    Ok(address)
+}
+
+/// CODESIZE opcode handler
+/// - Semantics: push the byte length of the executing code (the init code inside a creation).
+pub fn code_size(evm: &mut Evm) -> Result<(), EvmErrors> {
+    evm.stack.push(U256::from(evm.code.len()))?;
+
+    Ok(())
+}
+
+/// CODECOPY opcode handler
+/// - Semantics: pop destOffset, pop offset, pop length, copy `code[offset..offset+length]` of the executing code to
+///   `memory[destOffset..]`, zero-filling past the end of the code.
+/// - Gas: `COPY_WORD_GAS` per word copied on top of the static 3, plus memory expansion.
+pub fn code_copy(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let dest = evm.stack.try_pop()?;
+    let offset = evm.stack.try_pop()?;
+    let length = evm.stack.try_pop()?;
+    let (dest, len) = copy_access(evm, dest, length)?;
+    evm.memory
+        .store_padded(dest, evm.code.as_slice(), offset.saturating_to(), len);
+
+    Ok(())
+}
+
+/// EXTCODESIZE opcode handler
+/// - Semantics: pop an address, push the byte length of its code; 0 for accounts without code or that don't exist.
+/// - Gas: the cold-account surcharge on the address's first access since Berlin.
+pub fn ext_code_size(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let address = pop_account(evm)?;
+    let size = evm.storage.code(address).len();
+    evm.stack.push(U256::from(size))?;
+
+    Ok(())
+}
 
-    let address_account = evm.storage.data.get(&address).unwrap();
-    let balance: U256 = address_account.balance;
-    evm.stack.push(balance).unwrap();
+/// EXTCODECOPY opcode handler
+/// - Semantics: pop an address, destOffset, offset and length, copy `code[offset..offset+length]` of that
+///   account to `memory[destOffset..]`, zero-filling past the end of its code.
+/// - Gas: the cold-account surcharge, then `COPY_WORD_GAS` per word copied and memory expansion.
+pub fn ext_code_copy(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let address = pop_account(evm)?;
+    let dest = evm.stack.try_pop()?;
+    let offset = evm.stack.try_pop()?;
+    let length = evm.stack.try_pop()?;
+    let (dest, len) = copy_access(evm, dest, length)?;
+    evm.memory.store_padded(
+        dest,
+        evm.storage.code(address).as_slice(),
+        offset.saturating_to(),
+        len,
+    );
+
+    Ok(())
+}
+
+/// EXTCODEHASH opcode handler (EIP-1052, since Constantinople)
+/// - Semantics: pop an address, push the keccak256 of its code. An existing account without code pushes the
+///   empty-code hash `KECCAK256_EMPTY`; a missing or EIP-161 empty account pushes 0.
+/// - Gas: the cold-account surcharge on the address's first access since Berlin.
+pub fn ext_code_hash(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let address = pop_account(evm)?;
+    let hash = if evm.storage.is_empty_account(address) {
+        U256::ZERO
+    } else {
+        U256::from_be_bytes(evm.storage.code_hash(address).0)
+    };
+    evm.stack.push(hash)?;
+
+    Ok(())
 }
 
 /// ORIGIN opcode handler
 /// - Semantics: push the transaction origin address (tx.from) padded to 32 bytes.
-/// - Implementation mirrors `address` logic but uses `tx.from`.
-pub fn origin(evm: &mut Evm) {
-    let address: Address = evm.tx.from;
+pub fn origin(evm: &mut Evm) -> Result<(), EvmErrors> {
+    evm.stack.push(address_to_word(evm.tx.from))?;
 
-    let mut padded = [0u8; 32]; // length is 32 bytes
+    Ok(())
+}
 
-    // the address is 20bytes long, hence, padded with zero
-    padded[12..].copy_from_slice(address.as_slice()); //
+/// CALLER opcode handler
+/// - Semantics: push the address that made the current call, padded to 32 bytes.
+/// - Implementation: reads the executing frame (`context.caller`); at the top level that is `tx.from`.
+pub fn caller(evm: &mut Evm) -> Result<(), EvmErrors> {
+    evm.stack.push(address_to_word(evm.context.caller))?;
 
-    let value = U256::from_be_bytes(padded);
-    evm.stack.push(value).unwrap();
+    Ok(())
 }
 
 /// CALLVALUE opcode handler
-/// - Semantics: push the `tx.value` (amount of wei sent with the call).
-pub fn call_value(evm: &mut Evm) {
-    let value = evm.tx.value;
-    evm.stack.push(value).unwrap();
-}
-
-/// CALLDATALOAD partial handler
-/// - Semantics: intended to pop offset and push 32 bytes starting from `tx.data[offset]`.
-/// - Implementation note: this function reads the offset and prepares to use `tx.data` but the final conversion
-///   into a `U256` is left commented out. This must be completed to match EVM semantics and handle out-of-bounds reads.
-pub fn call_data_load(evm: &mut Evm) {
-    let offset = evm.stack.pop().unwrap();
-    let offset = offset.as_limbs()[0] as usize;
-
-    let data = evm.tx.data.as_slice();
-    // let value = U256::from_be_bytes(data[offset..offset + 32].);
-    // evm.stack.push(value).unwrap();
-}
-
-/// GASPRICE opcode handler (simplified)
-/// - Implementation pushes `tx.gas_limit` as a stand-in for gas price (this is not the usual meaning).
-/// - In EVM semantics GASPRICE should push `tx.gas_price` or chain gas price; adjust accordingly.
-pub fn gas_price(evm: &mut Evm) {
-    let gas_price = evm.tx.gas_limit;
-    evm.stack.push(gas_price).unwrap();
-}
-
-/// BLOCKHASH opcode handler (partial)
-/// - Semantics: pop block number `n`, if `n` is within the last 256 blocks return blockhash(n) else 0.
-/// - Implementation: checks if requested block number is greater than current block number and pushes 0 if so.
-/// - Note: full historical block-hash semantics are not implemented here.
-pub fn block_hash(evm: &mut Evm) {
-    // get the request block number from the stack
-    let block_number = evm.stack.pop().unwrap();
-
-    // get the current block number from the block environment
+/// - Semantics: push `context.value` (amount of wei sent with the current call).
+pub fn call_value(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let value = evm.context.value;
+    evm.stack.push(value)?;
+
+    Ok(())
+}
+
+/// CALLDATALOAD opcode handler
+/// - Semantics: pop offset, push the 32 bytes of the current call's input starting at `offset` as a big-endian
+///   word. Bytes past the end of the input read as zero, so any offset beyond it pushes 0.
+pub fn call_data_load(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let offset = evm.stack.try_pop()?;
+    let word = load_word_padded(&evm.context.calldata, offset.saturating_to());
+    evm.stack.push(word)?;
+
+    Ok(())
+}
+
+/// CALLDATASIZE opcode handler
+/// - Semantics: push the byte length of the current call's input.
+pub fn call_data_size(evm: &mut Evm) -> Result<(), EvmErrors> {
+    evm.stack.push(U256::from(evm.context.calldata.len()))?;
+
+    Ok(())
+}
+
+/// CALLDATACOPY opcode handler
+/// - Semantics: pop destOffset, pop offset, pop length, copy `calldata[offset..offset+length]` to
+///   `memory[destOffset..]`, zero-filling past the end of the input.
+/// - Gas: `COPY_WORD_GAS` per word copied on top of the static 3, plus memory expansion.
+pub fn call_data_copy(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let dest = evm.stack.try_pop()?;
+    let offset = evm.stack.try_pop()?;
+    let length = evm.stack.try_pop()?;
+    let (dest, len) = copy_access(evm, dest, length)?;
+    evm.memory
+        .store_padded(dest, &evm.context.calldata, offset.saturating_to(), len);
+
+    Ok(())
+}
+
+/// RETURNDATASIZE opcode handler (EIP-211, since Byzantium)
+/// - Semantics: push the byte length of `return_data`, the output of the last call or creation this frame made
+///   (empty before the first one, and after a successful creation).
+pub fn return_data_size(evm: &mut Evm) -> Result<(), EvmErrors> {
+    evm.stack.push(U256::from(evm.return_data.len()))?;
+
+    Ok(())
+}
+
+/// RETURNDATACOPY opcode handler (EIP-211, since Byzantium)
+/// - Semantics: pop destOffset, pop offset, pop length, copy `return_data[offset..offset+length]` to
+///   `memory[destOffset..]`.
+/// - Unlike the other copies there is no zero-padding: a range reaching past the end of `return_data` fails the
+///   frame with `ReturnDataOutOfBounds`.
+/// - Gas: `COPY_WORD_GAS` per word copied on top of the static 3, plus memory expansion.
+pub fn return_data_copy(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let dest = evm.stack.try_pop()?;
+    let offset = evm.stack.try_pop()?;
+    let length = evm.stack.try_pop()?;
+
+    let size = evm.return_data.len();
+    let (offset, len): (usize, usize) = (offset.saturating_to(), length.saturating_to());
+    if offset.checked_add(len).is_none_or(|end| end > size) {
+        return Err(EvmErrors::ReturnDataOutOfBounds { offset, len, size });
+    }
+    let (dest, len) = copy_access(evm, dest, length)?;
+    if len == 0 {
+        return Ok(());
+    }
+    evm.memory.data[dest..dest + len].copy_from_slice(&evm.return_data[offset..offset + len]);
+
+    Ok(())
+}
+
+/// Charge the per-word copy cost and memory expansion for writing `length` bytes at `dest`, for the *COPY
+/// opcodes. Returns the destination index and the length.
+fn copy_access(evm: &mut Evm, dest: U256, length: U256) -> Result<(usize, usize), EvmErrors> {
+    let len: usize = length.saturating_to();
+    let words = (len as u64).div_ceil(32);
+    if !evm.gas.record_cost(words.saturating_mul(COPY_WORD_GAS)) {
+        return Err(EvmErrors::OutOfGas);
+    }
+    let dest = memory_access(evm, dest, len)?;
+    Ok((dest, len))
+}
+
+/// GASPRICE opcode handler
+/// - Semantics: push the transaction's gas price (`tx.gas_price`).
+pub fn gas_price(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let gas_price = evm.tx.gas_price;
+    evm.stack.push(gas_price)?;
+
+    Ok(())
+}
+
+/// BLOCKHASH opcode handler
+/// - Semantics: pop block number `n`, push the hash of block `n` if it is one of the 256 blocks before the
+///   current one, 0 otherwise (including the current block and any future block).
+/// - Note: the block environment only carries the parent's hash (`block_env.block_hash`), so that is what
+///   `n = number - 1` returns; older blocks inside the window push 0.
+pub fn block_hash(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let block_number = evm.stack.try_pop()?;
     let current_block_number = evm.block_env.number;
 
-    // check if the requested block number
-    // is within the range of the current block number
-    if block_number > current_block_number {
-        evm.stack.push(U256::ZERO).unwrap();
+    let hash = if current_block_number.checked_sub(block_number) == Some(U256::ONE) {
+        evm.block_env.block_hash
     } else {
-        // get the block hash from the block environment
-        let block_hash = evm.block_env.block_hash.as_limbs()[0];
+        U256::ZERO
+    };
+    evm.stack.push(hash)?;
 
-        // evm.stack.push(block_hash).unwrap();
-    }
+    Ok(())
 }
 
 /// COINBASE opcode handler
 /// - Semantics: push the block coinbase/miner address as 32 bytes.
-pub fn coin_base(evm: &mut Evm) {
-    let coin_base = evm.block_env.coinbase;
+pub fn coin_base(evm: &mut Evm) -> Result<(), EvmErrors> {
+    evm.stack.push(address_to_word(evm.block_env.coinbase))?;
 
-    evm.stack
-        .push(U256::from_be_slice(coin_base.as_slice()))
-        .unwrap();
+    Ok(())
 }
 
 /// TIMESTAMP opcode handler
 /// - Semantics: push current block timestamp.
-pub fn timestamp(evm: &mut Evm) {
+pub fn timestamp(evm: &mut Evm) -> Result<(), EvmErrors> {
     let timestamp = evm.block_env.timestamp;
 
-    evm.stack.push(timestamp).unwrap();
+    evm.stack.push(timestamp)?;
+
+    Ok(())
 }
 
 /// NUMBER opcode handler
 /// - Semantics: push current block number.
-pub fn number(evm: &mut Evm) {
+pub fn number(evm: &mut Evm) -> Result<(), EvmErrors> {
     let number = evm.block_env.number;
 
-    evm.stack.push(number).unwrap();
+    evm.stack.push(number)?;
+
+    Ok(())
 }
 
 /// GASLIMIT opcode handler
 /// - Semantics: push current block gas limit.
-pub fn gas_limit(evm: &mut Evm) {
+pub fn gas_limit(evm: &mut Evm) -> Result<(), EvmErrors> {
     let gas_limit = evm.block_env.gas_limit;
 
-    evm.stack.push(gas_limit).unwrap();
+    evm.stack.push(gas_limit)?;
+
+    Ok(())
 }
 
 /// CHAINID opcode handler
 /// - Semantics: push chain id.
-pub fn chain_id(evm: &mut Evm) {
+pub fn chain_id(evm: &mut Evm) -> Result<(), EvmErrors> {
     let chain_id = evm.block_env.chain_id;
 
-    evm.stack.push(chain_id).unwrap();
+    evm.stack.push(chain_id)?;
+
+    Ok(())
+}
+
+/// SELFBALANCE opcode handler
+/// - Semantics: push the balance of the executing account (`context.address`), 0 if it does not exist.
+/// - Gas: a flat 5; unlike BALANCE the account is always warm, so there is no cold surcharge.
+pub fn self_balance(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let balance = evm
+        .storage
+        .data
+        .get(&evm.context.address)
+        .map(|account| account.info.balance)
+        .unwrap_or_default();
+    evm.stack.push(balance)?;
+
+    Ok(())
+}
+
+/// BASEFEE opcode handler
+/// - Semantics: push the current block's base fee.
+pub fn base_fee(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let base_fee = evm.block_env.base_fee;
+
+    evm.stack.push(base_fee)?;
+
+    Ok(())
 }
 
 /// POP opcode handler
 /// - Semantics: remove the top stack element and discard it.
-pub fn pop(evm: &mut Evm) {
-    evm.stack.pop().unwrap();
+pub fn pop(evm: &mut Evm) -> Result<(), EvmErrors> {
+    evm.stack.try_pop()?;
+
+    Ok(())
+}
+
+/// Highest byte offset memory may grow to. Gas makes anything near this unaffordable on a metered run; the
+/// cap keeps an unlimited-gas run from trying to allocate gigabytes off a single huge offset.
+pub(crate) const MEMORY_ADDRESS_LIMIT: usize = u32::MAX as usize;
+
+/// Charge memory expansion for an access of `len` bytes at `offset`, grow memory to cover it and return
+/// `offset` as an index. A zero-length access touches nothing and costs nothing.
+/// - `MemoryOutOfBounds` when the range ends past `MEMORY_ADDRESS_LIMIT` or `Limits::max_memory_size`.
+/// - `OutOfGas` when the expansion can't be paid for.
+pub(crate) fn memory_access(evm: &mut Evm, offset: U256, len: usize) -> Result<usize, EvmErrors> {
+    let start = offset.saturating_to::<usize>();
+    if len == 0 {
+        return Ok(start);
+    }
+    let end = start.saturating_add(len);
+    let limit = evm
+        .limits
+        .max_memory_size
+        .map_or(MEMORY_ADDRESS_LIMIT, |max| max.min(MEMORY_ADDRESS_LIMIT));
+    if end > limit {
+        return Err(EvmErrors::MemoryOutOfBounds { offset: start, len });
+    }
+    if !evm.gas.record_memory_expansion(end as u64) {
+        return Err(EvmErrors::OutOfGas);
+    }
+    evm.memory.resize_to_fit(start, len);
+    Ok(start)
 }
 
 /// MLOAD opcode handler
 /// - Semantics: pop offset, load 32-byte word from memory starting at offset, push that word.
-/// - Note: `load_word` assumes memory has enough bytes; ensure memory is grown appropriately.
-pub fn m_load(evm: &mut Evm) {
-    let offset = evm.stack.pop().unwrap();
+/// - Memory grows (word-aligned) to cover the read.
+pub fn m_load(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let offset = evm.stack.try_pop()?;
+    let offset = memory_access(evm, offset, 32)?;
 
-    let word = evm.memory.load_word(offset.as_limbs()[0] as usize);
+    let word = evm.memory.load_word(offset);
 
-    evm.stack.push(word).unwrap();
+    evm.stack.push(word)?;
+
+    Ok(())
 }
 
 /// MSTORE opcode handler (alternate)
 /// - Semantics: pop offset, pop value, store the 32-byte word at memory[offset].
-/// - Note: similar to `mstore` above; ensure memory length suffices.
-pub fn m_store(evm: &mut Evm) {
-    let offset = evm.stack.pop().unwrap();
-    let value = evm.stack.pop().unwrap();
+/// - Memory grows (word-aligned) to cover the write.
+pub fn m_store(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let offset = evm.stack.try_pop()?;
+    let value = evm.stack.try_pop()?;
+    let offset = memory_access(evm, offset, 32)?;
+
+    evm.memory.store_word(offset, value);
 
-    evm.memory.store_word(offset.as_limbs()[0] as usize, value);
+    Ok(())
 }
 
 /// MSTORE8 opcode handler
 /// - Semantics: pop offset, pop value, store the least-significant byte of value at memory[offset].
-pub fn m_store8(evm: &mut Evm) {
-    let offset = evm.stack.pop().unwrap();
-    let value = evm.stack.pop().unwrap();
+pub fn m_store8(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let offset = evm.stack.try_pop()?;
+    let value = evm.stack.try_pop()?;
+    let offset = memory_access(evm, offset, 1)?;
 
-    evm.memory
-        .store_byte(offset.as_limbs()[0] as usize, value.as_limbs()[0] as u8);
+    evm.memory.store_byte(offset, value.as_limbs()[0] as u8);
+
+    Ok(())
 }
 
-/// SLOAD opcode handler (partial)
-/// - Semantics: pop storage slot key, load value from persistent storage for the executing contract address.
-/// - Note: this implementation reads from `storage` using `evm.tx.to` as the contract address; callers must ensure
-///   that `storage` contains an account entry for that address.
-pub fn s_load(evm: &mut Evm) {
-    let offset = evm.stack.pop().unwrap();
+/// SLOAD opcode handler
+/// - Semantics: pop storage slot key, push its value from the executing contract's (`context.address`) persistent
+///   storage. Slots never written, and accounts that don't exist, read as zero.
+/// - Gas: the cold surcharge (`COLD_SLOAD_GAS` instead of the warm base) on a slot's first access since Berlin.
+pub fn s_load(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let key = evm.stack.try_pop()?;
+    if evm.slot_was_cold(key) && !evm.gas.record_cost(COLD_SLOAD_GAS - WARM_STORAGE_READ_GAS) {
+        return Err(EvmErrors::OutOfGas);
+    }
+    let word = evm.storage.s_load(evm.context.address, key);
+    evm.stack.push(word)?;
 
-    let locator: Address = evm.tx.to;
+    Ok(())
+}
 
-    let word = evm.storage.s_load(locator, offset);
+/// TLOAD opcode handler (EIP-1153, since Cancun)
+/// - Semantics: pop key, push the current contract's transient slot `key`; zero if this transaction hasn't written it.
+pub fn t_load(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let key = evm.stack.try_pop()?;
+    let value = evm.storage.t_load(evm.context.address, key);
+    evm.stack.push(value)?;
 
-    // evm.stack.push(word).unwrap();
+    Ok(())
 }
 
-/// SSTORE opcode handler (partial)
-/// - Semantics: pop offset, pop value, store value into persistent storage at slot `offset` for the current contract address.
-pub fn s_store(evm: &mut Evm) {
-    let offset = evm.stack.pop().unwrap();
-    let value = evm.stack.pop().unwrap();
+/// TSTORE opcode handler (EIP-1153, since Cancun)
+/// - Semantics: pop key, pop value, write the current contract's transient slot `key`. Transient storage is
+///   discarded when the transaction ends and a callee's writes are undone if it fails or reverts.
+/// - Gas: the static 100 only; no refunds, no warm/cold distinction.
+/// - Fails with `StateChangeDuringStaticCall` inside a STATICCALL.
+pub fn t_store(evm: &mut Evm) -> Result<(), EvmErrors> {
+    if evm.context.is_static {
+        return Err(EvmErrors::StateChangeDuringStaticCall);
+    }
+    let key = evm.stack.try_pop()?;
+    let value = evm.stack.try_pop()?;
+    evm.storage.t_store(evm.context.address, key, value);
+
+    Ok(())
+}
+
+/// SSTORE opcode handler
+/// - Semantics: pop key, pop value, store value into persistent storage at slot `key` for the current contract address.
+/// - Gas: `sstore_cost` of the write, given the slot's original and present values, plus `COLD_SLOAD_GAS` on the
+///   slot's first access since Berlin, on top of the static base already charged; `sstore_refund` goes to the
+///   refund counter.
+/// - Fails with `StateChangeDuringStaticCall` inside a STATICCALL, and with `OutOfGas` when no more than the
+///   2300-gas call stipend is left (the EIP-2200 sentry, since Istanbul).
+pub fn s_store(evm: &mut Evm) -> Result<(), EvmErrors> {
+    if evm.context.is_static {
+        return Err(EvmErrors::StateChangeDuringStaticCall);
+    }
+    // The sentry looks at the gas left before the static base was charged.
+    let base = static_gas(Opcode::SSTORE, evm.spec);
+    if evm.spec >= SpecId::Istanbul && evm.gas.remaining().saturating_add(base) <= CALL_STIPEND {
+        return Err(EvmErrors::OutOfGas);
+    }
+    let key = evm.stack.try_pop()?;
+    let value = evm.stack.try_pop()?;
+
+    let address: Address = evm.context.address;
+    let original = evm.storage.original_value(address, key);
+    let present = evm.storage.s_load(address, key);
+    let mut cost = sstore_cost(evm.spec, original, present, value);
+    if evm.slot_was_cold(key) {
+        cost += COLD_SLOAD_GAS;
+    }
+    if !evm.gas.record_cost(cost.saturating_sub(base)) {
+        return Err(EvmErrors::OutOfGas);
+    }
+    evm.gas
+        .record_refund(sstore_refund(evm.spec, original, present, value));
+
+    evm.storage.s_store(address, key, value);
 
-    let locator: Address = evm.tx.to;
+    Ok(())
+}
 
-    evm.storage.s_store(locator, offset, value);
+/// Move `pc` to `target` if it is a JUMPDEST of the executing code.
+fn jump_to(evm: &mut Evm, target: U256) -> Result<(), EvmErrors> {
+    match usize::try_from(target) {
+        Ok(pc) if evm.code.is_jump_dest(pc) => {
+            evm.pc = pc;
+            Ok(())
+        }
+        _ => Err(EvmErrors::InvalidJump { target }),
+    }
 }
 
 /// JUMP opcode handler
 /// - Semantics: pop target and set `pc` to that value (absolute jump).
-/// - Note: real EVM requires target to be a valid `JUMPDEST`; validation is not performed here.
-pub fn jump(evm: &mut Evm) {
-    let target = evm.stack.pop().unwrap();
+/// - Fails with `InvalidJump` unless the target is a JUMPDEST instruction (not a `0x5B` byte in PUSH data).
+pub fn jump(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let target = evm.stack.try_pop()?;
 
-    evm.pc = target.as_limbs()[0] as usize;
+    jump_to(evm, target)
 }
 
 /// JUMPI opcode handler
 /// - Semantics: pop target, pop condition. If condition != 0, set `pc = target` (conditional jump).
-pub fn jumpi(evm: &mut Evm) {
-    let target = evm.stack.pop().unwrap();
-    let condition = evm.stack.pop().unwrap();
+/// - Fails with `InvalidJump` if the jump is taken and the target isn't a JUMPDEST; an untaken jump never fails.
+pub fn jumpi(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let target = evm.stack.try_pop()?;
+    let condition = evm.stack.try_pop()?;
 
-    if condition.as_limbs()[0] != 0 {
-        evm.pc = target.as_limbs()[0] as usize;
+    if !condition.is_zero() {
+        jump_to(evm, target)?;
     }
+
+    Ok(())
 }
 
-/// JUMPDEST handler (no-op in many implementations)
-/// - Semantics: marks a valid destination for `JUMP`/`JUMPI`. Here it does nothing.
-pub fn jump_dest(evm: &mut Evm) {
-    let pc = evm.pc;
+/// JUMPDEST handler
+/// - Semantics: marks a valid destination for `JUMP`/`JUMPI` (see `Bytecode::is_jump_dest`); executing it does
+///   nothing.
+pub fn jump_dest(_evm: &mut Evm) -> Result<(), EvmErrors> {
+    Ok(())
 }
 
 /// PC opcode handler
-/// - Semantics: push current program counter. This implementation currently reads `evm.pc` but doesn't push it.
-pub fn pc(evm: &mut Evm) {
-    evm.pc;
+/// - Semantics: push the position of this PC instruction. `step()` has already moved `pc` past it, hence `- 1`.
+pub fn pc(evm: &mut Evm) -> Result<(), EvmErrors> {
+    evm.stack.push(U256::from(evm.pc - 1))?;
+
+    Ok(())
 }
 
 /// MSIZE opcode handler
-/// - Semantics: push memory size in bytes. This implementation reads `memory.data.len()` but doesn't push it.
-pub fn m_size(evm: &mut Evm) {
-    evm.memory.data.len();
+/// - Semantics: push memory size in bytes.
+pub fn m_size(evm: &mut Evm) -> Result<(), EvmErrors> {
+    evm.stack.push(U256::from(evm.memory.msize()))?;
+
+    Ok(())
+}
+
+/// GAS opcode handler
+/// - Semantics: push the gas remaining in the current frame.
+pub fn gas(evm: &mut Evm) -> Result<(), EvmErrors> {
+    evm.stack.push(U256::from(evm.gas.remaining()))?;
+
+    Ok(())
+}
+
+/// MCOPY opcode handler
+/// - Semantics: pop dest, pop offset, pop length, copy `length` bytes of memory from `offset` to `dest`.
+/// - Gas: 3 per copied word plus expansion to cover both the source and destination ranges.
+pub fn m_copy(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let dest = evm.stack.try_pop()?;
+    let offset = evm.stack.try_pop()?;
+    let length = evm.stack.try_pop()?;
+
+    let len: usize = length.saturating_to();
+    let copy_cost = (len as u64).div_ceil(32).saturating_mul(3);
+    if !evm.gas.record_cost(copy_cost) {
+        return Err(EvmErrors::OutOfGas);
+    }
+    if len == 0 {
+        return Ok(());
+    }
+    let offset = memory_access(evm, offset, len)?;
+    let dest = memory_access(evm, dest, len)?;
+
+    evm.memory.copy(offset, dest, len);
+
+    Ok(())
+}
+
+/// CALL opcode handler
+/// - Semantics: pop gas, address, value, argsOffset, argsSize, retOffset, retSize; run the target's code with
+///   `value` transferred and `memory[argsOffset..argsOffset+argsSize]` as calldata. Pushes 1 on success, 0 otherwise
+///   (see `Evm::begin_call` / `Evm::end_call`).
+pub fn call(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let inputs = CallInputs::pop(&mut evm.stack, CallScheme::Call)?;
+    evm.begin_call(inputs)
+}
+
+/// CALLCODE opcode handler
+/// - Semantics: like CALL, but the target's code runs against the current account's storage and balance.
+pub fn call_code(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let inputs = CallInputs::pop(&mut evm.stack, CallScheme::CallCode)?;
+    evm.begin_call(inputs)
+}
+
+/// DELEGATECALL opcode handler
+/// - Semantics: pop gas, address, argsOffset, argsSize, retOffset, retSize; run the target's code in the current
+///   context, keeping the current caller and value.
+pub fn delegate_call(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let inputs = CallInputs::pop(&mut evm.stack, CallScheme::DelegateCall)?;
+    evm.begin_call(inputs)
+}
+
+/// STATICCALL opcode handler
+/// - Semantics: like CALL without a value operand; the callee (and anything it calls) can't change state.
+pub fn static_call(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let inputs = CallInputs::pop(&mut evm.stack, CallScheme::StaticCall)?;
+    evm.begin_call(inputs)
+}
+
+/// CREATE opcode handler
+/// - Semantics: pop value, offset, size; run `memory[offset..offset + size]` as init code for a new account at
+///   `keccak256(rlp([address, nonce]))[12..]`, store what it returns as that account's code, and push the new
+///   address (0 on failure).
+pub fn create(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let inputs = CreateInputs::pop(&mut evm.stack, false)?;
+    evm.begin_create(inputs)
 }
 
-/// GAS opcode handler (partial)
-/// - Semantics: push remaining gas. This implementation returns block_env.gas_limit which is not correct gas accounting.
-pub fn gas(evm: &mut Evm) {
-    evm.block_env.gas_limit;
+/// CREATE2 opcode handler
+/// - Semantics: like CREATE with a fourth operand, salt; the new address is
+///   `keccak256(0xff ++ address ++ salt ++ keccak256(init_code))[12..]` (EIP-1014).
+pub fn create2(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let inputs = CreateInputs::pop(&mut evm.stack, true)?;
+    evm.begin_create(inputs)
 }
 
-/// MCOPY opcode handler (partial)
-/// - Semantics: copy memory region; this implementation reads stack operands but the actual copy is commented out.
-pub fn m_copy(evm: &mut Evm) {
-    let offset = evm.stack.pop().unwrap();
-    let length = evm.stack.pop().unwrap();
-    let dest = evm.stack.pop().unwrap();
+/// Copy `length` bytes of memory at `offset` into `return_data` and end the run with them as output.
+fn halt_with_output(evm: &mut Evm, reverted: bool) -> Result<(), EvmErrors> {
+    let offset = evm.stack.try_pop()?;
+    let length = evm.stack.try_pop()?;
 
-    // evm.memory.copy(offset.as_limbs()[0] as usize, dest.as_limbs()[0] as usize, length.as_limbs()[0] as usize);
+    let len: usize = length.saturating_to();
+    evm.limits.check_return_data(len)?;
+    let offset = memory_access(evm, offset, len)?;
+    evm.return_data = if len == 0 {
+        Vec::new()
+    } else {
+        evm.memory.data[offset..offset + len].to_vec()
+    };
+    let output = evm.return_data.clone().into();
+    evm.status = if reverted {
+        ProgramExitStatus::Revert { output }
+    } else {
+        ProgramExitStatus::Success {
+            output,
+            gas_used: evm.gas.spent(),
+        }
+    };
+
+    Ok(())
+}
+
+/// INVALID opcode handler
+/// - Semantics: the designated invalid instruction (EIP-141): always halts with `InvalidOpcode`, consuming all
+///   gas left in the frame like any exceptional halt.
+pub fn invalid(_evm: &mut Evm) -> Result<(), EvmErrors> {
+    Err(EvmErrors::InvalidOpcode)
+}
+
+/// RETURN opcode handler
+/// - Semantics: pop offset, pop length, halt successfully with `memory[offset..offset+length]` as output.
+/// - Memory grows to cover the range (and is charged for it) like any other access.
+pub fn ret(evm: &mut Evm) -> Result<(), EvmErrors> {
+    halt_with_output(evm, false)
+}
+
+/// REVERT opcode handler
+/// - Semantics: pop offset, pop length, halt with `memory[offset..offset+length]` as output and flag the run as
+///   reverted; `transact_commit` discards its state changes.
+/// - Unlike a failure, the gas left is not consumed.
+pub fn revert(evm: &mut Evm) -> Result<(), EvmErrors> {
+    halt_with_output(evm, true)
+}
+
+/// PUSH1..PUSH32 opcode handler, `N` being the number of immediate bytes
+/// - Semantics: push the `N` bytes that follow the opcode as a big-endian word, then skip over them.
+/// - `pc` already points at the first immediate when the handler runs.
+/// - Immediates cut off by the end of the code read as zero bytes (on the right, so `0x61 0x01` at the very
+///   end pushes 0x0100).
+/// - Example: `0x60 0x2a` pushes 0x2a, `0x61 0x01 0x02` pushes 0x0102.
+pub fn push<const N: usize>(evm: &mut Evm) -> Result<(), EvmErrors> {
+    let code = evm.code.as_slice();
+    let start = evm.pc.min(code.len());
+    let end = evm.pc.saturating_add(N).min(code.len());
+
+    let mut word = [0u8; 32];
+    word[32 - N..32 - N + (end - start)].copy_from_slice(&code[start..end]);
+    evm.pc += N;
+
+    evm.stack.push(U256::from_be_bytes(word))?;
+
+    Ok(())
+}
+
+/// DUP1..DUP16 opcode handler
+/// - Semantics: push a copy of the `N`-th stack item (DUP1 duplicates the top).
+pub fn dup<const N: usize>(evm: &mut Evm) -> Result<(), EvmErrors> {
+    evm.stack.dup(N)?;
+
+    Ok(())
+}
+
+/// SWAP1..SWAP16 opcode handler
+/// - Semantics: exchange the top stack item with the one `N` below it (SWAP1 swaps the top two).
+pub fn swap<const N: usize>(evm: &mut Evm) -> Result<(), EvmErrors> {
+    evm.stack.swap(N)?;
+
+    Ok(())
+}
+
+/// LOG0..LOG4 opcode handler, `N` being the number of topics
+/// - Semantics: pop offset, pop length, pop `N` topics, append `Log { address, topics, data }` to `evm.logs`, with
+///   `address` the executing contract and `data = memory[offset..offset+length]`.
+/// - Gas: `LOG_DATA_GAS` per byte plus memory expansion. The 375 base and `LOG_TOPIC_GAS` per topic are the
+///   opcode's static cost, already charged by `step()`.
+/// - Not allowed in a static context. A callee that fails or reverts takes its logs with it (see `call_frame`).
+pub fn log<const N: usize>(evm: &mut Evm) -> Result<(), EvmErrors> {
+    if evm.context.is_static {
+        return Err(EvmErrors::StateChangeDuringStaticCall);
+    }
+    let offset = evm.stack.try_pop()?;
+    let length = evm.stack.try_pop()?;
+    let mut topics = Vec::with_capacity(N);
+    for _ in 0..N {
+        topics.push(B256::from(evm.stack.try_pop()?));
+    }
+
+    let len: usize = length.saturating_to();
+    if !evm
+        .gas
+        .record_cost((len as u64).saturating_mul(LOG_DATA_GAS))
+    {
+        return Err(EvmErrors::OutOfGas);
+    }
+    let offset = memory_access(evm, offset, len)?;
+    let data = if len == 0 {
+        Bytes::new()
+    } else {
+        Bytes::copy_from_slice(&evm.memory.data[offset..offset + len])
+    };
+    evm.logs
+        .push(Log::new_unchecked(evm.context.address, topics, data));
+
+    Ok(())
 }
 
 /// PUSH0 opcode handler (special PUSH of zero)
 /// - Semantics: push zero onto the stack.
-pub fn push_0(evm: &mut Evm) {
-    evm.stack.push(U256::ZERO).unwrap();
+pub fn push_0(evm: &mut Evm) -> Result<(), EvmErrors> {
+    evm.stack.push(U256::ZERO)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::KECCAK256_EMPTY;
+    use primitives::{bytecode::Bytecode, memory::Memory};
+
+    use super::*;
+    use crate::{
+        expect_stack, expect_storage,
+        gas::{EXP_BYTE_GAS, LOG_TOPIC_GAS},
+        jump_tables::OpcodeFn,
+    };
+
+    fn word(hex: &str) -> U256 {
+        U256::from_str_radix(hex, 16).unwrap()
+    }
+
+    /// Run `code` as a top-level frame with a 100k gas limit.
+    fn run_code(code: Vec<u8>, setup: impl FnOnce(&mut Evm)) -> Evm {
+        let mut evm = Evm {
+            code: Bytecode::new(code),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(100_000));
+        setup(&mut evm);
+        evm.run();
+        assert!(evm.status.is_success(), "{:?}", evm.status);
+        evm
+    }
+
+    /// Run a shift handler with `value` below `shift` on the stack and return the pushed result.
+    fn shift(handler: OpcodeFn, value: &str, shift: &str) -> U256 {
+        let mut evm = Evm::default();
+        evm.stack.push(word(value)).unwrap();
+        evm.stack.push(word(shift)).unwrap();
+        handler(&mut evm).unwrap();
+
+        assert_eq!(evm.stack.len(), 1);
+        evm.stack.pop().unwrap()
+    }
+
+    #[test]
+    fn jumps_only_land_on_jump_dests() {
+        // PUSH1 0x5B; JUMPDEST
+        let mut evm = Evm {
+            code: Bytecode::new(vec![0x60, 0x5B, 0x5B]),
+            ..Default::default()
+        };
+        evm.stack.push(U256::from(2)).unwrap();
+        jump(&mut evm).unwrap();
+        assert_eq!(evm.pc, 2);
+
+        for target in [U256::from(1), U256::from(3), U256::MAX] {
+            evm.stack.push(target).unwrap();
+            assert_eq!(jump(&mut evm), Err(EvmErrors::InvalidJump { target }));
+        }
+
+        // An untaken JUMPI ignores its target; a condition with only high bits set is still true.
+        evm.stack.push(U256::ZERO).unwrap();
+        evm.stack.push(U256::from(1)).unwrap();
+        jumpi(&mut evm).unwrap();
+        evm.stack.push(U256::ONE << 200).unwrap();
+        evm.stack.push(U256::from(1)).unwrap();
+        assert!(jumpi(&mut evm).is_err());
+    }
+
+    #[test]
+    fn top_level_frame_context() {
+        let mut evm = Evm::default();
+        evm.tx.from = Address::repeat_byte(0xaa);
+        evm.tx.to = Address::repeat_byte(0xbb);
+        evm.tx.value = U256::from(7);
+        evm.execute();
+
+        address(&mut evm).unwrap();
+        caller(&mut evm).unwrap();
+        call_value(&mut evm).unwrap();
+
+        assert_eq!(evm.stack.pop().unwrap(), U256::from(7));
+        assert_eq!(evm.stack.pop().unwrap(), address_to_word(evm.tx.from));
+        assert_eq!(evm.stack.pop().unwrap(), address_to_word(evm.tx.to));
+    }
+
+    #[test]
+    fn sstore_writes_current_contract_storage() {
+        let mut evm = Evm::default();
+        evm.context.address = Address::repeat_byte(0xc0);
+        evm.stack.push(U256::from(42)).unwrap();
+        evm.stack.push(U256::from(1)).unwrap();
+        s_store(&mut evm).unwrap();
+
+        expect_stack!(evm, []);
+        expect_storage!(evm, Address::repeat_byte(0xc0), 1 => 42, 0 => 0);
+    }
+
+    #[test]
+    fn sstore_charges_from_original_value_and_honours_sentry() {
+        let contract = Address::repeat_byte(0xc0);
+        let mut evm = Evm::default().with_gas_limit(Some(100_000));
+        evm.context.address = contract;
+        evm.storage.s_store(contract, U256::from(1), U256::from(5));
+        evm.storage.clear_original_values();
+
+        // 5 -> 0 clears the cold slot (reset price plus the cold surcharge), 0 -> 5 restores it (warm price,
+        // covered by the static base). The clear refund is taken back again; what remains is the reset/warm
+        // difference.
+        for value in [0, 5] {
+            evm.stack.push(U256::from(value)).unwrap();
+            evm.stack.push(U256::from(1)).unwrap();
+            s_store(&mut evm).unwrap();
+        }
+        assert_eq!(evm.gas.spent(), 2_900 + COLD_SLOAD_GAS - 100);
+        assert_eq!(evm.gas.refunded(), 2_900 - 100);
+
+        let mut evm = Evm::default().with_gas_limit(Some(2_300 - 100));
+        evm.stack.push(U256::from(1)).unwrap();
+        evm.stack.push(U256::from(1)).unwrap();
+        assert_eq!(s_store(&mut evm), Err(EvmErrors::OutOfGas));
+    }
+
+    #[test]
+    fn sload_pushes_stored_value_and_zero_when_cold() {
+        let contract = Address::repeat_byte(0xc0);
+        let mut evm = Evm::default();
+        evm.context.address = contract;
+        evm.storage.s_store(contract, U256::from(1), U256::from(42));
+
+        for key in [1, 2] {
+            evm.stack.push(U256::from(key)).unwrap();
+            s_load(&mut evm).unwrap();
+        }
+        // An account with no storage at all reads as zero too, and the read marks it touched.
+        evm.context.address = Address::repeat_byte(0xdd);
+        evm.stack.push(U256::from(1)).unwrap();
+        s_load(&mut evm).unwrap();
+
+        expect_stack!(evm, [42, 0, 0]);
+        assert!(evm.storage.touched.contains(&Address::repeat_byte(0xdd)));
+        assert!(!evm.storage.data.contains_key(&Address::repeat_byte(0xdd)));
+    }
+
+    #[test]
+    fn log2_records_topics_and_memory_data() {
+        // MSTORE(0, 0xabcd); LOG2(offset 30, len 2, topics 1, 2); STOP
+        let mut evm = Evm {
+            code: Bytecode::new(vec![
+                0x61, 0xab, 0xcd, 0x60, 0x00, 0x52, 0x60, 0x02, 0x60, 0x01, 0x60, 0x02, 0x60, 0x1e,
+                0xA2, 0x00,
+            ]),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(100_000));
+        evm.context.address = Address::repeat_byte(0xc0);
+        evm.run();
+
+        assert!(evm.status.is_success());
+        expect_stack!(evm, []);
+        let log = &evm.logs[0];
+        assert_eq!(log.address, Address::repeat_byte(0xc0));
+        assert_eq!(
+            log.topics(),
+            &[B256::from(U256::ONE), B256::from(U256::from(2))]
+        );
+        assert_eq!(log.data.data.as_ref(), &[0xab, 0xcd]);
+        // PUSH2, PUSH1, MSTORE with its memory word, four PUSH1s; then LOG2's static 375 + 2 * 375 and 8 per byte.
+        assert_eq!(
+            evm.gas.spent(),
+            3 + 3 + 6 + 4 * 3 + 375 + 2 * LOG_TOPIC_GAS + 2 * LOG_DATA_GAS
+        );
+    }
+
+    #[test]
+    fn log_topics_are_charged_once() {
+        // PUSH1 1; PUSH0; PUSH0; LOG1; STOP
+        let mut evm = Evm {
+            code: Bytecode::new(vec![0x60, 0x01, 0x5F, 0x5F, 0xA1, 0x00]),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(100_000));
+        evm.run();
+
+        assert!(evm.status.is_success());
+        assert_eq!(evm.gas.spent(), 3 + 2 + 2 + 750);
+    }
+
+    #[test]
+    fn calldata_reads_zero_pad_past_the_end() {
+        let mut evm = Evm::default().with_gas_limit(Some(100_000));
+        evm.context.calldata = Bytes::from(vec![0xaa, 0xbb, 0xcc]);
+
+        call_data_size(&mut evm).unwrap();
+        evm.stack.push(U256::ONE).unwrap();
+        call_data_load(&mut evm).unwrap();
+        evm.stack.push(U256::MAX).unwrap();
+        call_data_load(&mut evm).unwrap();
+        expect_stack!(evm, [3, word("bbcc") << 240, 0]);
+
+        // CALLDATACOPY(dest 0, offset 2, length 4)
+        for item in [4, 2, 0] {
+            evm.stack.push(U256::from(item)).unwrap();
+        }
+        let before = evm.gas.spent();
+        call_data_copy(&mut evm).unwrap();
+        assert_eq!(evm.memory.data[..4], [0xcc, 0, 0, 0]);
+        assert_eq!(evm.gas.spent() - before, COPY_WORD_GAS + 3);
+    }
+
+    #[test]
+    fn empty_returndatacopy_touches_nothing() {
+        // RETURNDATACOPY(dest 0x20, offset 0, length 0) on empty memory and return data.
+        let mut evm = Evm {
+            code: Bytecode::new(vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x20, 0x3E, 0x00]),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(1_000));
+        evm.run();
+
+        assert!(evm.status.is_success());
+        assert!(evm.memory.data.is_empty());
+        assert_eq!(evm.gas.spent(), 3 * 4);
+
+        // A zero-length range starting past the end is still out of bounds (EIP-211).
+        let mut evm = Evm::default();
+        for item in [0, 1, 0] {
+            evm.stack.push(U256::from(item)).unwrap();
+        }
+        assert_eq!(
+            return_data_copy(&mut evm),
+            Err(EvmErrors::ReturnDataOutOfBounds {
+                offset: 1,
+                len: 0,
+                size: 0
+            })
+        );
+    }
+
+    #[test]
+    fn code_introspection_of_self_and_other_accounts() {
+        let contract = Address::repeat_byte(0xc0);
+        let funded = Address::repeat_byte(0xf0);
+        let mut evm = Evm {
+            code: Bytecode::new(vec![0x60, 0x01, 0x00]),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(100_000));
+        evm.storage.set_code(contract, vec![0xde, 0xad, 0xbe, 0xef]);
+        evm.storage.data.entry(funded).or_default().info.balance = U256::ONE;
+
+        code_size(&mut evm).unwrap();
+        for address in [contract, contract, funded, Address::repeat_byte(0xee)] {
+            evm.stack.push(address_to_word(address)).unwrap();
+        }
+        ext_code_hash(&mut evm).unwrap();
+        let missing = evm.stack.pop().unwrap();
+        ext_code_hash(&mut evm).unwrap();
+        let no_code = evm.stack.pop().unwrap();
+        ext_code_hash(&mut evm).unwrap();
+        let with_code = evm.stack.pop().unwrap();
+        ext_code_size(&mut evm).unwrap();
+        expect_stack!(evm, [3, 4]);
+        assert_eq!(missing, U256::ZERO);
+        assert_eq!(no_code, U256::from_be_bytes(KECCAK256_EMPTY.0));
+        assert_eq!(
+            with_code,
+            U256::from_be_bytes(keccak256([0xde, 0xad, 0xbe, 0xef]).0)
+        );
+
+        // CODECOPY(dest 0, offset 1, length 3); EXTCODECOPY(contract, dest 3, offset 2, length 3)
+        for item in [3, 1, 0] {
+            evm.stack.push(U256::from(item)).unwrap();
+        }
+        code_copy(&mut evm).unwrap();
+        for item in [
+            U256::from(3),
+            U256::from(2),
+            U256::from(3),
+            address_to_word(contract),
+        ] {
+            evm.stack.push(item).unwrap();
+        }
+        ext_code_copy(&mut evm).unwrap();
+        assert_eq!(evm.memory.data[..6], [0x01, 0x00, 0x00, 0xbe, 0xef, 0x00]);
+    }
+
+    #[test]
+    fn push_reads_immediates_and_pads_truncated_ones() {
+        let mut code = vec![0x7f];
+        code.extend(1..=32u8);
+        code.extend([0x61, 0x01, 0x02, 0x61, 0x01]);
+        let mut evm = Evm {
+            code: primitives::bytecode::Bytecode::new(code),
+            ..Default::default()
+        };
+        evm.run();
+
+        let push32 = U256::from_be_bytes(core::array::from_fn::<u8, 32, _>(|i| i as u8 + 1));
+        assert_eq!(
+            evm.stack.as_slice(),
+            &[push32, U256::from(0x0102), U256::from(0x0100)]
+        );
+        assert_eq!(evm.pc, 33 + 3 + 3);
+    }
+
+    #[test]
+    fn dup16_and_swap16_reach_the_deepest_item() {
+        let mut evm = Evm::default();
+        for i in 1..=17u64 {
+            evm.stack.push(U256::from(i)).unwrap();
+        }
+
+        swap::<16>(&mut evm).unwrap();
+        assert_eq!(evm.stack.as_slice()[0], U256::from(17));
+        assert_eq!(evm.stack.as_slice()[16], U256::from(1));
+
+        dup::<16>(&mut evm).unwrap();
+        assert_eq!(evm.stack.len(), 18);
+        assert_eq!(evm.stack.pop(), Some(U256::from(2)));
+    }
+
+    #[test]
+    fn exp_charges_per_exponent_byte() {
+        let mut evm = Evm::default().with_gas_limit(Some(1_000));
+        evm.stack.push(U256::from(0x0100)).unwrap();
+        evm.stack.push(U256::from(2)).unwrap();
+        exp(&mut evm).unwrap();
+
+        expect_stack!(evm, [0]);
+        assert_eq!(evm.gas.spent(), 2 * EXP_BYTE_GAS);
+
+        let mut evm = Evm::default().with_gas_limit(Some(EXP_BYTE_GAS * 31));
+        evm.stack.push(U256::MAX).unwrap();
+        evm.stack.push(U256::from(3)).unwrap();
+        assert_eq!(exp(&mut evm), Err(EvmErrors::OutOfGas));
+        assert!(evm.stack.is_empty());
+    }
+
+    #[test]
+    fn keccak256_hashes_memory_slice() {
+        let mut evm = Evm::default();
+        evm.stack.push(U256::ZERO).unwrap();
+        evm.stack.push(U256::from(0x100)).unwrap();
+        keccak_256(&mut evm).unwrap();
+        // Empty input: no memory touched.
+        expect_stack!(
+            evm,
+            [U256::from_be_bytes(alloy::primitives::KECCAK256_EMPTY.0)]
+        );
+        assert_eq!(evm.memory.msize(), 0);
+
+        let mut evm = Evm::default();
+        evm.memory.store_word(0, U256::from(1));
+        evm.stack.push(U256::from(32)).unwrap();
+        evm.stack.push(U256::ZERO).unwrap();
+        keccak_256(&mut evm).unwrap();
+        expect_stack!(
+            evm,
+            [U256::from_be_bytes(
+                keccak256(U256::from(1).to_be_bytes::<32>()).0
+            )]
+        );
+        assert_eq!(evm.gas.spent(), KECCAK256_WORD_GAS + 3);
+    }
+
+    #[test]
+    fn or_xor_not() {
+        let a = U256::from(0b1100);
+        let b = U256::from(0b1010);
+        for (handler, expected) in [(or as OpcodeFn, 0b1110), (xor, 0b0110), (and, 0b1000)] {
+            let mut evm = Evm::default();
+            evm.stack.push(b).unwrap();
+            evm.stack.push(a).unwrap();
+            handler(&mut evm).unwrap();
+            expect_stack!(evm, [expected]);
+        }
+
+        let mut evm = Evm::default();
+        evm.stack.push(U256::ZERO).unwrap();
+        not(&mut evm).unwrap();
+        expect_stack!(evm, [U256::MAX]);
+    }
+
+    #[test]
+    fn modular_ops_and_byte_do_not_panic() {
+        let mut evm = Evm::default();
+        for (a, b, c) in [
+            (U256::MAX, U256::from(2), U256::ZERO),
+            (U256::MAX, U256::from(2), U256::from(10)),
+        ] {
+            evm.stack.push(c).unwrap();
+            evm.stack.push(b).unwrap();
+            evm.stack.push(a).unwrap();
+            addmod(&mut evm).unwrap();
+        }
+        // (2^256 - 1 + 2) % 10 = 7, not the wrapped 1 % 10.
+        expect_stack!(evm, [0, 7]);
+
+        evm.stack.push(word("0102")).unwrap();
+        evm.stack.push(U256::from(30)).unwrap();
+        byte(&mut evm).unwrap();
+        evm.stack.push(word("0102")).unwrap();
+        evm.stack.push(U256::from(32)).unwrap();
+        byte(&mut evm).unwrap();
+        expect_stack!(evm, [0, 7, 1, 0]);
+    }
+
+    #[test]
+    fn comparisons_run_from_bytecode() {
+        let evm = run_code(
+            vec![
+                0x60, 0x03, 0x60, 0x02, 0x10, // LT(2, 3)
+                0x60, 0x03, 0x60, 0x02, 0x11, // GT(2, 3)
+                0x60, 0x01, 0x5F, 0x19, 0x12, // SLT(-1, 1)
+                0x60, 0x01, 0x5F, 0x19, 0x13, // SGT(-1, 1)
+                0x60, 0x07, 0x80, 0x14, // EQ(7, 7)
+                0x5F, 0x15, // ISZERO(0)
+                0x00,
+            ],
+            |_| {},
+        );
+        expect_stack!(evm, [1, 0, 1, 0, 1, 1]);
+    }
+
+    #[test]
+    fn addmod_mulmod_and_signextend_run_from_bytecode() {
+        let evm = run_code(
+            vec![
+                0x60, 0x0a, 0x60, 0x02, 0x5F, 0x19, 0x08, // ADDMOD(MAX, 2, 10)
+                0x60, 0x0a, 0x5F, 0x19, 0x5F, 0x19, 0x09, // MULMOD(MAX, MAX, 10)
+                0x60, 0xff, 0x5F, 0x0B, // SIGNEXTEND(0, 0xff)
+                0x60, 0x7f, 0x5F, 0x0B, // SIGNEXTEND(0, 0x7f)
+                0x61, 0x01, 0x80, 0x5F,
+                0x0B, // SIGNEXTEND(0, 0x0180): bits above byte 0 are dropped
+                0x61, 0x80, 0x00, 0x60, 0x01, 0x0B, // SIGNEXTEND(1, 0x8000)
+                0x60, 0x80, 0x60, 0x1f, 0x0B, // SIGNEXTEND(31, 0x80): already a full word
+                0x00,
+            ],
+            |_| {},
+        );
+        // 2^256 - 1 is 5 mod 10: (MAX + 2) % 10 = 7 without wrapping, MAX * MAX % 10 = 25 % 10 = 5.
+        expect_stack!(
+            evm,
+            [
+                U256::from(7),
+                U256::from(5),
+                U256::MAX,
+                U256::from(0x7f),
+                U256::MAX - U256::from(0x7f),
+                U256::MAX - U256::from(0x7fff),
+                U256::from(0x80)
+            ]
+        );
+    }
+
+    #[test]
+    fn signextend_from_byte_30_sets_only_the_top_byte() {
+        let mut evm = Evm::default();
+        evm.stack.push(U256::ONE << 247).unwrap();
+        evm.stack.push(U256::from(30)).unwrap();
+        signextend(&mut evm).unwrap();
+        expect_stack!(evm, [(U256::MAX << 248) | (U256::ONE << 247)]);
+    }
+
+    #[test]
+    fn block_and_transaction_environment_opcodes() {
+        // COINBASE; TIMESTAMP; NUMBER; GASLIMIT; CHAINID; BASEFEE; GASPRICE; STOP
+        let evm = run_code(
+            vec![0x41, 0x42, 0x43, 0x45, 0x46, 0x48, 0x3A, 0x00],
+            |evm| {
+                evm.block_env.coinbase = Address::repeat_byte(0xcb);
+                evm.block_env.timestamp = U256::from(1_700_000_000);
+                evm.block_env.number = U256::from(100);
+                evm.block_env.gas_limit = U256::from(30_000_000);
+                evm.block_env.chain_id = U256::ONE;
+                evm.block_env.base_fee = U256::from(7);
+                evm.tx.gas_limit = U256::from(21_000);
+                evm.tx.gas_price = U256::from(3);
+            },
+        );
+        expect_stack!(
+            evm,
+            [
+                address_to_word(Address::repeat_byte(0xcb)),
+                U256::from(1_700_000_000),
+                U256::from(100),
+                U256::from(30_000_000),
+                U256::ONE,
+                U256::from(7),
+                U256::from(3)
+            ]
+        );
+        assert_eq!(evm.gas.spent(), 7 * 2);
+    }
+
+    #[test]
+    fn blockhash_returns_the_parent_hash_and_zero_elsewhere() {
+        let evm = run_code(
+            vec![
+                0x61, 0x01, 0x2b, 0x40, // BLOCKHASH(299): the parent
+                0x61, 0x01, 0x2c, 0x40, // BLOCKHASH(300): the current block
+                0x61, 0x01, 0x2d, 0x40, // BLOCKHASH(301): a future block
+                0x60, 0x2c, 0x40, // BLOCKHASH(44): 256 blocks back, no hash on record
+                0x60, 0x2b, 0x40, // BLOCKHASH(43): outside the window
+                0x00,
+            ],
+            |evm| {
+                evm.block_env.number = U256::from(300);
+                evm.block_env.block_hash = word("abcd");
+            },
+        );
+        expect_stack!(
+            evm,
+            [word("abcd"), U256::ZERO, U256::ZERO, U256::ZERO, U256::ZERO]
+        );
+        assert_eq!(evm.gas.spent(), 3 * 3 + 2 * 3 + 5 * 20);
+    }
+
+    #[test]
+    fn selfbalance_reads_the_executing_account_without_a_cold_surcharge() {
+        // SELFBALANCE; STOP
+        let evm = run_code(vec![0x47, 0x00], |evm| {
+            evm.context.address = Address::repeat_byte(0xc0);
+            evm.storage
+                .set_balance(Address::repeat_byte(0xc0), U256::from(1234));
+        });
+        expect_stack!(evm, [1234]);
+        assert_eq!(evm.gas.spent(), 5);
+
+        // An account that doesn't exist has no balance.
+        let evm = run_code(vec![0x47, 0x00], |evm| {
+            evm.context.address = Address::repeat_byte(0xdd);
+        });
+        expect_stack!(evm, [0]);
+    }
+
+    #[test]
+    fn pc_msize_and_gas_report_the_frame() {
+        // PUSH1 0x2a; PUSH1 0; MSTORE8; PC; MSIZE
+        let mut evm = Evm {
+            code: Bytecode::new(vec![0x60, 0x2a, 0x60, 0x00, 0x53, 0x58, 0x59, 0x00]),
+            ..Default::default()
+        };
+        evm.run();
+        assert!(evm.status.is_success());
+        // PC pushes its own position, MSIZE the word-aligned size MSTORE8 grew memory to.
+        expect_stack!(evm, [5, 32]);
+
+        // PUSH1 1; GAS; STOP: GAS reports what is left after its own static cost.
+        let mut evm = Evm {
+            code: Bytecode::new(vec![0x60, 0x01, 0x5A, 0x00]),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(1_000));
+        evm.run();
+        assert!(evm.status.is_success());
+        expect_stack!(evm, [1, 1_000 - 3 - 2]);
+    }
+
+    #[test]
+    fn mcopy_copies_overlapping_ranges() {
+        let mut data = vec![1, 2, 3, 4];
+        data.resize(32, 0);
+        let mut evm = Evm {
+            // PUSH1 4; PUSH0; PUSH1 2; MCOPY: length 4, offset 0, dest 2.
+            // PUSH0; PUSH3 0x010000; DUP1; MCOPY: nothing, from and to past the end.
+            code: Bytecode::new(vec![
+                0x60, 0x04, 0x5F, 0x60, 0x02, 0x5E, 0x5F, 0x62, 0x01, 0x00, 0x00, 0x80, 0x5E, 0x00,
+            ]),
+            memory: Memory::new_with_data(data),
+            ..Default::default()
+        }
+        .with_gas_limit(Some(1_000));
+        evm.run();
+        assert!(evm.status.is_success());
+
+        assert_eq!(&evm.memory.data[..6], &[1, 2, 1, 2, 3, 4]);
+        // Copying nothing from past the end neither grows memory nor costs more than the static 3.
+        assert_eq!(evm.memory.msize(), 32);
+        // The first MCOPY pays one copied word plus expansion to one word: gas charges memory it hasn't seen
+        // paid for.
+        assert_eq!(evm.gas.spent(), (3 + 2 + 3) + (3 + 3 + 3) + (2 + 3 + 3) + 3);
+    }
+
+    #[test]
+    fn sdiv_rounds_toward_zero_and_wraps_min_by_minus_one() {
+        let minus = |n: u64| U256::ZERO.wrapping_sub(U256::from(n));
+        let vectors = [
+            (minus(8), U256::from(3), minus(2)),
+            (U256::from(8), minus(3), minus(2)),
+            (minus(8), minus(3), U256::from(2)),
+            (minus(1), U256::ZERO, U256::ZERO),
+            (word(MIN), minus(1), word(MIN)),
+        ];
+        for (a, b, expected) in vectors {
+            let mut evm = Evm::default();
+            evm.stack.push(b).unwrap();
+            evm.stack.push(a).unwrap();
+            sdiv(&mut evm).unwrap();
+            assert_eq!(evm.stack.pop().unwrap(), expected, "SDIV({a:#x}, {b:#x})");
+        }
+    }
+
+    #[test]
+    fn smod_takes_the_sign_of_the_dividend() {
+        let minus = |n: u64| U256::ZERO.wrapping_sub(U256::from(n));
+        let vectors = [
+            (minus(1), U256::from(2), minus(1)),
+            (minus(8), U256::from(3), minus(2)),
+            (U256::from(8), minus(3), U256::from(2)),
+            (minus(8), minus(3), minus(2)),
+            (U256::from(10), U256::from(3), U256::from(1)),
+            (minus(1), U256::ZERO, U256::ZERO),
+            (word(MIN), minus(1), U256::ZERO),
+        ];
+        for (a, b, expected) in vectors {
+            let mut evm = Evm::default();
+            evm.stack.push(b).unwrap();
+            evm.stack.push(a).unwrap();
+            smod(&mut evm).unwrap();
+            assert_eq!(evm.stack.pop().unwrap(), expected, "SMOD({a:#x}, {b:#x})");
+        }
+    }
+
+    const ONES: &str = "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff";
+    const MIN: &str = "8000000000000000000000000000000000000000000000000000000000000000";
+    const MAX: &str = "7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff";
+
+    // Test vectors from https://eips.ethereum.org/EIPS/eip-145 as (value, shift, expected).
+
+    #[test]
+    fn shl_eip145_vectors() {
+        let vectors = [
+            ("01", "00", "01"),
+            ("01", "01", "02"),
+            ("01", "ff", MIN),
+            ("01", "0100", "00"),
+            ("01", "0101", "00"),
+            (ONES, "00", ONES),
+            (
+                ONES,
+                "01",
+                "fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe",
+            ),
+            (ONES, "ff", MIN),
+            (ONES, "0100", "00"),
+            ("00", "01", "00"),
+            (
+                MAX,
+                "01",
+                "fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe",
+            ),
+        ];
+
+        for (value, amount, expected) in vectors {
+            assert_eq!(
+                shift(shl, value, amount),
+                word(expected),
+                "SHL {value} by {amount}"
+            );
+        }
+    }
+
+    #[test]
+    fn shr_eip145_vectors() {
+        let vectors = [
+            ("01", "00", "01"),
+            ("01", "01", "00"),
+            (
+                MIN,
+                "01",
+                "4000000000000000000000000000000000000000000000000000000000000000",
+            ),
+            (MIN, "ff", "01"),
+            (MIN, "0100", "00"),
+            (MIN, "0101", "00"),
+            (ONES, "00", ONES),
+            (ONES, "01", MAX),
+            (ONES, "ff", "01"),
+            (ONES, "0100", "00"),
+            ("00", "01", "00"),
+        ];
+
+        for (value, amount, expected) in vectors {
+            assert_eq!(
+                shift(shr, value, amount),
+                word(expected),
+                "SHR {value} by {amount}"
+            );
+        }
+    }
+
+    #[test]
+    fn sar_eip145_vectors() {
+        let vectors = [
+            ("01", "00", "01"),
+            ("01", "01", "00"),
+            (
+                MIN,
+                "01",
+                "c000000000000000000000000000000000000000000000000000000000000000",
+            ),
+            (MIN, "ff", ONES),
+            (MIN, "0100", ONES),
+            (MIN, "0101", ONES),
+            (ONES, "00", ONES),
+            (ONES, "01", ONES),
+            (ONES, "ff", ONES),
+            (ONES, "0100", ONES),
+            ("00", "01", "00"),
+            (
+                "4000000000000000000000000000000000000000000000000000000000000000",
+                "fe",
+                "01",
+            ),
+            (MAX, "f8", "7f"),
+            (MAX, "fe", "01"),
+            (MAX, "ff", "00"),
+            (MAX, "0100", "00"),
+        ];
+
+        for (value, amount, expected) in vectors {
+            assert_eq!(
+                shift(sar, value, amount),
+                word(expected),
+                "SAR {value} by {amount}"
+            );
+        }
+    }
+
+    #[test]
+    fn shifts_saturate_for_amounts_beyond_u64() {
+        let huge = "010000000000000000000000000000000000";
+
+        assert_eq!(shift(shl, ONES, huge), U256::ZERO);
+        assert_eq!(shift(shr, ONES, huge), U256::ZERO);
+        assert_eq!(shift(sar, ONES, huge), U256::MAX);
+        assert_eq!(shift(sar, MAX, huge), U256::ZERO);
+    }
 }
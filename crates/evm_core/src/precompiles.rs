@@ -0,0 +1,696 @@
+//! Precompiled contracts: native implementations behind the addresses 0x01..=0x0a.
+//!
+//! A call whose code address is an active precompile doesn't run bytecode. `begin_call` still suspends the caller
+//! and starts the callee frame (value moves, the target is touched), then runs the precompile on the calldata
+//! with the forwarded gas and ends the frame at once: `Success` with the output and the gas it costs, or, when
+//! the gas doesn't cover the cost or the input is malformed, an exceptional halt consuming all of it.
+//!
+//! | address | precompile | since | gas |
+//! |---|---|---|---|
+//! | 0x01 | ecrecover | Frontier | 3000 |
+//! | 0x02 | sha256 | Frontier | 60 + 12 per word |
+//! | 0x03 | ripemd160 | Frontier | 600 + 120 per word |
+//! | 0x04 | identity | Frontier | 15 + 3 per word |
+//! | 0x05 | modexp (EIP-198) | Byzantium | EIP-198, EIP-2565 since Berlin |
+//! | 0x06 | bn128 add (EIP-196) | Byzantium | 500, 150 since Istanbul (EIP-1108) |
+//! | 0x07 | bn128 mul (EIP-196) | Byzantium | 40000, 6000 since Istanbul |
+//! | 0x08 | bn128 pairing (EIP-197) | Byzantium | 100000 + 80000 per pair, 45000 + 34000 since Istanbul |
+//! | 0x09 | blake2f (EIP-152) | Istanbul | 1 per round |
+//! | 0x0a | KZG point evaluation (EIP-4844) | Cancun | 50000 |
+//!
+//! Inputs shorter than a precompile reads are zero-padded on the right, except where the EIP asks for an exact
+//! length (blake2f, point evaluation, a pairing input that isn't whole pairs).
+
+use alloy::primitives::{Address, B256, Bytes, Signature, U256, uint};
+use num_bigint::BigUint;
+use primitives::errors::EvmErrors;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+use substrate_bn::{AffineG1, AffineG2, Fq, Fq2, Fr, G1, G2, Group, Gt};
+
+use crate::{Evm, ProgramExitStatus, operations::ariths::MEMORY_ADDRESS_LIMIT, spec::SpecId};
+
+/// What a precompile produced, and the gas it cost.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrecompileOutput {
+    pub gas_used: u64,
+    pub output: Bytes,
+}
+
+/// A precompile: `(input, gas_limit, spec)` to its output, `Err(EvmErrors::OutOfGas)` when `gas_limit` doesn't
+/// cover the cost, or `Err(EvmErrors::PrecompileFailed)` for malformed input.
+pub type PrecompileFn = fn(&[u8], u64, SpecId) -> Result<PrecompileOutput, EvmErrors>;
+
+/// The precompile at `address` under `spec`, if one is active there.
+pub fn precompile(address: Address, spec: SpecId) -> Option<PrecompileFn> {
+    if address[..19] != [0; 19] {
+        return None;
+    }
+    let (since, precompile): (SpecId, PrecompileFn) = match address[19] {
+        0x01 => (SpecId::Frontier, ecrecover),
+        0x02 => (SpecId::Frontier, sha256),
+        0x03 => (SpecId::Frontier, ripemd160),
+        0x04 => (SpecId::Frontier, identity),
+        0x05 => (SpecId::Byzantium, modexp),
+        0x06 => (SpecId::Byzantium, bn128_add),
+        0x07 => (SpecId::Byzantium, bn128_mul),
+        0x08 => (SpecId::Byzantium, bn128_pairing),
+        0x09 => (SpecId::Istanbul, blake2f),
+        0x0a => (SpecId::Cancun, point_evaluation),
+        _ => return None,
+    };
+    (spec >= since).then_some(precompile)
+}
+
+/// Addresses of the precompiles active under `spec`, ascending.
+pub fn active_precompiles(spec: SpecId) -> impl Iterator<Item = Address> {
+    (0x01..=0x0a)
+        .map(|n: u8| Address::left_padding_from(&[n]))
+        .filter(move |address| precompile(*address, spec).is_some())
+}
+
+impl Evm {
+    /// Run `precompile` as the executing callee frame (just entered by `begin_call`) and end it.
+    pub(crate) fn run_precompile(&mut self, precompile: PrecompileFn) {
+        match precompile(&self.context.calldata, self.gas.remaining(), self.spec) {
+            Ok(PrecompileOutput { gas_used, output }) => {
+                let _ = self.gas.record_cost(gas_used);
+                self.status = ProgramExitStatus::Success {
+                    output,
+                    gas_used: self.gas.spent(),
+                };
+            }
+            Err(error) => self.exceptional_halt(error),
+        }
+    }
+}
+
+/// `Err(EvmErrors::OutOfGas)` if a cost of `gas_used` is over `gas_limit`.
+fn charge(gas_used: u64, gas_limit: u64) -> Result<(), EvmErrors> {
+    if gas_used > gas_limit {
+        Err(EvmErrors::OutOfGas)
+    } else {
+        Ok(())
+    }
+}
+
+/// `base + word * ceil(len / 32)`, the cost of the hashing and copying precompiles.
+fn linear_gas(len: usize, base: u64, word: u64) -> u64 {
+    (len as u64)
+        .div_ceil(32)
+        .saturating_mul(word)
+        .saturating_add(base)
+}
+
+/// `input[offset..offset + len]`, zero-padded on the right where `input` is shorter.
+fn padded(input: &[u8], offset: usize, len: usize) -> Vec<u8> {
+    let mut out = vec![0; len];
+    if offset < input.len() {
+        let available = (input.len() - offset).min(len);
+        out[..available].copy_from_slice(&input[offset..offset + available]);
+    }
+    out
+}
+
+/// 0x01: the address that signed hash `h` with `(v, r, s)`, left-padded to 32 bytes, from
+/// `h ++ v ++ r ++ s` (32-byte words). An invalid signature, or `v` other than 27 or 28, succeeds with empty output.
+fn ecrecover(input: &[u8], gas_limit: u64, _spec: SpecId) -> Result<PrecompileOutput, EvmErrors> {
+    const GAS: u64 = 3_000;
+    charge(GAS, gas_limit)?;
+
+    let input = padded(input, 0, 128);
+    let v = U256::from_be_slice(&input[32..64]);
+    let recovered = if v == U256::from(27) || v == U256::from(28) {
+        let signature = Signature::from_scalars_and_parity(
+            B256::from_slice(&input[64..96]),
+            B256::from_slice(&input[96..128]),
+            v == U256::from(28),
+        );
+        signature
+            .recover_address_from_prehash(&B256::from_slice(&input[..32]))
+            .ok()
+    } else {
+        None
+    };
+    Ok(PrecompileOutput {
+        gas_used: GAS,
+        output: recovered.map_or_else(Bytes::new, |address| {
+            Bytes::copy_from_slice(&B256::left_padding_from(address.as_slice())[..])
+        }),
+    })
+}
+
+/// 0x02: SHA-256 of the input.
+fn sha256(input: &[u8], gas_limit: u64, _spec: SpecId) -> Result<PrecompileOutput, EvmErrors> {
+    let gas_used = linear_gas(input.len(), 60, 12);
+    charge(gas_used, gas_limit)?;
+    Ok(PrecompileOutput {
+        gas_used,
+        output: Bytes::copy_from_slice(&Sha256::digest(input)),
+    })
+}
+
+/// 0x03: RIPEMD-160 of the input, left-padded to 32 bytes.
+fn ripemd160(input: &[u8], gas_limit: u64, _spec: SpecId) -> Result<PrecompileOutput, EvmErrors> {
+    let gas_used = linear_gas(input.len(), 600, 120);
+    charge(gas_used, gas_limit)?;
+    Ok(PrecompileOutput {
+        gas_used,
+        output: Bytes::copy_from_slice(&B256::left_padding_from(&Ripemd160::digest(input))[..]),
+    })
+}
+
+/// 0x04: the input, unchanged.
+fn identity(input: &[u8], gas_limit: u64, _spec: SpecId) -> Result<PrecompileOutput, EvmErrors> {
+    let gas_used = linear_gas(input.len(), 15, 3);
+    charge(gas_used, gas_limit)?;
+    Ok(PrecompileOutput {
+        gas_used,
+        output: Bytes::copy_from_slice(input),
+    })
+}
+
+/// 0x05: `base ^ exp % modulus`, from `len(base) ++ len(exp) ++ len(modulus) ++ base ++ exp ++ modulus` (lengths
+/// as 32-byte words). The output is `len(modulus)` bytes; a zero modulus gives zeros.
+/// A length past `MEMORY_ADDRESS_LIMIT` that gas still covers (only under `Gas::unlimited`) fails with
+/// `PrecompileFailed` rather than allocating it, as memory would.
+fn modexp(input: &[u8], gas_limit: u64, spec: SpecId) -> Result<PrecompileOutput, EvmErrors> {
+    let length = |offset| U256::from_be_slice(&padded(input, offset, 32)).saturating_to::<u64>();
+    let (base_len, exp_len, mod_len) = (length(0), length(32), length(64));
+
+    // Bit length of the exponent's first (at most) 32 bytes, minus one, plus 8 per byte past those.
+    let exp_head_len = exp_len.min(32) as usize;
+    let exp_head = U256::from_be_slice(&padded(
+        input,
+        96usize.saturating_add(base_len as usize),
+        exp_head_len,
+    ));
+    let adjusted_exp_len = exp_len
+        .saturating_sub(32)
+        .saturating_mul(8)
+        .saturating_add((exp_head.bit_len() as u64).saturating_sub(1));
+
+    let max_len = base_len.max(mod_len) as u128;
+    let gas_used = if spec >= SpecId::Berlin {
+        // EIP-2565
+        let words = max_len.div_ceil(8);
+        let complexity = words.saturating_mul(words);
+        (complexity.saturating_mul(adjusted_exp_len.max(1) as u128) / 3).max(200)
+    } else {
+        // EIP-198
+        let complexity = if max_len <= 64 {
+            max_len * max_len
+        } else if max_len <= 1_024 {
+            max_len * max_len / 4 + 96 * max_len - 3_072
+        } else {
+            (max_len.saturating_mul(max_len) / 16)
+                .saturating_add(480 * max_len)
+                .saturating_sub(199_680)
+        };
+        complexity.saturating_mul(adjusted_exp_len.max(1) as u128) / 20
+    };
+    let gas_used = u64::try_from(gas_used).unwrap_or(u64::MAX);
+    charge(gas_used, gas_limit)?;
+
+    if mod_len == 0 {
+        return Ok(PrecompileOutput {
+            gas_used,
+            output: Bytes::new(),
+        });
+    }
+    if [base_len, exp_len, mod_len]
+        .into_iter()
+        .any(|len| len > MEMORY_ADDRESS_LIMIT as u64)
+    {
+        return Err(EvmErrors::PrecompileFailed);
+    }
+    // Paid for: the cost grows with the square of these lengths.
+    let (base_len, exp_len, mod_len) = (base_len as usize, exp_len as usize, mod_len as usize);
+    let base = BigUint::from_bytes_be(&padded(input, 96, base_len));
+    let exp = BigUint::from_bytes_be(&padded(input, 96 + base_len, exp_len));
+    let modulus = BigUint::from_bytes_be(&padded(input, 96 + base_len + exp_len, mod_len));
+
+    let mut output = vec![0; mod_len];
+    if modulus != BigUint::ZERO {
+        let result = base.modpow(&exp, &modulus).to_bytes_be();
+        output[mod_len - result.len()..].copy_from_slice(&result);
+    }
+    Ok(PrecompileOutput {
+        gas_used,
+        output: output.into(),
+    })
+}
+
+/// A G1 point from 64 bytes `x ++ y`; `(0, 0)` is the point at infinity.
+fn read_g1(input: &[u8]) -> Result<G1, EvmErrors> {
+    let x = Fq::from_slice(&input[..32]).map_err(|_| EvmErrors::PrecompileFailed)?;
+    let y = Fq::from_slice(&input[32..64]).map_err(|_| EvmErrors::PrecompileFailed)?;
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1::zero());
+    }
+    AffineG1::new(x, y)
+        .map(Into::into)
+        .map_err(|_| EvmErrors::PrecompileFailed)
+}
+
+/// A G2 point from 128 bytes `x_im ++ x_re ++ y_im ++ y_re`; all zeros is the point at infinity.
+fn read_g2(input: &[u8]) -> Result<G2, EvmErrors> {
+    let fq = |offset: usize| {
+        Fq::from_slice(&input[offset..offset + 32]).map_err(|_| EvmErrors::PrecompileFailed)
+    };
+    let x = Fq2::new(fq(32)?, fq(0)?);
+    let y = Fq2::new(fq(96)?, fq(64)?);
+    if x.is_zero() && y.is_zero() {
+        return Ok(G2::zero());
+    }
+    AffineG2::new(x, y)
+        .map(Into::into)
+        .map_err(|_| EvmErrors::PrecompileFailed)
+}
+
+/// `x ++ y` of `point`, or 64 zero bytes for the point at infinity.
+fn write_g1(point: G1) -> Bytes {
+    let mut output = [0u8; 64];
+    if let Some(point) = AffineG1::from_jacobian(point) {
+        // Both slices are 32 bytes, the only length `to_big_endian` rejects otherwise.
+        let _ = point.x().to_big_endian(&mut output[..32]);
+        let _ = point.y().to_big_endian(&mut output[32..]);
+    }
+    Bytes::copy_from_slice(&output)
+}
+
+/// 0x06: the sum of two alt_bn128 G1 points, from `a ++ b` (64 bytes each).
+fn bn128_add(input: &[u8], gas_limit: u64, spec: SpecId) -> Result<PrecompileOutput, EvmErrors> {
+    let gas_used = if spec >= SpecId::Istanbul { 150 } else { 500 };
+    charge(gas_used, gas_limit)?;
+    let input = padded(input, 0, 128);
+    let sum = read_g1(&input[..64])? + read_g1(&input[64..])?;
+    Ok(PrecompileOutput {
+        gas_used,
+        output: write_g1(sum),
+    })
+}
+
+/// 0x07: an alt_bn128 G1 point times a scalar, from `point ++ scalar` (64 and 32 bytes).
+fn bn128_mul(input: &[u8], gas_limit: u64, spec: SpecId) -> Result<PrecompileOutput, EvmErrors> {
+    let gas_used = if spec >= SpecId::Istanbul {
+        6_000
+    } else {
+        40_000
+    };
+    charge(gas_used, gas_limit)?;
+    let input = padded(input, 0, 96);
+    let point = read_g1(&input[..64])?;
+    let scalar = Fr::from_slice(&input[64..]).map_err(|_| EvmErrors::PrecompileFailed)?;
+    Ok(PrecompileOutput {
+        gas_used,
+        output: write_g1(point * scalar),
+    })
+}
+
+/// 0x08: 1 (as a 32-byte word) if the product of the pairings of `(G1, G2)` pairs (192 bytes each) is one, else 0.
+fn bn128_pairing(
+    input: &[u8],
+    gas_limit: u64,
+    spec: SpecId,
+) -> Result<PrecompileOutput, EvmErrors> {
+    let (base, per_pair) = if spec >= SpecId::Istanbul {
+        (45_000, 34_000)
+    } else {
+        (100_000, 80_000)
+    };
+    let gas_used = ((input.len() / 192) as u64)
+        .saturating_mul(per_pair)
+        .saturating_add(base);
+    charge(gas_used, gas_limit)?;
+    if !input.len().is_multiple_of(192) {
+        return Err(EvmErrors::PrecompileFailed);
+    }
+
+    let pairs = input
+        .chunks(192)
+        .map(|pair| Ok((read_g1(&pair[..64])?, read_g2(&pair[64..])?)))
+        .collect::<Result<Vec<_>, EvmErrors>>()?;
+    let holds = substrate_bn::pairing_batch(&pairs) == Gt::one();
+    Ok(PrecompileOutput {
+        gas_used,
+        output: Bytes::copy_from_slice(&U256::from(holds).to_be_bytes::<32>()),
+    })
+}
+
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// The BLAKE2b mixing function G on words `a`, `b`, `c`, `d` of `v`.
+fn blake2b_mix(v: &mut [u64; 16], [a, b, c, d]: [usize; 4], x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// The BLAKE2b compression function F (RFC 7693) with a caller-chosen number of rounds.
+fn blake2b_compress(rounds: u32, h: &mut [u64; 8], m: &[u64; 16], t: [u64; 2], last: bool) {
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&BLAKE2B_IV);
+    v[12] ^= t[0];
+    v[13] ^= t[1];
+    if last {
+        v[14] = !v[14];
+    }
+    for round in 0..rounds as usize {
+        let s = &BLAKE2B_SIGMA[round % 10];
+        blake2b_mix(&mut v, [0, 4, 8, 12], m[s[0]], m[s[1]]);
+        blake2b_mix(&mut v, [1, 5, 9, 13], m[s[2]], m[s[3]]);
+        blake2b_mix(&mut v, [2, 6, 10, 14], m[s[4]], m[s[5]]);
+        blake2b_mix(&mut v, [3, 7, 11, 15], m[s[6]], m[s[7]]);
+        blake2b_mix(&mut v, [0, 5, 10, 15], m[s[8]], m[s[9]]);
+        blake2b_mix(&mut v, [1, 6, 11, 12], m[s[10]], m[s[11]]);
+        blake2b_mix(&mut v, [2, 7, 8, 13], m[s[12]], m[s[13]]);
+        blake2b_mix(&mut v, [3, 4, 9, 14], m[s[14]], m[s[15]]);
+    }
+    for (i, word) in h.iter_mut().enumerate() {
+        *word ^= v[i] ^ v[i + 8];
+    }
+}
+
+/// 0x09: the BLAKE2b compression function F, from exactly 213 bytes
+/// `rounds (4, big-endian) ++ h (64) ++ m (128) ++ t (16) ++ f (1)`, words little-endian. Returns the new `h`.
+fn blake2f(input: &[u8], gas_limit: u64, _spec: SpecId) -> Result<PrecompileOutput, EvmErrors> {
+    if input.len() != 213 {
+        return Err(EvmErrors::PrecompileFailed);
+    }
+    let rounds = u32::from_be_bytes(input[..4].try_into().unwrap());
+    let gas_used = u64::from(rounds);
+    charge(gas_used, gas_limit)?;
+    let last = match input[212] {
+        0 => false,
+        1 => true,
+        _ => return Err(EvmErrors::PrecompileFailed),
+    };
+
+    let word = |offset: usize| u64::from_le_bytes(input[offset..offset + 8].try_into().unwrap());
+    let mut h: [u64; 8] = std::array::from_fn(|i| word(4 + 8 * i));
+    let m: [u64; 16] = std::array::from_fn(|i| word(68 + 8 * i));
+    blake2b_compress(rounds, &mut h, &m, [word(196), word(204)], last);
+
+    Ok(PrecompileOutput {
+        gas_used,
+        output: h.iter().flat_map(|word| word.to_le_bytes()).collect(),
+    })
+}
+
+/// Field elements per blob (EIP-4844).
+const FIELD_ELEMENTS_PER_BLOB: u64 = 4_096;
+
+/// Order of the BLS12-381 scalar field.
+const BLS_MODULUS: U256 =
+    uint!(0x73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001_U256);
+
+/// 0x0a: verify that the blob committed to by `commitment` evaluates to `y` at `z`, from exactly 192 bytes
+/// `versioned_hash ++ z ++ y ++ commitment (48) ++ proof (48)`. The versioned hash must be
+/// `0x01 ++ sha256(commitment)[1..]`. Returns `FIELD_ELEMENTS_PER_BLOB ++ BLS_MODULUS` as 32-byte words.
+fn point_evaluation(
+    input: &[u8],
+    gas_limit: u64,
+    _spec: SpecId,
+) -> Result<PrecompileOutput, EvmErrors> {
+    const GAS: u64 = 50_000;
+    charge(GAS, gas_limit)?;
+    if input.len() != 192 {
+        return Err(EvmErrors::PrecompileFailed);
+    }
+
+    let commitment = &input[96..144];
+    let mut versioned_hash = Sha256::digest(commitment);
+    versioned_hash[0] = 0x01;
+    if input[..32] != versioned_hash[..] {
+        return Err(EvmErrors::PrecompileFailed);
+    }
+
+    let verified = (|| {
+        c_kzg::ethereum_kzg_settings(0).verify_kzg_proof(
+            &c_kzg::Bytes48::from_bytes(commitment)?,
+            &c_kzg::Bytes32::from_bytes(&input[32..64])?,
+            &c_kzg::Bytes32::from_bytes(&input[64..96])?,
+            &c_kzg::Bytes48::from_bytes(&input[144..])?,
+        )
+    })();
+    if !matches!(verified, Ok(true)) {
+        return Err(EvmErrors::PrecompileFailed);
+    }
+
+    let mut output = U256::from(FIELD_ELEMENTS_PER_BLOB).to_be_bytes_vec();
+    output.extend_from_slice(&BLS_MODULUS.to_be_bytes::<32>());
+    Ok(PrecompileOutput {
+        gas_used: GAS,
+        output: output.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{address, hex};
+    use primitives::bytecode::Bytecode;
+
+    use super::*;
+    use crate::expect_stack;
+
+    fn call(address: u8, input: &[u8], spec: SpecId) -> Result<PrecompileOutput, EvmErrors> {
+        let precompile = precompile(Address::left_padding_from(&[address]), spec).unwrap();
+        precompile(input, 1_000_000, spec)
+    }
+
+    #[test]
+    fn activation_follows_the_forks() {
+        let count = |spec| active_precompiles(spec).count();
+        assert_eq!(count(SpecId::Homestead), 4);
+        assert_eq!(count(SpecId::Byzantium), 8);
+        assert_eq!(count(SpecId::Istanbul), 9);
+        assert_eq!(count(SpecId::Cancun), 10);
+        assert!(precompile(Address::left_padding_from(&[0x0b]), SpecId::Prague).is_none());
+        assert!(precompile(Address::repeat_byte(1), SpecId::Prague).is_none());
+    }
+
+    #[test]
+    fn hashes_and_identity() {
+        let sha = call(0x02, b"", SpecId::Cancun).unwrap();
+        assert_eq!(sha.gas_used, 60);
+        assert_eq!(
+            sha.output[..],
+            hex!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+
+        let ripemd = call(0x03, b"", SpecId::Cancun).unwrap();
+        assert_eq!(ripemd.gas_used, 600);
+        assert_eq!(
+            ripemd.output[..],
+            hex!("0000000000000000000000009c1185a5c5e9fc54612808977ee8f548b2258d31")
+        );
+
+        let copied = call(0x04, &[7; 33], SpecId::Cancun).unwrap();
+        assert_eq!(copied.gas_used, 15 + 3 * 2);
+        assert_eq!(copied.output[..], [7; 33]);
+        assert_eq!(
+            identity(&[7; 33], 20, SpecId::Cancun),
+            Err(EvmErrors::OutOfGas)
+        );
+    }
+
+    #[test]
+    fn ecrecover_ignores_bad_signatures() {
+        let mut input = [0u8; 128];
+        input[63] = 29;
+        let out = call(0x01, &input, SpecId::Cancun).unwrap();
+        assert_eq!(out.gas_used, 3_000);
+        assert!(out.output.is_empty());
+        // v = 27 but r = s = 0.
+        input[63] = 27;
+        assert!(
+            call(0x01, &input, SpecId::Cancun)
+                .unwrap()
+                .output
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn modexp_prices_per_fork() {
+        // 3 ^ 5 % 7, one byte each.
+        let mut input = vec![0u8; 96];
+        for len in [31, 63, 95] {
+            input[len] = 1;
+        }
+        input.extend([3, 5, 7]);
+
+        let berlin = call(0x05, &input, SpecId::Berlin).unwrap();
+        assert_eq!(berlin.output[..], [5]);
+        assert_eq!(berlin.gas_used, 200);
+        // EIP-198: 1 * 2 / 20 (the exponent's bit length is 3).
+        assert_eq!(call(0x05, &input, SpecId::Byzantium).unwrap().gas_used, 0);
+
+        // Zero modulus: zeros of the modulus' length.
+        let zero_modulus = [&input[..96], &[3, 5, 0][..]].concat();
+        assert_eq!(
+            call(0x05, &zero_modulus, SpecId::Cancun).unwrap().output[..],
+            [0]
+        );
+    }
+
+    #[test]
+    fn modexp_lengths_past_the_memory_limit_fail_without_allocating() {
+        // base_len = 2^32, exp_len = 0, mod_len = 1 and no data.
+        let mut input = vec![0u8; 96];
+        input[27] = 1;
+        input[95] = 1;
+
+        assert_eq!(call(0x05, &input, SpecId::Cancun), Err(EvmErrors::OutOfGas));
+        assert_eq!(
+            modexp(&input, u64::MAX, SpecId::Cancun),
+            Err(EvmErrors::PrecompileFailed)
+        );
+        // mod_len = 2^32 is capped the same way.
+        input[27] = 0;
+        input[31] = 0;
+        input[91] = 1;
+        input[95] = 0;
+        assert_eq!(
+            modexp(&input, u64::MAX, SpecId::Cancun),
+            Err(EvmErrors::PrecompileFailed)
+        );
+    }
+
+    #[test]
+    fn bn128_add_mul_and_pairing_agree() {
+        // The G1 generator (1, 2).
+        let mut generator = [0u8; 64];
+        generator[31] = 1;
+        generator[63] = 2;
+
+        let doubled = call(0x06, &[generator, generator].concat(), SpecId::Cancun).unwrap();
+        assert_eq!(doubled.gas_used, 150);
+        let mut two = [0u8; 32];
+        two[31] = 2;
+        let times_two = call(0x07, &[&generator[..], &two].concat(), SpecId::Byzantium).unwrap();
+        assert_eq!(times_two.gas_used, 40_000);
+        assert_eq!(doubled.output, times_two.output);
+        assert_ne!(doubled.output[..], [0; 64]);
+
+        // A point off the curve.
+        generator[63] = 3;
+        assert_eq!(
+            call(0x06, &generator, SpecId::Cancun),
+            Err(EvmErrors::PrecompileFailed)
+        );
+
+        // No pairs: the empty product is one.
+        let empty = call(0x08, &[], SpecId::Cancun).unwrap();
+        assert_eq!(empty.gas_used, 45_000);
+        assert_eq!(empty.output[31], 1);
+        assert_eq!(
+            call(0x08, &[0; 191], SpecId::Cancun),
+            Err(EvmErrors::PrecompileFailed)
+        );
+    }
+
+    #[test]
+    fn blake2f_matches_eip_152() {
+        // EIP-152 test vector 5: BLAKE2b-512("abc") in 12 rounds. `h` is the IV with the parameter block mixed in.
+        let mut h = BLAKE2B_IV;
+        h[0] ^= 0x0101_0040;
+        let mut input = 12u32.to_be_bytes().to_vec();
+        input.extend(h.iter().flat_map(|word| word.to_le_bytes()));
+        input.extend(*b"abc");
+        input.extend([0; 125]);
+        input.extend(3u64.to_le_bytes());
+        input.extend([0; 8]);
+        input.push(1);
+
+        let out = call(0x09, &input, SpecId::Cancun).unwrap();
+        assert_eq!(out.gas_used, 12);
+        assert_eq!(
+            out.output[..],
+            hex!(
+                "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+            )
+        );
+        assert_eq!(
+            call(0x09, &input[1..], SpecId::Cancun),
+            Err(EvmErrors::PrecompileFailed)
+        );
+    }
+
+    #[test]
+    fn point_evaluation_checks_the_versioned_hash() {
+        assert_eq!(
+            call(0x0a, &[0; 192], SpecId::Cancun),
+            Err(EvmErrors::PrecompileFailed)
+        );
+        assert_eq!(
+            point_evaluation(&[0; 192], 49_999, SpecId::Cancun),
+            Err(EvmErrors::OutOfGas)
+        );
+    }
+
+    #[test]
+    fn calls_dispatch_to_precompiles() {
+        // MSTORE(0, 0x2a); STATICCALL(gas 0xffff, target, 0, 32, 32, 32); MLOAD 32; STOP
+        let run = |target: u8, spec| {
+            let code = vec![
+                0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x20, 0x60, 0x20, 0x60, 0x00, 0x60,
+                target, 0x61, 0xff, 0xff, 0xFA, 0x60, 0x20, 0x51, 0x00,
+            ];
+            let mut evm = Evm {
+                code: Bytecode::new(code),
+                ..Default::default()
+            }
+            .with_gas_limit(Some(100_000))
+            .with_spec(spec);
+            evm.run();
+            evm
+        };
+
+        let identity = run(0x04, SpecId::Cancun);
+        assert!(identity.status.is_success());
+        expect_stack!(identity, [1, 0x2a]);
+        assert_eq!(identity.return_data.len(), 32);
+
+        // A failing precompile (bn128 add on a point off the curve) keeps all the gas it was given.
+        let failed = run(0x06, SpecId::Cancun);
+        expect_stack!(failed, [0, 0]);
+        assert!(failed.gas.spent() > 0xffff);
+
+        // blake2f isn't active before Istanbul: 0x09 is an empty account there.
+        expect_stack!(run(0x09, SpecId::Byzantium), [1, 0]);
+        assert_eq!(
+            active_precompiles(SpecId::Cancun).last(),
+            Some(address!("0x000000000000000000000000000000000000000a"))
+        );
+    }
+}
@@ -0,0 +1,30 @@
+//! Everything needed to build, run and inspect an `Evm`, in one import.
+
+pub use crate::{
+    Evm, ProgramExitStatus, TransactResult, Trap,
+    access::AccessSet,
+    call_inputs::{CallInputs, CallScheme, CreateInputs, CreateScheme},
+    checkpoint::Checkpoint,
+    erc7562::{ValidationReport, Violation, ViolationKind, inspect_validation},
+    gas::Gas,
+    gas_diff::{GasDiffReport, OpGas, OpGasDiff, gas_diff},
+    limits::{Limits, Profile},
+    op_stats::{OpStats, OpStatsReport, SequenceCount},
+    opcodes::{Opcode, OpcodeInfo},
+    precompiles::{PrecompileFn, PrecompileOutput, precompile},
+    spec::SpecId,
+    tracer::{Redaction, TraceSink, TraceStep, Tracer},
+};
+pub use primitives::{
+    bytecode::Bytecode,
+    errors::EvmErrors,
+    evm_types::{
+        AccountDiff, AccountInfo, BlockEnv, EvmAccount, EvmStorage, StateDiff, StateSize,
+        StorageRange, Transaction,
+    },
+    journal::{JournalCheckpoint, JournalEntry, JournaledState},
+    memory::Memory,
+    rpc_snapshot::{ImportSummary, RpcRecord},
+    stack::Stack,
+    state_dump::{SerializableAccount, SerializableState},
+};
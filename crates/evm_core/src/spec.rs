@@ -0,0 +1,77 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Ethereum mainnet hardforks, in activation order. `Ord` follows activation, so
+/// `spec >= SpecId::Shanghai` reads as "Shanghai rules are active".
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum SpecId {
+    Frontier,
+    Homestead,
+    TangerineWhistle,
+    SpuriousDragon,
+    Byzantium,
+    Constantinople,
+    Petersburg,
+    Istanbul,
+    Berlin,
+    London,
+    Merge,
+    Shanghai,
+    #[default]
+    Cancun,
+    Prague,
+}
+
+impl SpecId {
+    /// Number of forks, for tables indexed by `spec as usize`.
+    pub const COUNT: usize = SpecId::Prague as usize + 1;
+
+    /// Every fork, in activation order.
+    pub const ALL: [SpecId; SpecId::COUNT] = [
+        SpecId::Frontier,
+        SpecId::Homestead,
+        SpecId::TangerineWhistle,
+        SpecId::SpuriousDragon,
+        SpecId::Byzantium,
+        SpecId::Constantinople,
+        SpecId::Petersburg,
+        SpecId::Istanbul,
+        SpecId::Berlin,
+        SpecId::London,
+        SpecId::Merge,
+        SpecId::Shanghai,
+        SpecId::Cancun,
+        SpecId::Prague,
+    ];
+}
+
+/// Parses fork names case-insensitively (`cancun`, `Shanghai`, `tangerinewhistle`); `paris` is accepted for `Merge`.
+impl FromStr for SpecId {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        if name.eq_ignore_ascii_case("paris") {
+            return Ok(SpecId::Merge);
+        }
+        SpecId::ALL
+            .into_iter()
+            .find(|spec| format!("{spec:?}").eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("unknown fork `{name}`"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fork_names() {
+        assert_eq!("cancun".parse(), Ok(SpecId::Cancun));
+        assert_eq!("TangerineWhistle".parse(), Ok(SpecId::TangerineWhistle));
+        assert_eq!("paris".parse(), Ok(SpecId::Merge));
+        assert!("osaka".parse::<SpecId>().is_err());
+    }
+}
@@ -0,0 +1,363 @@
+//! Instruction-level tracing with pluggable output sinks.
+//!
+//! `Evm::run_traced` records one `TraceStep` per executed instruction (in the spirit of EIP-3155) and hands it
+//! to a `Tracer`, which forwards it to the configured `TraceSink`:
+//! - `Writer`: any `io::Write`, one JSON object per line (files, sockets, stdout).
+//! - `Channel`: an mpsc sender, for streaming steps to another thread as they happen.
+//! - `Buffer`: kept in memory, for tests and small runs.
+//! - `Callback`: a closure called with each step.
+//!
+//! Only `Buffer` holds on to steps, so a server streaming traces never buffers a whole execution.
+//!
+//! `Tracer::with_redaction` scrubs stack values before they reach the sink, so traces of proprietary contracts
+//! can be attached to bug reports: see `Redaction`.
+
+use std::{
+    fmt,
+    io::{self, Write},
+    sync::mpsc::Sender,
+};
+
+use alloy::primitives::{B256, U256, keccak256};
+use serde::Serialize;
+
+use crate::{Evm, ProgramExitStatus, opcodes::Opcode};
+
+/// One executed instruction, captured before it ran (except `gas_cost`, measured across it).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceStep {
+    pub pc: usize,
+    pub op: u8,
+    pub op_name: String,
+    pub gas: u64,
+    pub gas_cost: u64,
+    /// Stack before the instruction, bottom first.
+    pub stack: Vec<U256>,
+    pub depth: usize,
+}
+
+/// Where a `Tracer` sends its steps.
+pub enum TraceSink {
+    Writer(Box<dyn Write + Send>),
+    Channel(Sender<TraceStep>),
+    Buffer(Vec<TraceStep>),
+    Callback(Box<dyn FnMut(&TraceStep) + Send>),
+}
+
+impl fmt::Debug for TraceSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceSink::Writer(_) => f.write_str("Writer"),
+            TraceSink::Channel(_) => f.write_str("Channel"),
+            TraceSink::Buffer(steps) => f.debug_tuple("Buffer").field(&steps.len()).finish(),
+            TraceSink::Callback(_) => f.write_str("Callback"),
+        }
+    }
+}
+
+/// How a redacting `Tracer` rewrites stack words. Everything else in a step (pc, opcode, gas, depth, stack
+/// height) is kept, so the trace still shows what the interpreter did.
+/// - Words below `2^(8 * keep_bytes)` are kept: offsets, sizes, counters, jump targets and small constants.
+/// - Address-shaped words (exactly 20 significant bytes) become `keccak256(salt ++ word)[12..]`.
+/// - Any other word keeps its leading `keep_bytes` bytes (a calldata word keeps its selector); the rest is taken
+///   from the same hash.
+///
+/// Equal words get equal replacements, so comparisons and lookups still read the same in the redacted trace.
+/// Pick a random `salt` per report, or known addresses and values can be recovered by hashing candidates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Redaction {
+    pub salt: B256,
+    pub keep_bytes: usize,
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Redaction {
+            salt: B256::ZERO,
+            keep_bytes: 4,
+        }
+    }
+}
+
+impl Redaction {
+    /// The replacement for one stack word.
+    pub fn redact_word(&self, word: U256) -> U256 {
+        let keep_bits = 8 * self.keep_bytes.min(32);
+        if keep_bits == 256 || word >> keep_bits == U256::ZERO {
+            return word;
+        }
+        let digest = keccak256([self.salt.as_slice(), &word.to_be_bytes::<32>()].concat());
+        let mut bytes = [0u8; 32];
+        if word.bit_len() > 152 && word.bit_len() <= 160 {
+            bytes[12..].copy_from_slice(&digest[12..]);
+        } else {
+            bytes[..self.keep_bytes].copy_from_slice(&word.to_be_bytes::<32>()[..self.keep_bytes]);
+            bytes[self.keep_bytes..].copy_from_slice(&digest[self.keep_bytes..]);
+        }
+        U256::from_be_bytes(bytes)
+    }
+
+    /// Redact every stack word of `step` in place.
+    pub fn redact_step(&self, step: &mut TraceStep) {
+        for word in &mut step.stack {
+            *word = self.redact_word(*word);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Tracer {
+    sink: TraceSink,
+    redaction: Option<Redaction>,
+}
+
+impl Tracer {
+    pub fn new(sink: TraceSink) -> Self {
+        Tracer {
+            sink,
+            redaction: None,
+        }
+    }
+
+    /// Redact every recorded step with `redaction` before it reaches the sink.
+    pub fn with_redaction(mut self, redaction: Redaction) -> Self {
+        self.redaction = Some(redaction);
+        self
+    }
+
+    /// Forward one step to the sink, redacted if the tracer was built `with_redaction`.
+    /// A `Channel` whose receiver is gone drops the step silently (the client went away);
+    /// `Writer` errors are returned so the run can stop.
+    pub fn record(&mut self, mut step: TraceStep) -> io::Result<()> {
+        if let Some(redaction) = &self.redaction {
+            redaction.redact_step(&mut step);
+        }
+        match &mut self.sink {
+            TraceSink::Writer(writer) => {
+                serde_json::to_writer(&mut *writer, &step)?;
+                writer.write_all(b"\n")
+            }
+            TraceSink::Channel(sender) => {
+                let _ = sender.send(step);
+                Ok(())
+            }
+            TraceSink::Buffer(steps) => {
+                steps.push(step);
+                Ok(())
+            }
+            TraceSink::Callback(callback) => {
+                callback(&step);
+                Ok(())
+            }
+        }
+    }
+
+    /// Flush a `Writer` sink; other sinks have nothing to flush.
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.sink {
+            TraceSink::Writer(writer) => writer.flush(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Give back the sink, e.g. to read a `Buffer` after the run.
+    pub fn into_sink(self) -> TraceSink {
+        self.sink
+    }
+}
+
+impl Evm {
+    /// Like `run`, but records every executed instruction into `tracer`.
+    /// Stops early with the sink's error if a `Writer` sink fails.
+    ///
+    /// A step's `gas_cost` is charged to the frame it ran in: for a CALL or CREATE that starts a callee it includes
+    /// the gas forwarded to it. Running off the end of the code (an implicit STOP) isn't recorded.
+    pub fn run_traced(&mut self, tracer: &mut Tracer) -> io::Result<()> {
+        while self.status == ProgramExitStatus::default() {
+            if self.pc >= self.code.len() {
+                // Inside a callee this resumes the caller, whose instructions are still to be traced.
+                self.step();
+                continue;
+            }
+
+            let op = self.code.as_slice()[self.pc];
+            let pc = self.pc;
+            let gas = self.gas.remaining();
+            let stack = self.stack.as_slice().to_vec();
+            let executed = self.instructions_executed;
+            let depth = self.depth();
+
+            self.execute_instruction();
+            if self.status == ProgramExitStatus::InstructionBudgetExhausted
+                && self.instructions_executed == executed
+            {
+                break;
+            }
+            // A callee started by this step has parked the frame it ran in on the call stack.
+            let remaining = if self.depth() > depth {
+                self.call_stack
+                    .frames()
+                    .last()
+                    .map_or(0, |frame| frame.gas.remaining())
+            } else {
+                self.gas.remaining()
+            };
+            self.end_frame();
+
+            tracer.record(TraceStep {
+                pc,
+                op,
+                op_name: Opcode::from_u8(op)
+                    .map_or_else(|| format!("0x{op:02x}"), |opcode| opcode.to_string()),
+                gas,
+                gas_cost: gas - remaining,
+                stack,
+                depth,
+            })?;
+        }
+        tracer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex, mpsc};
+
+    use alloy::primitives::Address;
+    use primitives::bytecode::Bytecode;
+
+    use super::*;
+    use crate::expect_stack;
+
+    /// PUSH1 1; PUSH1 2; ADD; STOP
+    fn evm() -> Evm {
+        Evm {
+            code: Bytecode::new(vec![0x60, 0x01, 0x60, 0x02, 0x01, 0x00]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn buffer_records_every_instruction() {
+        let mut tracer = Tracer::new(TraceSink::Buffer(Vec::new()));
+        evm().run_traced(&mut tracer).unwrap();
+
+        let TraceSink::Buffer(steps) = tracer.into_sink() else {
+            unreachable!()
+        };
+        let names: Vec<&str> = steps.iter().map(|s| s.op_name.as_str()).collect();
+        assert_eq!(names, ["PUSH1", "PUSH1", "ADD", "STOP"]);
+        assert_eq!(steps[2].stack, vec![U256::from(1), U256::from(2)]);
+        assert_eq!(steps[2].gas_cost, 3);
+    }
+
+    #[test]
+    fn nested_calls_are_traced_per_frame() {
+        let callee = Address::left_padding_from(&[0xc0]);
+        // CALL(gas 0xffff, 0xc0, 0, 0, 0, 0, 0); PUSH1 7; STOP
+        let caller = vec![
+            0x5F, 0x5F, 0x5F, 0x5F, 0x5F, 0x60, 0xc0, 0x61, 0xff, 0xff, 0xF1, 0x60, 0x07, 0x00,
+        ];
+        // An explicit STOP, and code that runs off its end (the implicit STOP isn't a step).
+        for callee_code in [vec![0x00], vec![0x60, 0x01]] {
+            let mut evm = Evm {
+                code: Bytecode::new(caller.clone()),
+                ..Default::default()
+            }
+            .with_gas_limit(Some(100_000));
+            evm.storage.set_code(callee, callee_code);
+            let mut tracer = Tracer::new(TraceSink::Buffer(Vec::new()));
+            evm.run_traced(&mut tracer).unwrap();
+
+            assert!(evm.status.is_success());
+            expect_stack!(evm, [1, 7]);
+            let TraceSink::Buffer(steps) = tracer.into_sink() else {
+                unreachable!()
+            };
+            let depths: Vec<usize> = steps.iter().map(|step| step.depth).collect();
+            assert_eq!(depths, [1, 1, 1, 1, 1, 1, 1, 1, 2, 1, 1]);
+
+            // The CALL is charged the forwarded gas (plus the cold access); the callee's unused gas comes back
+            // without showing up as a negative cost.
+            let call = &steps[7];
+            assert_eq!(call.op_name, "CALL");
+            assert!(call.gas_cost > 0xffff);
+            assert_eq!(steps[8].gas, 0xffff);
+            assert_eq!(steps.last().unwrap().gas_cost, 0);
+        }
+    }
+
+    #[test]
+    fn channel_streams_steps() {
+        let (sender, receiver) = mpsc::channel();
+        let mut tracer = Tracer::new(TraceSink::Channel(sender));
+        evm().run_traced(&mut tracer).unwrap();
+        drop(tracer);
+
+        assert_eq!(
+            receiver.iter().map(|step| step.pc).collect::<Vec<_>>(),
+            [0, 2, 4, 5]
+        );
+    }
+
+    #[test]
+    fn redaction_keeps_small_words_and_pseudonymises_the_rest() {
+        let redaction = Redaction {
+            salt: B256::repeat_byte(7),
+            keep_bytes: 4,
+        };
+        let address = U256::from_be_slice(&[0xAB; 20]);
+        let selector_word = U256::from(0xa9059cbbu64) << 224 | U256::from(12345);
+
+        assert_eq!(redaction.redact_word(U256::from(0x40)), U256::from(0x40));
+        let pseudonym = redaction.redact_word(address);
+        assert_ne!(pseudonym, address);
+        assert_eq!(pseudonym >> 160, U256::ZERO);
+        assert_eq!(redaction.redact_word(address), pseudonym);
+        let redacted = redaction.redact_word(selector_word);
+        assert_eq!(redacted >> 224, U256::from(0xa9059cbbu64));
+        assert_ne!(redacted, selector_word);
+
+        // PUSH20 address; PUSH1 0x40; STOP
+        let mut code = vec![0x73];
+        code.extend([0xAB; 20]);
+        code.extend([0x60, 0x40, 0x00]);
+        let mut evm = Evm {
+            code: Bytecode::new(code),
+            ..Default::default()
+        };
+        let mut tracer = Tracer::new(TraceSink::Buffer(Vec::new())).with_redaction(redaction);
+        evm.run_traced(&mut tracer).unwrap();
+
+        let TraceSink::Buffer(steps) = tracer.into_sink() else {
+            unreachable!()
+        };
+        assert_eq!(steps[2].stack, vec![pseudonym, U256::from(0x40)]);
+        assert_eq!(steps[2].op_name, "STOP");
+    }
+
+    #[test]
+    fn writer_emits_json_lines() {
+        #[derive(Clone, Default)]
+        struct Shared(Arc<Mutex<Vec<u8>>>);
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let output = Shared::default();
+        let mut tracer = Tracer::new(TraceSink::Writer(Box::new(output.clone())));
+        evm().run_traced(&mut tracer).unwrap();
+
+        let text = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[2].contains(r#""opName":"ADD""#));
+        assert!(lines[2].contains(r#""gasCost":3"#));
+    }
+}
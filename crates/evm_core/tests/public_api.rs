@@ -0,0 +1,33 @@
+//! Compile-time check of the stable surface: everything here must keep building through `prelude`
+//! alone. A change that breaks this file is a breaking API change.
+
+use alloy::primitives::{Address, U256};
+use evm_core::prelude::*;
+
+#[test]
+fn prelude_covers_a_full_transaction() {
+    let tx = Transaction {
+        from: Address::with_last_byte(1),
+        gas_limit: U256::from(100_000),
+        data: vec![0x60, 0x01, 0x60, 0x02, 0x01, 0x00].into(),
+        ..Default::default()
+    };
+    let mut evm: Evm = Evm::new(
+        BlockEnv::default(),
+        tx,
+        Memory::new_with_data(vec![0u8; 64]),
+        Stack::default(),
+        EvmStorage::default(),
+    )
+    .with_gas_limit(None)
+    .with_instruction_budget(100);
+
+    let result: TransactResult = evm.transact();
+    let _: &StateDiff = &result.state_diff;
+    let _: &[Trap] = &evm.traps;
+    let _: Gas = evm.gas;
+
+    assert!(result.status.is_success());
+    assert_eq!(evm.stack.as_slice(), &[U256::from(3)]);
+    assert_eq!(Opcode::ADD.info().introduced, Some(SpecId::Frontier));
+}
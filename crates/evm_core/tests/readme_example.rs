@@ -0,0 +1,55 @@
+//! End-to-end run of the example documented in the README and `bins/evm/src/main.rs`:
+//! `PUSH1 6; PUSH1 7; ADD; PUSH1 0; MSTORE; PUSH1 0; MLOAD; STOP`.
+
+use alloy::primitives::{Address, U256};
+use evm_core::Evm;
+use primitives::{
+    evm_types::{BlockEnv, EvmStorage, Transaction},
+    memory::Memory,
+    stack::Stack,
+};
+
+fn example_evm() -> Evm {
+    let call_data: Vec<u8> = vec![
+        0x60, 0x06, // PUSH1 0x06
+        0x60, 0x07, // PUSH1 0x07
+        0x01, // ADD
+        0x60, 0x00, // PUSH1 0x00
+        0x52, // MSTORE
+        0x60, 0x00, // PUSH1 0x00
+        0x51, // MLOAD
+        0x00, // STOP
+    ];
+
+    let tx = Transaction {
+        from: Address::from_slice(&[1; 20]),
+        to: Address::ZERO,
+        value: U256::ZERO,
+        nonce: U256::ZERO,
+        data: call_data.into(),
+        gas_limit: U256::from(100000),
+        gas_price: U256::ZERO,
+    };
+
+    Evm::new(
+        BlockEnv::default(),
+        tx,
+        Memory::new_with_data(vec![0u8; 1024]),
+        Stack::default(),
+        EvmStorage::default(),
+    )
+}
+
+#[test]
+fn readme_example_runs_to_completion() {
+    let mut evm = example_evm();
+
+    evm.execute();
+    evm.run();
+
+    assert!(evm.status.is_success());
+    assert_eq!(evm.memory.load_word(0), U256::from(13));
+    assert_eq!(evm.stack.as_slice(), &[U256::from(13)]);
+    // MSTORE at offset 0 must not have clobbered the code being executed.
+    assert_eq!(evm.code.as_slice(), evm.tx.data.as_ref());
+}
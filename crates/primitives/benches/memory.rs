@@ -0,0 +1,78 @@
+//! Memory-churn throughput for `Memory::store_word` / `Memory::load_word`, with `load_word` measured against
+//! the bare `U256::from_be_slice` read it started as, which panics past the end of memory instead of zero-filling.
+//!
+//! Run with `cargo bench -p primitives --bench memory`. Uses `std::time::Instant` so no bench framework is needed.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use alloy::primitives::U256;
+use primitives::memory::Memory;
+
+const MEMORY_SIZE: usize = 64 * 1024;
+const ITERATIONS: usize = 2_000_000;
+
+fn bench(name: &str, mut f: impl FnMut(usize)) -> Duration {
+    let start = Instant::now();
+    for i in 0..ITERATIONS {
+        f(i);
+    }
+    let elapsed = start.elapsed();
+    let words_per_sec = ITERATIONS as f64 / elapsed.as_secs_f64();
+    let mib_per_sec = words_per_sec * 32.0 / (1024.0 * 1024.0);
+
+    println!(
+        "{name:<24} {elapsed:>12.2?}  {words_per_sec:>14.0} words/s  {mib_per_sec:>10.1} MiB/s"
+    );
+    elapsed
+}
+
+/// The original `load_word`: convert the memory slice directly, with no bounds handling.
+fn load_word_from_slice(memory: &Memory, offset: usize) -> U256 {
+    U256::from_be_slice(&memory.data[offset..offset + 32])
+}
+
+fn compare(name: &str, baseline: Duration, new: Duration) {
+    println!(
+        "{name:<24} {:>12.2}x the throughput of from_be_slice",
+        baseline.as_secs_f64() / new.as_secs_f64()
+    );
+}
+
+fn main() {
+    let mut memory = Memory::new_with_data(vec![0u8; MEMORY_SIZE]);
+    let word = U256::from(0xdead_beef_u64) << 128;
+    let slots = MEMORY_SIZE - 32;
+
+    bench("store_word aligned", |i| {
+        memory.store_word(black_box((i * 32) % slots), black_box(word));
+    });
+    bench("store_word unaligned", |i| {
+        memory.store_word(black_box((i * 33 + 1) % slots), black_box(word));
+    });
+    let aligned = bench("load_word aligned", |i| {
+        black_box(memory.load_word(black_box((i * 32) % slots)));
+    });
+    let aligned_slice = bench("from_be_slice aligned", |i| {
+        black_box(load_word_from_slice(&memory, black_box((i * 32) % slots)));
+    });
+    let unaligned = bench("load_word unaligned", |i| {
+        black_box(memory.load_word(black_box((i * 33 + 1) % slots)));
+    });
+    let unaligned_slice = bench("from_be_slice unaligned", |i| {
+        black_box(load_word_from_slice(
+            &memory,
+            black_box((i * 33 + 1) % slots),
+        ));
+    });
+    bench("load/store churn", |i| {
+        let offset = (i * 17) % slots;
+        let value = memory.load_word(black_box(offset));
+        memory.store_word(black_box((offset + 5) % slots), value + U256::from(i));
+    });
+
+    compare("load_word aligned", aligned_slice, aligned);
+    compare("load_word unaligned", unaligned_slice, unaligned);
+}
@@ -0,0 +1,88 @@
+//! Allocator traffic and throughput of push-heavy workloads on `Stack`, compared with a growable
+//! `Vec<U256>` stack (the previous backing store).
+//!
+//! Run with `cargo bench -p primitives --bench stack`. A counting global allocator reports how many
+//! allocations each workload performs; `std::time::Instant` times it, so no bench framework is needed.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use alloy::primitives::U256;
+use primitives::stack::{STACK_LIMIT, Stack};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Fresh stacks per iteration, as each call frame gets its own stack.
+const FRAMES: usize = 2_000;
+
+fn bench(name: &str, mut f: impl FnMut(usize)) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for i in 0..FRAMES {
+        f(i);
+    }
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    println!(
+        "{name:<28} {elapsed:>12.2?}  {:>8.2} allocations/frame",
+        allocations as f64 / FRAMES as f64
+    );
+}
+
+fn main() {
+    bench("Vec<U256> fill + drain", |i| {
+        let mut stack: Vec<U256> = Vec::new();
+        for j in 0..STACK_LIMIT {
+            stack.push(black_box(U256::from(i + j)));
+        }
+        while let Some(value) = stack.pop() {
+            black_box(value);
+        }
+    });
+    bench("Stack fill + drain", |i| {
+        let mut stack = Stack::default();
+        for j in 0..STACK_LIMIT {
+            stack.push(black_box(U256::from(i + j))).unwrap();
+        }
+        while let Some(value) = stack.pop() {
+            black_box(value);
+        }
+    });
+
+    let mut stack = Stack::default();
+    bench("Stack reused push/pop churn", |i| {
+        for j in 0..STACK_LIMIT {
+            stack.push(black_box(U256::from(i + j))).unwrap();
+            if j % 3 == 0 {
+                black_box(stack.pop());
+            }
+        }
+        while stack.pop().is_some() {}
+    });
+}
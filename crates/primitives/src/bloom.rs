@@ -0,0 +1,73 @@
+//! Logs bloom helpers (the 2048-bit filter from the yellow paper, `M3:2048`).
+//!
+//! These wrap alloy's `Bloom` so indexer-style consumers can build and query blooms
+//! without going through block execution.
+
+use alloy::primitives::{Address, B256, Bloom, BloomInput, Log};
+
+/// Build the bloom of a set of logs: each log contributes its address and every topic.
+pub fn logs_bloom<'a>(logs: impl IntoIterator<Item = &'a Log>) -> Bloom {
+    let mut bloom = Bloom::ZERO;
+    for log in logs {
+        bloom.accrue_log(log);
+    }
+    bloom
+}
+
+/// Whether `address` may have emitted a log included in `bloom` (false positives are possible, false negatives are not).
+pub fn contains_address(bloom: &Bloom, address: Address) -> bool {
+    bloom.contains_input(BloomInput::Raw(address.as_slice()))
+}
+
+/// Whether `topic` may appear in a log included in `bloom` (false positives are possible, false negatives are not).
+pub fn contains_topic(bloom: &Bloom, topic: B256) -> bool {
+    bloom.contains_input(BloomInput::Raw(topic.as_slice()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{Bytes, LogData};
+
+    fn log(address: Address, topics: Vec<B256>) -> Log {
+        Log {
+            address,
+            data: LogData::new_unchecked(topics, Bytes::new()),
+        }
+    }
+
+    #[test]
+    fn empty_logs_give_empty_bloom() {
+        assert_eq!(logs_bloom([]), Bloom::ZERO);
+    }
+
+    #[test]
+    fn bloom_contains_addresses_and_topics_of_its_logs() {
+        let emitter = Address::left_padding_from(&[0xaa]);
+        let topic = B256::left_padding_from(&[0x01]);
+        let logs = [log(emitter, vec![topic])];
+
+        let bloom = logs_bloom(&logs);
+
+        assert!(contains_address(&bloom, emitter));
+        assert!(contains_topic(&bloom, topic));
+        assert!(!contains_address(
+            &bloom,
+            Address::left_padding_from(&[0xbb])
+        ));
+        assert!(!contains_topic(&bloom, B256::left_padding_from(&[0x02])));
+    }
+
+    #[test]
+    fn bloom_matches_alloy_accrual() {
+        let logs = [
+            log(Address::left_padding_from(&[1]), vec![B256::repeat_byte(1)]),
+            log(
+                Address::left_padding_from(&[2]),
+                vec![B256::repeat_byte(2), B256::repeat_byte(3)],
+            ),
+        ];
+
+        assert_eq!(logs_bloom(&logs), logs.iter().collect::<Bloom>());
+    }
+}
@@ -0,0 +1,147 @@
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::slice_padded;
+
+/// Contract code as stored in `EvmStorage::codes`.
+///
+/// The code can't change once built, so the JUMPDEST analysis is done at most once per code, on the first
+/// `is_jump_dest` call, and shared by every clone made after it. It isn't serialized.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Bytecode {
+    code: Vec<u8>,
+    #[serde(skip)]
+    jump_dests: OnceLock<Vec<u8>>,
+}
+
+impl PartialEq for Bytecode {
+    fn eq(&self, other: &Self) -> bool {
+        self.code == other.code
+    }
+}
+
+impl Eq for Bytecode {}
+
+impl Bytecode {
+    pub const fn new(code: Vec<u8>) -> Self {
+        Bytecode {
+            code,
+            jump_dests: OnceLock::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.code
+    }
+
+    /// Read `len` bytes starting at `offset`, zero-filling past the end of the code.
+    /// Used by CODECOPY and EXTCODECOPY.
+    pub fn slice(&self, offset: usize, len: usize) -> Vec<u8> {
+        slice_padded(&self.code, offset, len)
+    }
+
+    /// Whether `pc` holds a JUMPDEST instruction, i.e. is a valid JUMP / JUMPI target. A `0x5B` byte inside
+    /// PUSH data is not an instruction and doesn't count.
+    pub fn is_jump_dest(&self, pc: usize) -> bool {
+        let bitmap = self.jump_dests.get_or_init(|| jump_dest_bitmap(&self.code));
+        bitmap
+            .get(pc / 8)
+            .is_some_and(|byte| byte & (1 << (pc % 8)) != 0)
+    }
+}
+
+/// One bit per code byte, set where a JUMPDEST instruction starts. PUSH1..PUSH32 immediates are skipped.
+fn jump_dest_bitmap(code: &[u8]) -> Vec<u8> {
+    let mut bitmap = vec![0u8; code.len().div_ceil(8)];
+    let mut pc = 0;
+    while pc < code.len() {
+        match code[pc] {
+            0x5B => bitmap[pc / 8] |= 1 << (pc % 8),
+            op @ 0x60..=0x7F => pc += (op - 0x5F) as usize,
+            _ => {}
+        }
+        pc += 1;
+    }
+    bitmap
+}
+
+impl From<Vec<u8>> for Bytecode {
+    fn from(code: Vec<u8>) -> Self {
+        Bytecode::new(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code() -> Bytecode {
+        Bytecode::new(vec![0x60, 0x01, 0x60, 0x02, 0x01])
+    }
+
+    #[test]
+    fn slice_within_code() {
+        assert_eq!(code().slice(1, 3), vec![0x01, 0x60, 0x02]);
+        assert_eq!(code().slice(0, 5), code().as_slice());
+    }
+
+    #[test]
+    fn slice_with_zero_len_is_empty() {
+        assert!(code().slice(0, 0).is_empty());
+        assert!(code().slice(100, 0).is_empty());
+        assert!(code().slice(usize::MAX, 0).is_empty());
+    }
+
+    #[test]
+    fn slice_crossing_the_end_is_zero_filled() {
+        assert_eq!(code().slice(3, 4), vec![0x02, 0x01, 0x00, 0x00]);
+        assert_eq!(code().slice(4, 1), vec![0x01]);
+    }
+
+    #[test]
+    fn slice_starting_at_or_beyond_the_end_is_all_zeros() {
+        assert_eq!(code().slice(5, 3), vec![0; 3]);
+        assert_eq!(code().slice(1_000, 32), vec![0; 32]);
+        assert_eq!(code().slice(usize::MAX, 4), vec![0; 4]);
+    }
+
+    #[test]
+    fn slice_with_huge_len_does_not_overflow() {
+        let len = 1 << 20;
+        let out = code().slice(2, len);
+
+        assert_eq!(out.len(), len);
+        assert_eq!(&out[..3], &[0x60, 0x02, 0x01]);
+        assert!(out[3..].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn slice_of_empty_code() {
+        assert_eq!(Bytecode::default().slice(0, 2), vec![0, 0]);
+    }
+
+    #[test]
+    fn jump_dests_skip_push_data() {
+        // JUMPDEST; PUSH2 0x5B5B; JUMPDEST; PUSH32 <0x5B at its last byte>; JUMPDEST
+        let mut code = vec![0x5B, 0x61, 0x5B, 0x5B, 0x5B, 0x7F];
+        code.extend([0x5B; 32]);
+        code.push(0x5B);
+        let code = Bytecode::new(code);
+
+        let valid: Vec<usize> = (0..code.len() + 8)
+            .filter(|pc| code.is_jump_dest(*pc))
+            .collect();
+        assert_eq!(valid, vec![0, 4, 38]);
+        assert!(!Bytecode::default().is_jump_dest(0));
+    }
+}
@@ -1,6 +1,72 @@
-#[derive(Debug)]
+use alloy::primitives::{Address, U256};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum EvmErrors {
+    /// A push onto a stack already holding 1024 items.
     StackOverflow,
-    StackUnderflow,
-    StackTooDeep,
+    /// An instruction needed `required` stack items but only `present` were available.
+    StackUnderflow {
+        required: usize,
+        present: usize,
+    },
+    InvalidOpcode,
+    /// JUMP / JUMPI to `target`, which isn't a JUMPDEST instruction in the executing code.
+    InvalidJump {
+        target: U256,
+    },
+    /// Not enough gas left for an instruction's cost.
+    OutOfGas,
+    /// A memory access of `len` bytes at `offset` reached past the highest address memory may grow to.
+    MemoryOutOfBounds {
+        offset: usize,
+        len: usize,
+    },
+    /// A state change (SSTORE, a value transfer, ...) was attempted inside a STATICCALL.
+    StateChangeDuringStaticCall,
+    /// A call would nest more than 1024 frames deep.
+    CallTooDeep,
+    /// A transaction's calldata is `size` bytes, over the configured `limit`.
+    CalldataTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    /// `RETURN` / `REVERT` tried to hand back `size` bytes, over the configured `limit`.
+    ReturnDataTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    /// CREATE / CREATE2 init code is `size` bytes, over the EIP-3860 `limit`.
+    InitcodeTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    /// A creation's init code returned `size` bytes of runtime code, over the code size `limit`.
+    CodeTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    /// A creation's init code returned runtime code starting with `0xEF` (EIP-3541).
+    InvalidCodePrefix,
+    /// A creation transaction's new `address` already has code or a nonce.
+    CreateCollision {
+        address: Address,
+    },
+    /// `address` has a nonce over `u64::MAX`, which a state snapshot can't hold (EIP-2681 caps nonces there).
+    NonceTooLarge {
+        address: Address,
+    },
+    /// `opcode` is on the configured list of denied opcodes.
+    OpcodeNotAllowed {
+        opcode: u8,
+    },
+    /// A precompiled contract was given input it can't process (an invalid curve point, a malformed blake2f or
+    /// point evaluation input, a failed KZG proof, ...).
+    PrecompileFailed,
+    /// RETURNDATACOPY asked for `len` bytes at `offset` of a return-data buffer only `size` bytes long (EIP-211).
+    ReturnDataOutOfBounds {
+        offset: usize,
+        len: usize,
+        size: usize,
+    },
 }
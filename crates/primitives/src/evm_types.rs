@@ -1,18 +1,48 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use alloy::primitives::{Address, U256};
+use alloy::primitives::{Address, B256, Bytes, KECCAK256_EMPTY, U256};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Default)]
+use crate::{
+    bytecode::Bytecode,
+    utils::{load_word_padded, slice_padded},
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Transaction {
     pub from: Address,
     pub to: Address,
     pub value: U256,
     pub nonce: U256,
-    pub data: Vec<u8>,
+    pub data: Bytes,
     pub gas_limit: U256,
+    /// Price per unit of gas, as read by GASPRICE.
+    #[serde(default)]
+    pub gas_price: U256,
+}
+
+impl Transaction {
+    /// The 32-byte calldata word at `offset`, zero-filled past the end (CALLDATALOAD).
+    pub fn load_word(&self, offset: usize) -> U256 {
+        load_word_padded(&self.data, offset)
+    }
+
+    /// `len` bytes of calldata from `offset`, zero-filled past the end (CALLDATACOPY).
+    pub fn slice_padded(&self, offset: usize, len: usize) -> Vec<u8> {
+        slice_padded(&self.data, offset, len)
+    }
+
+    /// Calldata for a sub-call: a cheap reference-counted view when the range lies inside the calldata,
+    /// a zero-filled copy otherwise. Large calldata is never copied when frames pass it down unchanged.
+    pub fn calldata_slice(&self, offset: usize, len: usize) -> Bytes {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.data.len() => self.data.slice(offset..end),
+            _ => Bytes::from(self.slice_padded(offset, len)),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct BlockEnv {
     pub number: U256,
     pub timestamp: U256,
@@ -20,19 +50,90 @@ pub struct BlockEnv {
     pub difficulty: U256,
     pub gas_limit: U256,
     pub base_fee: U256,
+    /// Hash of the parent block (`number - 1`), the one block hash BLOCKHASH can return.
     pub block_hash: U256,
     pub chain_id: U256,
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct EvmAccount {
+/// Account header: everything about an account except its storage slots and the code bytes themselves.
+/// `code_hash` points into `EvmStorage::codes`; accounts without code carry `KECCAK256_EMPTY`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountInfo {
     pub balance: U256,
     pub nonce: U256,
-    pub code: Vec<u8>,
-    pub word: HashMap<U256, U256>,
+    pub code_hash: B256,
+}
+
+impl Default for AccountInfo {
+    fn default() -> Self {
+        AccountInfo {
+            balance: U256::ZERO,
+            nonce: U256::ZERO,
+            code_hash: KECCAK256_EMPTY,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EvmAccount {
+    pub info: AccountInfo,
+    pub storage: HashMap<U256, U256>,
 }
 
-#[derive(Debug, Clone, Default)]
+/// World state.
+/// - `data`: accounts keyed by address. Hash maps have no stable order; anything shown to users
+///   (`iter_accounts`, `iter_account`, `diff`, `dump_state`) is sorted by address and slot.
+/// - `codes`: contract code keyed by its keccak256 hash, so identical code shared by many accounts
+///   (e.g. thousands of minimal proxies) is stored once.
+/// - `touched`: accounts accessed since the last `clear_touched()`; everything else is a pruning candidate.
+/// - `code_overrides`: host-installed code per address (`etch`), shadowing the account's own code without
+///   changing its `code_hash`, so overrides never show up in diffs or dumps.
+/// - `original_values`: value each slot written since the last `clear_original_values()` had before its first
+///   write — EIP-2200's "original value", which SSTORE gas and refunds are based on.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct EvmStorage {
     pub data: HashMap<Address, EvmAccount>,
+    pub codes: HashMap<B256, Bytecode>,
+    pub touched: HashSet<Address>,
+    pub code_overrides: HashMap<Address, Bytecode>,
+    pub original_values: HashMap<Address, HashMap<U256, U256>>,
+}
+
+/// In-memory size of an `EvmStorage`, as reported by `EvmStorage::size()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StateSize {
+    pub accounts: usize,
+    pub slots: usize,
+    pub code_bytes: usize,
+}
+
+/// One page of an account's storage, as returned by `EvmStorage::storage_range_at`.
+/// - `slots`: `(key, value)` pairs in ascending key order.
+/// - `next_key`: first key of the following page, or `None` when the range reached the end of storage.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageRange {
+    pub slots: Vec<(U256, U256)>,
+    pub next_key: Option<U256>,
+}
+
+/// Changes to a single account between two states, as `(before, after)` pairs.
+/// - `info`: set when balance, nonce or code hash changed.
+/// - `storage`: every slot whose value changed; absent slots read as zero.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountDiff {
+    pub info: Option<(AccountInfo, AccountInfo)>,
+    pub storage: BTreeMap<U256, (U256, U256)>,
+}
+
+/// Difference between two `EvmStorage`s (see `EvmStorage::diff`), keyed by address in ascending order.
+/// Accounts that did not change are omitted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateDiff {
+    pub accounts: BTreeMap<Address, AccountDiff>,
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
 }
@@ -0,0 +1,308 @@
+//! `EvmStorage` with an undo log, so a failed sub-call or a `REVERT` rolls back exactly what it changed.
+//!
+//! Every balance change, nonce change, storage write, code change and account creation made through
+//! `JournaledState`'s own methods pushes a `JournalEntry` holding the previous value. `checkpoint()` marks the
+//! current end of the journal, `revert_to()` undoes everything recorded after a mark, and `commit()` makes all
+//! recorded changes final and empties the journal (at the end of a transaction).
+//!
+//! It also holds transient storage (EIP-1153): per-transaction slots that `t_store` journals like any other write
+//! and `commit()` wipes.
+//!
+//! Reads go straight to the wrapped `EvmStorage` through `Deref`. `DerefMut` is there for setting up state
+//! (seeding accounts, `etch`, `touch`): changes made through it are not journaled and survive `revert_to`.
+//! Neither the `touched` set nor `original_values` is journaled; both describe the transaction, not the state.
+
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+};
+
+use alloy::primitives::{Address, B256, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::evm_types::{EvmAccount, EvmStorage};
+
+/// One undoable change, holding what was there before it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum JournalEntry {
+    /// The account didn't exist; undone by removing it.
+    AccountCreated {
+        address: Address,
+    },
+    BalanceChanged {
+        address: Address,
+        previous: U256,
+    },
+    NonceChanged {
+        address: Address,
+        previous: U256,
+    },
+    /// `previous` is `None` when the slot had never been written.
+    StorageChanged {
+        address: Address,
+        key: U256,
+        previous: Option<U256>,
+    },
+    CodeChanged {
+        address: Address,
+        previous: B256,
+    },
+    TransientChanged {
+        address: Address,
+        key: U256,
+        previous: U256,
+    },
+}
+
+/// A position in the journal, returned by `JournaledState::checkpoint`.
+/// The default checkpoint is the start of the journal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct JournalCheckpoint(usize);
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JournaledState {
+    state: EvmStorage,
+    journal: Vec<JournalEntry>,
+    transient: HashMap<Address, HashMap<U256, U256>>,
+}
+
+impl From<EvmStorage> for JournaledState {
+    fn from(state: EvmStorage) -> Self {
+        JournaledState::new(state)
+    }
+}
+
+impl Deref for JournaledState {
+    type Target = EvmStorage;
+
+    fn deref(&self) -> &EvmStorage {
+        &self.state
+    }
+}
+
+impl DerefMut for JournaledState {
+    fn deref_mut(&mut self) -> &mut EvmStorage {
+        &mut self.state
+    }
+}
+
+impl JournaledState {
+    /// Wrap `state` with an empty journal.
+    pub fn new(state: EvmStorage) -> Self {
+        JournaledState::from_parts(state, Vec::new(), HashMap::new())
+    }
+
+    /// Rebuild a state whose journal and transient storage were saved with `journal()` and `transient()`, e.g.
+    /// from a serialized interpreter checkpoint.
+    pub fn from_parts(
+        state: EvmStorage,
+        journal: Vec<JournalEntry>,
+        transient: HashMap<Address, HashMap<U256, U256>>,
+    ) -> Self {
+        JournaledState {
+            state,
+            journal,
+            transient,
+        }
+    }
+
+    pub fn into_inner(self) -> EvmStorage {
+        self.state
+    }
+
+    /// Changes recorded since the last `commit()`, oldest first.
+    pub fn journal(&self) -> &[JournalEntry] {
+        &self.journal
+    }
+
+    /// Transient storage written in the current transaction, by address and slot.
+    pub fn transient(&self) -> &HashMap<Address, HashMap<U256, U256>> {
+        &self.transient
+    }
+
+    /// Mark the current end of the journal.
+    pub fn checkpoint(&self) -> JournalCheckpoint {
+        JournalCheckpoint(self.journal.len())
+    }
+
+    /// Undo every change recorded after `checkpoint`, newest first. A checkpoint taken before the last `commit()`
+    /// undoes nothing.
+    pub fn revert_to(&mut self, checkpoint: JournalCheckpoint) {
+        while self.journal.len() > checkpoint.0 {
+            let Some(entry) = self.journal.pop() else {
+                break;
+            };
+            match entry {
+                JournalEntry::AccountCreated { address } => {
+                    self.state.data.remove(&address);
+                }
+                JournalEntry::BalanceChanged { address, previous } => {
+                    self.account(address).info.balance = previous;
+                }
+                JournalEntry::NonceChanged { address, previous } => {
+                    self.account(address).info.nonce = previous;
+                }
+                JournalEntry::StorageChanged {
+                    address,
+                    key,
+                    previous,
+                } => {
+                    let storage = &mut self.account(address).storage;
+                    match previous {
+                        Some(value) => storage.insert(key, value),
+                        None => storage.remove(&key),
+                    };
+                }
+                JournalEntry::CodeChanged { address, previous } => {
+                    self.account(address).info.code_hash = previous;
+                }
+                JournalEntry::TransientChanged {
+                    address,
+                    key,
+                    previous,
+                } => {
+                    self.transient
+                        .entry(address)
+                        .or_default()
+                        .insert(key, previous);
+                }
+            }
+        }
+    }
+
+    /// Make every recorded change final, empty the journal and discard transient storage. Outstanding
+    /// checkpoints become no-ops.
+    pub fn commit(&mut self) {
+        self.journal.clear();
+        self.transient.clear();
+    }
+
+    pub fn set_balance(&mut self, address: Address, balance: U256) {
+        let previous = self.journaled_account(address).info.balance;
+        self.journal
+            .push(JournalEntry::BalanceChanged { address, previous });
+        self.account(address).info.balance = balance;
+    }
+
+    /// Move `value` wei from `from` to `to`. The caller checks that `from` can afford it.
+    pub fn transfer(&mut self, from: Address, to: Address, value: U256) {
+        let from_balance = self.journaled_account(from).info.balance;
+        self.set_balance(from, from_balance - value);
+        let to_balance = self.journaled_account(to).info.balance;
+        self.set_balance(to, to_balance + value);
+    }
+
+    pub fn set_nonce(&mut self, address: Address, nonce: U256) {
+        let previous = self.journaled_account(address).info.nonce;
+        self.journal
+            .push(JournalEntry::NonceChanged { address, previous });
+        self.account(address).info.nonce = nonce;
+    }
+
+    pub fn bump_nonce(&mut self, address: Address) {
+        let nonce = self.journaled_account(address).info.nonce;
+        self.set_nonce(address, nonce + U256::ONE);
+    }
+
+    /// `EvmStorage::s_store`, journaled.
+    pub fn s_store(&mut self, address: Address, key: U256, value: U256) {
+        let previous = self.journaled_account(address).storage.get(&key).copied();
+        self.journal.push(JournalEntry::StorageChanged {
+            address,
+            key,
+            previous,
+        });
+        self.state.s_store(address, key, value);
+    }
+
+    /// `EvmStorage::set_code`, journaled. The code bytes stay in the code store after a revert.
+    pub fn set_code(&mut self, address: Address, code: Vec<u8>) {
+        let previous = self.journaled_account(address).info.code_hash;
+        self.journal
+            .push(JournalEntry::CodeChanged { address, previous });
+        self.state.set_code(address, code);
+    }
+
+    /// Transient slot `key` of `address`; zero if it wasn't written in this transaction.
+    pub fn t_load(&self, address: Address, key: U256) -> U256 {
+        self.transient
+            .get(&address)
+            .and_then(|slots| slots.get(&key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Write transient slot `key` of `address`, journaled.
+    pub fn t_store(&mut self, address: Address, key: U256, value: U256) {
+        let previous = self.t_load(address, key);
+        self.journal.push(JournalEntry::TransientChanged {
+            address,
+            key,
+            previous,
+        });
+        self.transient
+            .entry(address)
+            .or_default()
+            .insert(key, value);
+    }
+
+    /// The account at `address`, created (and journaled as created) if missing.
+    fn journaled_account(&mut self, address: Address) -> &mut EvmAccount {
+        if !self.state.data.contains_key(&address) {
+            self.journal.push(JournalEntry::AccountCreated { address });
+        }
+        self.account(address)
+    }
+
+    fn account(&mut self, address: Address) -> &mut EvmAccount {
+        self.state.data.entry(address).or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revert_to_undoes_changes_after_the_checkpoint() {
+        let (alice, bob) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let mut state = JournaledState::default();
+        state.set_balance(alice, U256::from(100));
+        state.s_store(alice, U256::ONE, U256::from(7));
+        let before = state.clone().into_inner();
+
+        let checkpoint = state.checkpoint();
+        state.transfer(alice, bob, U256::from(40));
+        state.bump_nonce(alice);
+        state.s_store(alice, U256::ONE, U256::from(8));
+        state.s_store(alice, U256::from(2), U256::from(9));
+        state.set_code(alice, vec![0x00]);
+        assert_eq!(state.data[&bob].info.balance, U256::from(40));
+
+        state.revert_to(checkpoint);
+        assert!(before.diff(&state).is_empty());
+        assert!(!state.data.contains_key(&bob));
+        assert!(!state.data[&alice].storage.contains_key(&U256::from(2)));
+        assert_eq!(state.journal().len(), 3);
+    }
+
+    #[test]
+    fn nested_checkpoints_and_commit() {
+        let alice = Address::repeat_byte(1);
+        let mut state = JournaledState::default();
+
+        let outer = state.checkpoint();
+        state.set_nonce(alice, U256::from(1));
+        let inner = state.checkpoint();
+        state.set_nonce(alice, U256::from(2));
+        state.revert_to(inner);
+        assert_eq!(state.data[&alice].info.nonce, U256::from(1));
+
+        state.t_store(alice, U256::ONE, U256::from(3));
+        state.commit();
+        state.revert_to(outer);
+        assert_eq!(state.data[&alice].info.nonce, U256::from(1));
+        assert!(state.journal().is_empty());
+        assert_eq!(state.t_load(alice, U256::ONE), U256::ZERO);
+    }
+}
@@ -1,21 +1,13 @@
+pub mod bloom;
+pub mod bytecode;
 pub mod constants;
 pub mod errors;
 pub mod evm_types;
+pub mod journal;
 pub mod memory;
+pub mod rpc_snapshot;
+pub mod slots;
 pub mod stack;
+pub mod state_dump;
 pub mod storage;
-
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub mod utils;
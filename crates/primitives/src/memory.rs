@@ -1,8 +1,7 @@
-//!
-
 use alloy::primitives::U256;
+use serde::{Deserialize, Serialize};
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Memory {
     // Fields
     pub data: Vec<u8>,
@@ -17,27 +16,130 @@ impl Memory {
         Memory { data }
     }
 
+    /// Size of memory in bytes, as reported by `MSIZE`. Always a multiple of 32 once memory has grown.
+    pub fn msize(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Grow memory, zero-filled, so that `offset..offset + len` is addressable. The new size is rounded up to
+    /// a whole number of 32-byte words; memory never shrinks and a zero-length access never grows it.
+    ///
+    /// Returns the memory size in words after the call. Callers charge for the growth beforehand through
+    /// `Gas::record_memory_expansion(offset + len)`, which uses the same word rounding.
+    pub fn resize_to_fit(&mut self, offset: usize, len: usize) -> usize {
+        if len != 0 {
+            let end = offset
+                .checked_add(len)
+                .and_then(|end| end.checked_next_multiple_of(32))
+                .expect("memory access overflows usize");
+            if end > self.data.len() {
+                self.data.resize(end, 0);
+            }
+        }
+        self.data.len().div_ceil(32)
+    }
+
+    /// Write `word` big-endian at `offset..offset + 32`, growing memory first if needed. Any offset works;
+    /// there is no alignment requirement.
     pub fn store_word(&mut self, offset: usize, word: U256) {
+        self.resize_to_fit(offset, 32);
         let word_to_bytes: [u8; 32] = word.to_be_bytes::<32>();
         self.data[offset..offset + 32].copy_from_slice(&word_to_bytes);
     }
 
+    /// Read the big-endian word at `offset..offset + 32`.
+    /// Bytes past the end of memory read as zero; reading does not grow memory (`MLOAD` grows it explicitly).
     pub fn load_word(&self, offset: usize) -> U256 {
-        let bytes = &self.data[offset..offset + 32];
+        if let Some(word) = self.data.get(offset..offset.saturating_add(32)) {
+            return U256::from_be_slice(word);
+        }
+        let mut bytes = [0u8; 32];
+        if offset < self.data.len() {
+            let available = &self.data[offset..];
+            bytes[..available.len()].copy_from_slice(available);
+        }
 
-        U256::from_be_slice(bytes.try_into().unwrap())
+        U256::from_be_bytes(bytes)
     }
 
+    /// Write a single byte at `offset`, growing memory first if needed.
     pub fn store_byte(&mut self, offset: usize, byte: u8) {
+        self.resize_to_fit(offset, 1);
         self.data[offset] = byte;
     }
 
+    /// Read the byte at `offset`; zero past the end of memory.
     pub fn load_byte(&self, offset: usize) -> u8 {
-        self.data[offset]
+        self.data.get(offset).copied().unwrap_or(0)
     }
 
-    pub fn copy(&mut self, offset: usize, dest: usize, length: usize) -> u8 {
-        let data = &self.data[offset..offset + length];
-        0
+    /// Write `len` bytes of `source` starting at `offset` to `dest`, zero-filling whatever lies past the end of
+    /// `source` (CALLDATACOPY, CODECOPY, EXTCODECOPY), growing memory first if needed.
+    pub fn store_padded(&mut self, dest: usize, source: &[u8], offset: usize, len: usize) {
+        if len == 0 {
+            return;
+        }
+        self.resize_to_fit(dest, len);
+        let target = &mut self.data[dest..dest + len];
+        let available = source.len().saturating_sub(offset).min(len);
+        if available > 0 {
+            target[..available].copy_from_slice(&source[offset..offset + available]);
+        }
+        target[available..].fill(0);
+    }
+
+    /// Copy `length` bytes from `offset` to `dest` (ranges may overlap), growing memory to cover both.
+    pub fn copy(&mut self, offset: usize, dest: usize, length: usize) {
+        self.resize_to_fit(offset, length);
+        self.resize_to_fit(dest, length);
+        self.data.copy_within(offset..offset + length, dest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_padded_zero_fills_past_the_source() {
+        let mut memory = Memory::new_with_data(vec![0xff; 8]);
+
+        memory.store_padded(2, &[1, 2, 3], 1, 4);
+        assert_eq!(memory.data[..8], [0xff, 0xff, 2, 3, 0, 0, 0xff, 0xff]);
+
+        memory.store_padded(0, &[1, 2, 3], usize::MAX, 2);
+        assert_eq!(memory.data[..2], [0, 0]);
+    }
+
+    #[test]
+    fn word_round_trips_at_unaligned_offsets() {
+        let mut memory = Memory::new_with_data(vec![0u8; 96]);
+        let word = U256::from_be_bytes(core::array::from_fn::<u8, 32, _>(|i| i as u8 + 1));
+
+        for offset in [0, 1, 7, 31, 33, 64] {
+            memory.store_word(offset, word);
+            assert_eq!(memory.load_word(offset), word, "offset {offset}");
+            assert_eq!(memory.load_byte(offset), 1);
+            assert_eq!(memory.load_byte(offset + 31), 32);
+        }
+    }
+
+    #[test]
+    fn grows_word_aligned_on_demand() {
+        let mut memory = Memory::new();
+        assert_eq!(memory.msize(), 0);
+        assert_eq!(memory.load_word(4096), U256::ZERO);
+
+        memory.store_word(4096, U256::from(7));
+        assert_eq!(memory.msize(), 4096 + 32);
+        assert_eq!(memory.load_word(4096), U256::from(7));
+
+        // Unaligned access rounds the new size up to the next word.
+        memory.store_byte(5000, 1);
+        assert_eq!(memory.msize(), 5024);
+
+        assert_eq!(memory.resize_to_fit(10_000, 0), 5024 / 32);
+        assert_eq!(memory.resize_to_fit(0, 64), 5024 / 32);
+        assert_eq!(memory.msize(), 5024);
     }
 }
@@ -0,0 +1,236 @@
+//! Offline seeding of `EvmStorage` from recorded JSON-RPC responses.
+//!
+//! A crawler (or a fork cache) that fetched accounts over RPC can save every call it made as
+//! `{"method", "params", "result"}` records; importing them rebuilds the touched part of the chain state
+//! without a live node, so transactions can be replayed air-gapped. Recognised methods:
+//!
+//! - `eth_getBalance [address, block]` -> balance
+//! - `eth_getTransactionCount [address, block]` -> nonce
+//! - `eth_getCode [address, block]` -> code
+//! - `eth_getStorageAt [address, slot, block]` -> slot value
+//!
+//! Records of any other method (`eth_chainId`, `eth_blockNumber`, ...) are counted as skipped. The block tag
+//! is ignored: a snapshot is expected to come from a single block.
+//!
+//! The file is either a JSON array of records or one record per line (JSON lines), optionally gzip-compressed.
+
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use alloy::primitives::{Address, Bytes, U256};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use crate::evm_types::EvmStorage;
+
+/// One recorded RPC call and its result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcRecord {
+    pub method: String,
+    #[serde(default)]
+    pub params: Vec<Value>,
+    pub result: Value,
+}
+
+/// What `import_rpc_records` applied.
+/// - `accounts`: distinct addresses seeded.
+/// - `skipped`: records of methods that carry no account state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub accounts: usize,
+    pub balances: usize,
+    pub nonces: usize,
+    pub codes: usize,
+    pub slots: usize,
+    pub skipped: usize,
+}
+
+impl EvmStorage {
+    /// Read a snapshot file (see the module docs) and apply it on top of the current state.
+    pub fn import_rpc_snapshot(&mut self, path: impl AsRef<Path>) -> io::Result<ImportSummary> {
+        let raw = fs::read(path)?;
+        let text = if raw.starts_with(&[0x1f, 0x8b]) {
+            let mut text = String::new();
+            GzDecoder::new(raw.as_slice()).read_to_string(&mut text)?;
+            text
+        } else {
+            String::from_utf8(raw).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?
+        };
+
+        let records: Vec<RpcRecord> = if text.trim_start().starts_with('[') {
+            serde_json::from_str(&text)?
+        } else {
+            text.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<_, _>>()?
+        };
+        self.import_rpc_records(records)
+    }
+
+    /// Apply recorded RPC results to this state. Later records for the same field win.
+    ///
+    /// Returns `Err(io::ErrorKind::InvalidData)` for a recognised method whose params or result don't decode;
+    /// records before it have already been applied.
+    pub fn import_rpc_records(
+        &mut self,
+        records: impl IntoIterator<Item = RpcRecord>,
+    ) -> io::Result<ImportSummary> {
+        let mut summary = ImportSummary::default();
+        let mut seeded = HashSet::new();
+
+        for record in records {
+            let address: Address = match record.method.as_str() {
+                "eth_getBalance"
+                | "eth_getTransactionCount"
+                | "eth_getCode"
+                | "eth_getStorageAt" => param(&record, 0)?,
+                _ => {
+                    summary.skipped += 1;
+                    continue;
+                }
+            };
+            seeded.insert(address);
+            self.touched.insert(address);
+
+            match record.method.as_str() {
+                "eth_getBalance" => {
+                    self.data.entry(address).or_default().info.balance = result(&record)?;
+                    summary.balances += 1;
+                }
+                "eth_getTransactionCount" => {
+                    self.data.entry(address).or_default().info.nonce = result(&record)?;
+                    summary.nonces += 1;
+                }
+                "eth_getCode" => {
+                    let code: Bytes = result(&record)?;
+                    self.set_code(address, code.to_vec());
+                    summary.codes += 1;
+                }
+                _ => {
+                    let slot: U256 = param(&record, 1)?;
+                    let value: U256 = result(&record)?;
+                    self.data
+                        .entry(address)
+                        .or_default()
+                        .storage
+                        .insert(slot, value);
+                    summary.slots += 1;
+                }
+            }
+        }
+
+        summary.accounts = seeded.len();
+        Ok(summary)
+    }
+}
+
+fn param<T: DeserializeOwned>(record: &RpcRecord, index: usize) -> io::Result<T> {
+    let value = record.params.get(index).cloned().unwrap_or(Value::Null);
+    decode(record, value, "params")
+}
+
+fn result<T: DeserializeOwned>(record: &RpcRecord) -> io::Result<T> {
+    decode(record, record.result.clone(), "result")
+}
+
+fn decode<T: DeserializeOwned>(record: &RpcRecord, value: Value, what: &str) -> io::Result<T> {
+    serde_json::from_value(value).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: bad {what}: {err}", record.method),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn record(method: &str, params: Value, result: Value) -> RpcRecord {
+        serde_json::from_value(json!({ "method": method, "params": params, "result": result }))
+            .unwrap()
+    }
+
+    #[test]
+    fn seeds_accounts_from_rpc_results() {
+        let contract = "0x00000000000000000000000000000000000000c0";
+        let records = vec![
+            record("eth_chainId", json!([]), json!("0x1")),
+            record(
+                "eth_getBalance",
+                json!([contract, "latest"]),
+                json!("0x3e8"),
+            ),
+            record(
+                "eth_getTransactionCount",
+                json!([contract, "latest"]),
+                json!("0x1"),
+            ),
+            record(
+                "eth_getCode",
+                json!([contract, "latest"]),
+                json!("0x600100"),
+            ),
+            record(
+                "eth_getStorageAt",
+                json!([contract, "0x2", "latest"]),
+                json!("0x000000000000000000000000000000000000000000000000000000000000002a"),
+            ),
+        ];
+
+        let mut storage = EvmStorage::default();
+        let summary = storage.import_rpc_records(records).unwrap();
+
+        let address = Address::left_padding_from(&[0xc0]);
+        assert_eq!(
+            summary,
+            ImportSummary {
+                accounts: 1,
+                balances: 1,
+                nonces: 1,
+                codes: 1,
+                slots: 1,
+                skipped: 1,
+            }
+        );
+        assert_eq!(storage.data[&address].info.balance, U256::from(1_000));
+        assert_eq!(storage.data[&address].info.nonce, U256::ONE);
+        assert_eq!(storage.code(address).as_slice(), &[0x60, 0x01, 0x00]);
+        assert_eq!(
+            storage.data[&address].storage[&U256::from(2)],
+            U256::from(42)
+        );
+    }
+
+    #[test]
+    fn reads_json_lines_and_rejects_bad_params() {
+        let path =
+            std::env::temp_dir().join(format!("eth_vm_rpc_snapshot_{}.jsonl", std::process::id()));
+        fs::write(
+            &path,
+            concat!(
+                r#"{"method":"eth_getBalance","params":["0x00000000000000000000000000000000000000c0","latest"],"result":"0x1"}"#,
+                "\n\n",
+                r#"{"method":"eth_getBalance","params":["not an address"],"result":"0x1"}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+
+        let mut storage = EvmStorage::default();
+        let err = storage.import_rpc_snapshot(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("eth_getBalance: bad params"));
+        assert_eq!(storage.data.len(), 1);
+    }
+}
@@ -0,0 +1,69 @@
+//! Solidity storage layout: where mapping entries and dynamic array elements live.
+//!
+//! A state variable declared at slot `p` keeps its data at:
+//! - `mapping(K => V)`: `keccak256(pad32(key) ++ p)` for value-type keys (integers, `address`, `bytes32`, ...),
+//!   `keccak256(key ++ p)` for `string` and `bytes` keys;
+//! - `T[]`: length at `p`, element `i` at `keccak256(p) + i` (times the element's slot count for multi-slot `T`);
+//! - nested mappings: apply the mapping rule once per key, outermost first.
+//!
+//! Value-type keys are passed as stack words; turn an address into one with `utils::address_to_word`.
+
+use alloy::primitives::{U256, keccak256};
+
+/// Slot of `mapping[key]` for a mapping declared at `base_slot` with a value-type key.
+pub fn mapping_slot(base_slot: U256, key: U256) -> U256 {
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(&key.to_be_bytes::<32>());
+    preimage[32..].copy_from_slice(&base_slot.to_be_bytes::<32>());
+    U256::from_be_bytes(keccak256(preimage).0)
+}
+
+/// Slot of `mapping[key]` for a mapping declared at `base_slot` with a `string` or `bytes` key.
+pub fn bytes_mapping_slot(base_slot: U256, key: &[u8]) -> U256 {
+    let preimage = [key, &base_slot.to_be_bytes::<32>()].concat();
+    U256::from_be_bytes(keccak256(preimage).0)
+}
+
+/// Slot of `mapping[keys[0]][keys[1]]...` for nested mappings declared at `base_slot`. No keys gives `base_slot`.
+pub fn nested_mapping_slot(base_slot: U256, keys: &[U256]) -> U256 {
+    keys.iter()
+        .fold(base_slot, |slot, key| mapping_slot(slot, *key))
+}
+
+/// Slot of element `index` of a dynamic array declared at `base_slot`, for elements that take one slot each.
+/// Wraps around the slot space like the compiler's own arithmetic.
+pub fn array_slot(base_slot: U256, index: U256) -> U256 {
+    U256::from_be_bytes(keccak256(base_slot.to_be_bytes::<32>()).0).wrapping_add(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, uint};
+
+    use super::*;
+    use crate::utils::address_to_word;
+
+    #[test]
+    fn matches_solidity_layout() {
+        // keccak256(0x00 * 64) and keccak256(0x00 * 32), the first mapping entry / array element at slot 0.
+        assert_eq!(
+            mapping_slot(U256::ZERO, U256::ZERO),
+            uint!(0xad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5_U256)
+        );
+        assert_eq!(
+            array_slot(U256::ZERO, U256::from(2)),
+            uint!(0x290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e565_U256)
+        );
+
+        let owner = address_to_word(Address::repeat_byte(0xaa));
+        assert_eq!(
+            bytes_mapping_slot(U256::from(3), &owner.to_be_bytes::<32>()),
+            mapping_slot(U256::from(3), owner)
+        );
+        assert_eq!(
+            nested_mapping_slot(U256::from(1), &[owner, U256::from(7)]),
+            mapping_slot(mapping_slot(U256::from(1), owner), U256::from(7))
+        );
+        assert_eq!(nested_mapping_slot(U256::from(1), &[]), U256::from(1));
+    }
+}
@@ -1,35 +1,152 @@
+use std::fmt;
+
 use crate::errors::EvmErrors;
 use alloy::primitives::U256;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use smallvec::SmallVec;
+
+/// Maximum number of items on the EVM stack.
+pub const STACK_LIMIT: usize = 1024;
 
-#[derive(Debug, Clone, Default)]
+/// The operand stack, backed by a fixed 1024-slot boxed array plus a length index.
+/// The slots are allocated once when the stack is created; push and pop never touch the allocator.
+#[derive(Clone)]
 pub struct Stack {
-    pub data: Vec<U256>,
+    data: Box<[U256; STACK_LIMIT]>,
+    len: usize,
+}
+
+impl Default for Stack {
+    fn default() -> Self {
+        let data: Box<[U256; STACK_LIMIT]> = vec![U256::ZERO; STACK_LIMIT]
+            .into_boxed_slice()
+            .try_into()
+            .expect("slice has STACK_LIMIT items");
+        Stack { data, len: 0 }
+    }
+}
+
+impl fmt::Debug for Stack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+/// Stacks are equal when they hold the same items; unused slots don't matter.
+impl PartialEq for Stack {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+/// Serialized as the list of items, bottom first.
+impl Serialize for Stack {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_slice().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Stack {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let items = Vec::<U256>::deserialize(deserializer)?;
+        let mut stack = Stack::default();
+        for item in items {
+            stack
+                .push(item)
+                .map_err(|_| serde::de::Error::custom("more than STACK_LIMIT stack items"))?;
+        }
+        Ok(stack)
+    }
 }
 
 impl Stack {
     /// Push a value onto the stack.
-    /// Returns `Err(EvmErrors::StackTooDeep)` if the stack would exceed 1024 items.
+    /// Returns `Err(EvmErrors::StackOverflow)` if the stack would exceed 1024 items.
     pub fn push(&mut self, value: U256) -> Result<(), EvmErrors> {
-        if self.data.len() >= 1024 {
-            return Err(EvmErrors::StackTooDeep);
+        if self.len >= STACK_LIMIT {
+            return Err(EvmErrors::StackOverflow);
         }
-        self.data.push(value);
+        self.data[self.len] = value;
+        self.len += 1;
         Ok(())
     }
 
     /// Pop a value from the stack. Returns `None` if the stack is empty.
     pub fn pop(&mut self) -> Option<U256> {
-        self.data.pop()
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(self.data[self.len])
+    }
+
+    /// Pop a value, reporting an empty stack as `StackUnderflow` instead of `None`.
+    pub fn try_pop(&mut self) -> Result<U256, EvmErrors> {
+        self.pop().ok_or(EvmErrors::StackUnderflow {
+            required: 1,
+            present: 0,
+        })
+    }
+
+    /// Check that at least `n` items are present.
+    /// Returns `Err(EvmErrors::StackUnderflow { required: n, present })` otherwise.
+    pub fn require(&self, n: usize) -> Result<(), EvmErrors> {
+        if self.len < n {
+            return Err(EvmErrors::StackUnderflow {
+                required: n,
+                present: self.len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Pop `n` items at once, top of stack first.
+    /// Validates with `require(n)` before popping, so on error the stack is left untouched.
+    /// Up to 8 items are returned without allocating.
+    pub fn pop_n(&mut self, n: usize) -> Result<SmallVec<[U256; 8]>, EvmErrors> {
+        self.require(n)?;
+        let popped = self.data[self.len - n..self.len]
+            .iter()
+            .rev()
+            .copied()
+            .collect();
+        self.len -= n;
+        Ok(popped)
+    }
+
+    /// Push a copy of the `n`-th item from the top (`n = 1` is the top), as DUPn does.
+    /// Returns `StackUnderflow` if fewer than `n` items are present and `StackOverflow` if the stack is full.
+    pub fn dup(&mut self, n: usize) -> Result<(), EvmErrors> {
+        self.require(n)?;
+        self.push(self.data[self.len - n])
+    }
+
+    /// Exchange the top item with the one `n` below it (`n = 1` swaps the top two), as SWAPn does.
+    /// Returns `StackUnderflow` if fewer than `n + 1` items are present.
+    pub fn swap(&mut self, n: usize) -> Result<(), EvmErrors> {
+        self.require(n + 1)?;
+        self.data.swap(self.len - 1, self.len - 1 - n);
+        Ok(())
+    }
+
+    /// The live items, bottom of stack first.
+    pub fn as_slice(&self) -> &[U256] {
+        &self.data[..self.len]
+    }
+
+    /// Mutable view of the live items, bottom of stack first.
+    pub fn as_mut_slice(&mut self) -> &mut [U256] {
+        &mut self.data[..self.len]
     }
 
     /// Return current stack size. This is useful for testing and diagnostics.
     pub fn len(&self) -> usize {
-        self.data.len()
+        self.len
     }
 
     /// Return whether the stack is empty.
     pub fn is_empty(&self) -> bool {
-        self.data.is_empty()
+        self.len == 0
     }
 }
 
@@ -101,8 +218,66 @@ impl Stack {
 //         // Stack is full; length should be 1024
 //         assert_eq!(stack.len(), 1024);
 
-//         // Pushing one more item should return the `StackTooDeep` error
+//         // Pushing one more item should return the `StackOverflow` error
 //         let result = stack.push(make_u(0xff));
-//         assert!(matches!(result, Err(EvmErrors::StackTooDeep)));
+//         assert!(matches!(result, Err(EvmErrors::StackOverflow)));
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pop_n_returns_top_first_and_reports_shortfall() {
+        let mut stack = Stack::default();
+        for i in 1..=3u8 {
+            stack.push(U256::from(i)).unwrap();
+        }
+
+        assert_eq!(
+            stack.pop_n(4),
+            Err(EvmErrors::StackUnderflow {
+                required: 4,
+                present: 3
+            })
+        );
+        assert_eq!(stack.len(), 3);
+
+        assert_eq!(
+            stack.pop_n(2).unwrap().as_slice(),
+            &[U256::from(3), U256::from(2)]
+        );
+        assert_eq!(stack.as_slice(), &[U256::from(1)]);
+        assert!(stack.require(1).is_ok());
+    }
+
+    #[test]
+    fn dup_and_swap_index_from_the_top() {
+        let mut stack = Stack::default();
+        for i in 1..=3u8 {
+            stack.push(U256::from(i)).unwrap();
+        }
+
+        stack.dup(3).unwrap();
+        assert_eq!(stack.as_slice(), [1, 2, 3, 1].map(U256::from));
+        stack.swap(2).unwrap();
+        assert_eq!(stack.as_slice(), [1, 1, 3, 2].map(U256::from));
+
+        assert_eq!(
+            stack.dup(5),
+            Err(EvmErrors::StackUnderflow {
+                required: 5,
+                present: 4
+            })
+        );
+        assert_eq!(
+            stack.swap(4),
+            Err(EvmErrors::StackUnderflow {
+                required: 5,
+                present: 4
+            })
+        );
+        assert_eq!(stack.len(), 4);
+    }
+}
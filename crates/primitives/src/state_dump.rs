@@ -0,0 +1,158 @@
+//! State snapshots: `EvmStorage::dump_state` / `EvmStorage::load_state`.
+//!
+//! The file is gzip-compressed JSON, `{"accounts": {address: {nonce, balance, code, storage}}}`. The format is this
+//! crate's own: storage keys and values are minimal hex quantities and there are no block fields, so it can't be
+//! exchanged with anvil's `--dump-state` / `--load-state` files.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::Path,
+};
+
+use alloy::primitives::{Address, Bytes, U256};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::EvmErrors,
+    evm_types::{EvmAccount, EvmStorage},
+};
+
+/// One account in a snapshot.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SerializableAccount {
+    pub nonce: u64,
+    pub balance: U256,
+    pub code: Bytes,
+    pub storage: BTreeMap<U256, U256>,
+}
+
+/// Whole-state snapshot. Accounts are keyed in address order so dumps are byte-for-byte reproducible.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SerializableState {
+    pub accounts: BTreeMap<Address, SerializableAccount>,
+}
+
+/// Fails with `NonceTooLarge` for an account whose nonce doesn't fit in a `u64`, rather than truncating it.
+impl TryFrom<&EvmStorage> for SerializableState {
+    type Error = EvmErrors;
+
+    fn try_from(storage: &EvmStorage) -> Result<Self, Self::Error> {
+        let accounts = storage
+            .data
+            .iter()
+            .map(|(address, account)| {
+                let record = SerializableAccount {
+                    nonce: account
+                        .info
+                        .nonce
+                        .try_into()
+                        .map_err(|_| EvmErrors::NonceTooLarge { address: *address })?,
+                    balance: account.info.balance,
+                    code: Bytes::copy_from_slice(storage.code(*address).as_slice()),
+                    storage: account.storage.iter().map(|(k, v)| (*k, *v)).collect(),
+                };
+                Ok((*address, record))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(SerializableState { accounts })
+    }
+}
+
+impl From<SerializableState> for EvmStorage {
+    fn from(state: SerializableState) -> Self {
+        let mut storage = EvmStorage::default();
+        for (address, record) in state.accounts {
+            let mut account = EvmAccount::default();
+            account.info.nonce = U256::from(record.nonce);
+            account.info.balance = record.balance;
+            account.info.code_hash = storage.insert_code(record.code.to_vec());
+            account.storage = record.storage.into_iter().collect();
+            storage.data.insert(address, account);
+        }
+        storage
+    }
+}
+
+impl EvmStorage {
+    /// Write every account, its code and storage to `path` as a gzip-compressed snapshot.
+    /// A nonce over `u64::MAX` fails with `InvalidData` before anything is written.
+    pub fn dump_state(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let state = SerializableState::try_from(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{err:?}")))?;
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        serde_json::to_writer(&mut encoder, &state)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    /// Read a snapshot written by `dump_state`.
+    pub fn load_state(path: impl AsRef<Path>) -> io::Result<EvmStorage> {
+        let decoder = GzDecoder::new(BufReader::new(File::open(path)?));
+        let state: SerializableState = serde_json::from_reader(decoder)?;
+        Ok(state.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dump_and_load_round_trip() {
+        let mut storage = EvmStorage::default();
+        let contract = Address::left_padding_from(&[0xc0]);
+        let user = Address::left_padding_from(&[0x01]);
+        storage.set_code(contract, vec![0x60, 0x01, 0x00]);
+        storage.s_store(contract, U256::from(1), U256::from(42));
+        storage.data.entry(user).or_default().info.balance = U256::from(1_000);
+        storage.data.entry(user).or_default().info.nonce = U256::from(3);
+
+        let path =
+            std::env::temp_dir().join(format!("eth_vm_state_{}.json.gz", std::process::id()));
+        storage.dump_state(&path).unwrap();
+        let loaded = EvmStorage::load_state(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            SerializableState::try_from(&loaded),
+            SerializableState::try_from(&storage)
+        );
+        assert_eq!(loaded.code(contract).as_slice(), &[0x60, 0x01, 0x00]);
+        assert_eq!(loaded.data[&user].info.nonce, U256::from(3));
+    }
+
+    #[test]
+    fn snapshot_account_layout() {
+        let mut storage = EvmStorage::default();
+        storage.s_store(Address::ZERO, U256::from(1), U256::from(2));
+
+        let json = serde_json::to_value(SerializableState::try_from(&storage).unwrap()).unwrap();
+        let account = &json["accounts"]["0x0000000000000000000000000000000000000000"];
+
+        assert_eq!(account["nonce"], 0);
+        assert_eq!(account["code"], "0x");
+        assert_eq!(account["storage"]["0x1"], "0x2");
+    }
+
+    #[test]
+    fn nonce_over_u64_is_rejected_not_truncated() {
+        let mut storage = EvmStorage::default();
+        let user = Address::left_padding_from(&[0x01]);
+        storage.data.entry(user).or_default().info.nonce = U256::from(u64::MAX) + U256::ONE;
+
+        assert_eq!(
+            SerializableState::try_from(&storage),
+            Err(EvmErrors::NonceTooLarge { address: user })
+        );
+        let path =
+            std::env::temp_dir().join(format!("eth_vm_state_nonce_{}.json.gz", std::process::id()));
+        let err = storage.dump_state(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(!path.exists());
+    }
+}
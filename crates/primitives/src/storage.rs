@@ -1,28 +1,538 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use alloy::primitives::{Address, U256};
+use alloy::primitives::{Address, B256, KECCAK256_EMPTY, U256, keccak256};
 
-use crate::evm_types::{EvmAccount, EvmStorage};
+use crate::{
+    bytecode::Bytecode,
+    evm_types::{AccountDiff, EvmAccount, EvmStorage, StateDiff, StateSize, StorageRange},
+};
 
-impl EvmStorage {
-    pub fn default() -> Self {
-        EvmStorage {
-            data: HashMap::new(),
-        }
+static EMPTY_CODE: Bytecode = Bytecode::new(Vec::new());
+
+/// Rough per-entry costs used by `StateSize::estimated_bytes`.
+const ACCOUNT_BYTES: usize = size_of::<Address>() + size_of::<EvmAccount>();
+const SLOT_BYTES: usize = 2 * size_of::<U256>();
+
+impl StateSize {
+    /// Approximate heap footprint in bytes: accounts and slots at their entry size plus raw code length.
+    pub fn estimated_bytes(&self) -> usize {
+        self.accounts * ACCOUNT_BYTES + self.slots * SLOT_BYTES + self.code_bytes
     }
+}
 
+impl EvmStorage {
+    /// Value of slot `key` of `address`. Missing accounts and slots read as zero.
     pub fn s_load(&mut self, address: Address, key: U256) -> U256 {
+        self.touched.insert(address);
         self.data
             .get(&address)
-            .and_then(|evm_account: &EvmAccount| evm_account.word.get(&key).copied())
-            .unwrap()
+            .and_then(|evm_account: &EvmAccount| evm_account.storage.get(&key).copied())
+            .unwrap_or_default()
     }
 
+    /// Write slot `key` of `address`, creating the account if needed. The first write to a slot since
+    /// `clear_original_values()` remembers its previous value as the original value.
     pub fn s_store(&mut self, address: Address, key: U256, value: U256) {
-        self.data
+        self.touched.insert(address);
+        let account = self.data.entry(address).or_default();
+        let present = account.storage.get(&key).copied().unwrap_or_default();
+        account.storage.insert(key, value);
+        self.original_values
             .entry(address)
-            .or_insert_with(EvmAccount::default)
-            .word
-            .insert(key, value);
+            .or_default()
+            .entry(key)
+            .or_insert(present);
+    }
+
+    /// Value slot `key` of `address` had before the current transaction first wrote it; the current value if it
+    /// hasn't been written.
+    pub fn original_value(&self, address: Address, key: U256) -> U256 {
+        self.original_values
+            .get(&address)
+            .and_then(|slots| slots.get(&key))
+            .or_else(|| self.data.get(&address)?.storage.get(&key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Forget recorded original values, making the current state the original one. Call at transaction start.
+    pub fn clear_original_values(&mut self) {
+        self.original_values.clear();
+    }
+
+    /// Store `code` in the code-by-hash store and return its hash.
+    /// Identical code is only kept once no matter how many accounts point at it.
+    pub fn insert_code(&mut self, code: Vec<u8>) -> B256 {
+        if code.is_empty() {
+            return KECCAK256_EMPTY;
+        }
+
+        let code_hash = keccak256(&code);
+        self.codes.entry(code_hash).or_insert(Bytecode::new(code));
+        code_hash
+    }
+
+    /// Set the code of `address`, creating the account if needed.
+    pub fn set_code(&mut self, address: Address, code: Vec<u8>) {
+        let code_hash = self.insert_code(code);
+        self.touched.insert(address);
+        self.data.entry(address).or_default().info.code_hash = code_hash;
+    }
+
+    /// Code of `address` as execution sees it: an `etch` override if one is installed, otherwise the account's
+    /// own code. Missing accounts and accounts without code return empty code.
+    pub fn code(&self, address: Address) -> &Bytecode {
+        if let Some(code) = self.code_overrides.get(&address) {
+            return code;
+        }
+        self.data
+            .get(&address)
+            .and_then(|evm_account| self.codes.get(&evm_account.info.code_hash))
+            .unwrap_or(&EMPTY_CODE)
+    }
+
+    /// Hash of `code(address)`, so EXTCODEHASH agrees with what EXTCODECOPY and CALL observe under an override.
+    pub fn code_hash(&self, address: Address) -> B256 {
+        match self.code_overrides.get(&address) {
+            Some(code) if code.is_empty() => KECCAK256_EMPTY,
+            Some(code) => keccak256(code.as_slice()),
+            None => self
+                .data
+                .get(&address)
+                .map_or(KECCAK256_EMPTY, |account| account.info.code_hash),
+        }
+    }
+
+    /// Replace the code at `address` for execution only ("etch"), whether or not the account exists or has code.
+    /// The account's `code_hash` is untouched; `clear_etch` restores the original code.
+    pub fn etch(&mut self, address: Address, code: Vec<u8>) {
+        self.touched.insert(address);
+        self.code_overrides.insert(address, Bytecode::new(code));
+    }
+
+    /// Remove the override installed by `etch`, returning it.
+    pub fn clear_etch(&mut self, address: Address) -> Option<Bytecode> {
+        self.code_overrides.remove(&address)
+    }
+
+    /// Iterate accounts in ascending address order.
+    /// `data` is a `HashMap`, so this (not `data.iter()`) is the order to expose outside the crate.
+    pub fn iter_accounts(&self) -> impl Iterator<Item = (&Address, &EvmAccount)> {
+        let mut accounts: Vec<(&Address, &EvmAccount)> = self.data.iter().collect();
+        accounts.sort_unstable_by_key(|(address, _)| **address);
+        accounts.into_iter()
+    }
+
+    /// Iterate the storage slots of `address` in ascending key order.
+    /// Missing accounts yield nothing.
+    pub fn iter_account(&self, address: Address) -> impl Iterator<Item = (U256, U256)> {
+        let mut slots: Vec<(U256, U256)> = self
+            .data
+            .get(&address)
+            .map(|account| account.storage.iter().map(|(k, v)| (*k, *v)).collect())
+            .unwrap_or_default();
+        slots.sort_unstable_by_key(|(key, _)| *key);
+        slots.into_iter()
+    }
+
+    /// Page through the storage of `address` (the shape of `debug_storageRangeAt`):
+    /// returns up to `limit` slots with key `>= start_key`, plus the key to resume from.
+    pub fn storage_range_at(
+        &self,
+        address: Address,
+        start_key: U256,
+        limit: usize,
+    ) -> StorageRange {
+        let mut slots = self
+            .iter_account(address)
+            .skip_while(|(key, _)| *key < start_key);
+        let page: Vec<(U256, U256)> = slots.by_ref().take(limit).collect();
+
+        StorageRange {
+            slots: page,
+            next_key: slots.next().map(|(key, _)| key),
+        }
+    }
+
+    /// Compute what changed going from `self` (before) to `after`.
+    /// Missing accounts compare as default accounts and missing slots as zero.
+    pub fn diff(&self, after: &EvmStorage) -> StateDiff {
+        let empty = EvmAccount::default();
+        let addresses: HashSet<&Address> = self.data.keys().chain(after.data.keys()).collect();
+
+        let mut diff = StateDiff::default();
+        for address in addresses {
+            let before_account = self.data.get(address).unwrap_or(&empty);
+            let after_account = after.data.get(address).unwrap_or(&empty);
+
+            let mut account_diff = AccountDiff::default();
+            if before_account.info != after_account.info {
+                account_diff.info = Some((before_account.info.clone(), after_account.info.clone()));
+            }
+
+            let keys: HashSet<&U256> = before_account
+                .storage
+                .keys()
+                .chain(after_account.storage.keys())
+                .collect();
+            for key in keys {
+                let before_value = before_account.storage.get(key).copied().unwrap_or_default();
+                let after_value = after_account.storage.get(key).copied().unwrap_or_default();
+                if before_value != after_value {
+                    account_diff
+                        .storage
+                        .insert(*key, (before_value, after_value));
+                }
+            }
+
+            if account_diff != AccountDiff::default() {
+                diff.accounts.insert(*address, account_diff);
+            }
+        }
+        diff
+    }
+
+    /// Whether `address` is empty in the EIP-161 sense: missing, or with no nonce, no balance and no code. An `etch`
+    /// override counts as code.
+    pub fn is_empty_account(&self, address: Address) -> bool {
+        !self.code_overrides.contains_key(&address)
+            && self.data.get(&address).is_none_or(|account| {
+                account.info.nonce.is_zero()
+                    && account.info.balance.is_zero()
+                    && account.info.code_hash == KECCAK256_EMPTY
+            })
+    }
+
+    /// Mark `address` as used so `prune_untouched` keeps it.
+    pub fn touch(&mut self, address: Address) {
+        self.touched.insert(address);
+    }
+
+    /// Forget which accounts were touched, starting a new pruning epoch.
+    pub fn clear_touched(&mut self) {
+        self.touched.clear();
+    }
+
+    /// Count accounts, storage slots and code bytes currently held in memory.
+    pub fn size(&self) -> StateSize {
+        StateSize {
+            accounts: self.data.len(),
+            slots: self
+                .data
+                .values()
+                .map(|account| account.storage.len())
+                .sum(),
+            code_bytes: self.codes.values().map(Bytecode::len).sum(),
+        }
+    }
+
+    /// Drop untouched accounts until the estimated state size fits in `budget_bytes`,
+    /// then release code no remaining account points at. Touched accounts are never removed.
+    /// Returns the number of accounts pruned.
+    pub fn prune_untouched(&mut self, budget_bytes: usize) -> usize {
+        let mut size = self.size();
+        if size.estimated_bytes() <= budget_bytes {
+            return 0;
+        }
+
+        let mut candidates: Vec<Address> = self
+            .data
+            .keys()
+            .filter(|address| !self.touched.contains(*address))
+            .copied()
+            .collect();
+        candidates.sort();
+
+        // Accounts pointing at each code; its bytes are freed with the last of them.
+        let mut code_refs: HashMap<B256, usize> = HashMap::new();
+        for account in self.data.values() {
+            *code_refs.entry(account.info.code_hash).or_default() += 1;
+        }
+
+        let mut pruned = 0;
+        for address in candidates {
+            if size.estimated_bytes() <= budget_bytes {
+                break;
+            }
+            let account = self.data.remove(&address).unwrap();
+            size.accounts -= 1;
+            size.slots -= account.storage.len();
+            let code_hash = account.info.code_hash;
+            if let Some(refs) = code_refs.get_mut(&code_hash) {
+                *refs -= 1;
+                if *refs == 0 {
+                    size.code_bytes -= self.codes.get(&code_hash).map_or(0, Bytecode::len);
+                }
+            }
+            pruned += 1;
+        }
+
+        let live_code: HashSet<B256> = self
+            .data
+            .values()
+            .map(|account| account.info.code_hash)
+            .collect();
+        self.codes
+            .retain(|code_hash, _| live_code.contains(code_hash));
+
+        pruned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_dump::SerializableState;
+
+    #[test]
+    fn identical_code_is_stored_once() {
+        let mut storage = EvmStorage::default();
+        let code = vec![0x60, 0x01, 0x00];
+
+        for i in 1..=1000u16 {
+            storage.set_code(Address::left_padding_from(&i.to_be_bytes()), code.clone());
+        }
+
+        assert_eq!(storage.data.len(), 1000);
+        assert_eq!(storage.codes.len(), 1);
+        assert_eq!(
+            storage.code(Address::left_padding_from(&[7])).as_slice(),
+            code.as_slice()
+        );
+    }
+
+    #[test]
+    fn original_value_is_the_value_before_the_first_write() {
+        let mut storage = EvmStorage::default();
+        let key = U256::from(1);
+        storage.s_store(Address::ZERO, key, U256::from(5));
+        storage.clear_original_values();
+
+        assert_eq!(storage.original_value(Address::ZERO, key), U256::from(5));
+        storage.s_store(Address::ZERO, key, U256::from(6));
+        storage.s_store(Address::ZERO, key, U256::from(7));
+        assert_eq!(storage.original_value(Address::ZERO, key), U256::from(5));
+        assert_eq!(
+            storage.original_value(Address::ZERO, U256::from(2)),
+            U256::ZERO
+        );
+
+        storage.clear_original_values();
+        assert_eq!(storage.original_value(Address::ZERO, key), U256::from(7));
+    }
+
+    #[test]
+    fn accounts_without_code_use_empty_hash() {
+        let mut storage = EvmStorage::default();
+        storage.s_store(Address::ZERO, U256::from(1), U256::from(2));
+
+        assert_eq!(storage.data[&Address::ZERO].info.code_hash, KECCAK256_EMPTY);
+        assert!(storage.code(Address::ZERO).is_empty());
+        assert!(storage.code(Address::left_padding_from(&[9])).is_empty());
+    }
+
+    #[test]
+    fn etch_shadows_code_without_changing_state() {
+        let contract = Address::left_padding_from(&[1]);
+        let mut storage = EvmStorage::default();
+        storage.set_code(contract, vec![0x00]);
+        let before = storage.clone();
+
+        storage.etch(contract, vec![0x60, 0x01, 0x00]);
+        assert_eq!(storage.code(contract).as_slice(), &[0x60, 0x01, 0x00]);
+        assert_eq!(storage.code_hash(contract), keccak256([0x60, 0x01, 0x00]));
+        assert!(before.diff(&storage).is_empty());
+
+        // Etching an address with no account works too.
+        storage.etch(Address::ZERO, vec![0xfe]);
+        assert_eq!(storage.code(Address::ZERO).as_slice(), &[0xfe]);
+
+        assert!(storage.clear_etch(contract).is_some());
+        assert_eq!(storage.code(contract).as_slice(), &[0x00]);
+        assert_eq!(storage.code_hash(contract), keccak256([0x00]));
+    }
+
+    #[test]
+    fn iter_account_is_sorted_by_key() {
+        let mut storage = EvmStorage::default();
+        for key in [7u64, 1, 300, 42, 5] {
+            storage.s_store(Address::ZERO, U256::from(key), U256::from(key * 2));
+        }
+
+        let keys: Vec<U256> = storage
+            .iter_account(Address::ZERO)
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(keys, [1u64, 5, 7, 42, 300].map(U256::from));
+        assert_eq!(
+            storage
+                .iter_account(Address::left_padding_from(&[1]))
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn externally_visible_orders_are_sorted() {
+        let mut before = EvmStorage::default();
+        let mut after = EvmStorage::default();
+        for byte in [0x90u8, 0x03, 0xff, 0x41, 0x10, 0x7a] {
+            let address = Address::repeat_byte(byte);
+            before.s_store(address, U256::from(byte), U256::ONE);
+            for slot in [200u64, 3, 77] {
+                after.s_store(address, U256::from(slot), U256::from(byte));
+            }
+        }
+
+        let sorted = [0x03u8, 0x10, 0x41, 0x7a, 0x90, 0xff].map(Address::repeat_byte);
+        let iterated: Vec<Address> = after.iter_accounts().map(|(a, _)| *a).collect();
+        assert_eq!(iterated, sorted);
+
+        let diff = before.diff(&after);
+        assert!(diff.accounts.keys().copied().eq(sorted));
+        for account in diff.accounts.values() {
+            assert!(account.storage.keys().is_sorted());
+        }
+
+        let dump = serde_json::to_string(&SerializableState::try_from(&after).unwrap()).unwrap();
+        let positions: Vec<usize> = sorted
+            .iter()
+            .map(|address| dump.find(&address.to_string().to_lowercase()).unwrap())
+            .collect();
+        assert!(positions.is_sorted());
+    }
+
+    #[test]
+    fn storage_range_at_pages_through_slots() {
+        let mut storage = EvmStorage::default();
+        for key in 0u64..5 {
+            storage.s_store(Address::ZERO, U256::from(key), U256::from(key + 100));
+        }
+
+        let first = storage.storage_range_at(Address::ZERO, U256::ZERO, 2);
+        assert_eq!(
+            first.slots,
+            vec![
+                (U256::from(0), U256::from(100)),
+                (U256::from(1), U256::from(101))
+            ]
+        );
+        assert_eq!(first.next_key, Some(U256::from(2)));
+
+        let last = storage.storage_range_at(Address::ZERO, U256::from(3), 10);
+        assert_eq!(last.slots.len(), 2);
+        assert_eq!(last.next_key, None);
+
+        let past_end = storage.storage_range_at(Address::ZERO, U256::from(9), 10);
+        assert_eq!(past_end, StorageRange::default());
+    }
+
+    #[test]
+    fn diff_reports_changed_slots_and_info() {
+        let contract = Address::left_padding_from(&[1]);
+        let mut before = EvmStorage::default();
+        before.s_store(contract, U256::from(1), U256::from(10));
+        before.s_store(contract, U256::from(2), U256::from(20));
+
+        let mut after = before.clone();
+        after.s_store(contract, U256::from(1), U256::from(11));
+        after.s_store(contract, U256::from(3), U256::from(30));
+        after.data.get_mut(&contract).unwrap().info.balance = U256::from(5);
+
+        let diff = before.diff(&after);
+        let account = &diff.accounts[&contract];
+
+        assert_eq!(diff.accounts.len(), 1);
+        assert_eq!(
+            account.info.as_ref().map(|(b, a)| (b.balance, a.balance)),
+            Some((U256::ZERO, U256::from(5)))
+        );
+        assert_eq!(
+            account
+                .storage
+                .iter()
+                .map(|(k, v)| (*k, *v))
+                .collect::<Vec<_>>(),
+            vec![
+                (U256::from(1), (U256::from(10), U256::from(11))),
+                (U256::from(3), (U256::ZERO, U256::from(30))),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_of_identical_states_is_empty() {
+        let mut storage = EvmStorage::default();
+        storage.s_store(Address::ZERO, U256::from(1), U256::from(1));
+
+        assert!(storage.diff(&storage.clone()).is_empty());
+        // Writing zero to an absent slot is not a change.
+        let mut after = storage.clone();
+        after.s_store(Address::ZERO, U256::from(9), U256::ZERO);
+        assert!(storage.diff(&after).is_empty());
+    }
+
+    #[test]
+    fn size_counts_accounts_slots_and_code() {
+        let mut storage = EvmStorage::default();
+        storage.s_store(Address::ZERO, U256::from(1), U256::from(1));
+        storage.s_store(Address::ZERO, U256::from(2), U256::from(2));
+        storage.set_code(Address::left_padding_from(&[1]), vec![0x00; 10]);
+
+        assert_eq!(
+            storage.size(),
+            StateSize {
+                accounts: 2,
+                slots: 2,
+                code_bytes: 10,
+            }
+        );
+    }
+
+    #[test]
+    fn prune_keeps_touched_accounts_and_drops_orphan_code() {
+        let mut storage = EvmStorage::default();
+        let kept = Address::left_padding_from(&[1]);
+        let cold = Address::left_padding_from(&[2]);
+        storage.set_code(cold, vec![0x01; 64]);
+        storage.clear_touched();
+        storage.s_store(kept, U256::ZERO, U256::ONE);
+
+        assert_eq!(storage.prune_untouched(0), 1);
+        assert!(storage.data.contains_key(&kept));
+        assert!(!storage.data.contains_key(&cold));
+        assert!(storage.codes.is_empty());
+    }
+
+    #[test]
+    fn prune_stops_once_freed_code_fits_the_budget() {
+        let mut storage = EvmStorage::default();
+        let (first, second, third) = (
+            Address::left_padding_from(&[1]),
+            Address::left_padding_from(&[2]),
+            Address::left_padding_from(&[3]),
+        );
+        // The shared code outweighs all three accounts.
+        storage.set_code(first, vec![0x01; 10_000]);
+        storage.set_code(second, vec![0x01; 10_000]);
+        storage.s_store(third, U256::ZERO, U256::ONE);
+        storage.clear_touched();
+        let budget = 3 * ACCOUNT_BYTES;
+
+        // Dropping `first` frees nothing while `second` still uses the code; dropping `second` does.
+        assert_eq!(storage.prune_untouched(budget), 2);
+        assert!(storage.data.contains_key(&third));
+        assert!(storage.codes.is_empty());
+        assert!(storage.size().estimated_bytes() <= budget);
+    }
+
+    #[test]
+    fn prune_is_noop_within_budget() {
+        let mut storage = EvmStorage::default();
+        storage.set_code(Address::ZERO, vec![0x01; 64]);
+        storage.clear_touched();
+
+        assert_eq!(storage.prune_untouched(usize::MAX), 0);
+        assert_eq!(storage.size().accounts, 1);
     }
 }
@@ -0,0 +1,109 @@
+use alloy::primitives::{Address, B256, U256};
+
+/// Copy `len` bytes of `data` starting at `offset`, zero-filling whatever lies past the end of `data`.
+///
+/// This is the read rule shared by CODECOPY, EXTCODECOPY and CALLDATACOPY: an `offset` beyond the data yields
+/// only zeros, `len == 0` yields an empty buffer, and `offset + len` never overflows.
+pub fn slice_padded(data: &[u8], offset: usize, len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+    if offset < data.len() {
+        let end = offset.saturating_add(len).min(data.len());
+        out[..end - offset].copy_from_slice(&data[offset..end]);
+    }
+    out
+}
+
+/// Read the 32-byte big-endian word at `offset`, zero-filling past the end of `data` (CALLDATALOAD semantics).
+pub fn load_word_padded(data: &[u8], offset: usize) -> U256 {
+    let mut word = [0u8; 32];
+    if offset < data.len() {
+        let end = offset.saturating_add(32).min(data.len());
+        word[..end - offset].copy_from_slice(&data[offset..end]);
+    }
+    U256::from_be_bytes(word)
+}
+
+/// The low 20 bytes of a stack word, as the EVM interprets address operands; the high 12 bytes are ignored.
+pub fn word_to_address(word: U256) -> Address {
+    Address::from_word(B256::from(word))
+}
+
+/// An address as a stack word: its 20 bytes right-aligned, i.e. left-padded with 12 zero bytes.
+pub fn address_to_word(address: Address) -> U256 {
+    U256::from_be_bytes(address.into_word().0)
+}
+
+/// Read `bytes` as a big-endian integer left-padded to 32 bytes. Inputs longer than 32 bytes keep their last
+/// (least significant) 32 bytes, as a stack word would.
+pub fn be_slice_padded(bytes: &[u8]) -> U256 {
+    U256::from_be_slice(&bytes[bytes.len().saturating_sub(32)..])
+}
+
+/// Byte `index` of `word` counting from the most significant end (BYTE semantics); 0 for indices of 32 or more.
+pub fn be_byte(word: U256, index: U256) -> u8 {
+    if index < U256::from(32) {
+        word.to_be_bytes::<32>()[index.to::<usize>()]
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_word_padded_zero_fills_the_tail() {
+        let data = [0xaa, 0xbb];
+
+        assert_eq!(load_word_padded(&data, 0), U256::from(0xaabbu64) << 240);
+        assert_eq!(load_word_padded(&data, 1), U256::from(0xbbu64) << 248);
+        assert_eq!(load_word_padded(&data, 2), U256::ZERO);
+        assert_eq!(load_word_padded(&data, usize::MAX), U256::ZERO);
+    }
+
+    #[test]
+    fn load_word_padded_reads_full_words() {
+        let data: Vec<u8> = (1..=40).collect();
+        let expected = U256::from_be_slice(&data[4..36]);
+
+        assert_eq!(load_word_padded(&data, 4), expected);
+    }
+
+    #[test]
+    fn addresses_round_trip_through_words() {
+        let address = Address::from_slice(&(1..=20).collect::<Vec<u8>>());
+        let word = address_to_word(address);
+
+        assert_eq!(word.to_be_bytes::<32>()[..12], [0u8; 12]);
+        assert_eq!(word.to_be_bytes::<32>()[12..], *address.as_slice());
+        assert_eq!(word_to_address(word), address);
+        // The high 12 bytes of an operand don't select a different account.
+        assert_eq!(word_to_address(word | (U256::MAX << 160)), address);
+        assert_eq!(address_to_word(Address::ZERO), U256::ZERO);
+    }
+
+    #[test]
+    fn be_slice_padded_is_big_endian_and_left_padded() {
+        assert_eq!(be_slice_padded(&[]), U256::ZERO);
+        assert_eq!(be_slice_padded(&[0x01, 0x02]), U256::from(0x0102));
+        assert_eq!(be_slice_padded(&[0xff; 32]), U256::MAX);
+
+        let mut long = vec![0xee; 8];
+        long.extend(U256::from(7).to_be_bytes::<32>());
+        assert_eq!(be_slice_padded(&long), U256::from(7));
+    }
+
+    #[test]
+    fn be_byte_counts_from_the_most_significant_end() {
+        let word = be_slice_padded(&(0..32).collect::<Vec<u8>>());
+
+        for index in 0..32u8 {
+            assert_eq!(be_byte(word, U256::from(index)), index);
+        }
+        assert_eq!(be_byte(U256::from(0xab), U256::from(31)), 0xab);
+        assert_eq!(be_byte(U256::from(0xab), U256::ZERO), 0);
+        assert_eq!(be_byte(U256::MAX, U256::from(32)), 0);
+        assert_eq!(be_byte(U256::MAX, U256::MAX), 0);
+    }
+}